@@ -0,0 +1,575 @@
+//! Per-user package manager and build caches.
+//!
+//! Most disk space is often not taken by project build output but by
+//! caches each toolchain keeps per user and never trims: cargo's registry,
+//! npm's `_cacache`, the local Maven repository, Gradle's caches, pip's
+//! cache and the Go build cache. [`locate`] finds the ones present,
+//! [`measure`] sizes them in parallel, and
+//! [`GlobalCache::stale_entries`] lists what has not been used for a while
+//! in the caches whose layout allows removing single entries:
+//!
+//! - cargo: a crate version's `.crate` archive and its extracted sources,
+//!   whose `.cargo-ok` cargo reads on every build
+//! - Maven: an artifact version directory (the one holding the `.pom`)
+//! - Gradle: the caches of one Gradle version, such as `caches/8.5`
+//! - Go: a cache entry, whose mtime the go command refreshes when using it
+//!
+//! Last use is the newer of the access and modification times of files, so
+//! on filesystems mounted with `noatime` it falls back to when an entry was
+//! written. npm's and pip's caches keep no usage records and can only be
+//! removed whole, with [`GlobalCache::remove`].
+
+use crate::platform::{measure_dir, remove_dir_all};
+use crate::sweep::{remove_paths, serialize_paths};
+use anyhow::Result;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Which tool a cache belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum CacheKind {
+    /// `$CARGO_HOME/registry`
+    Cargo,
+    /// `~/.npm/_cacache`
+    Npm,
+    /// `~/.m2/repository`
+    Maven,
+    /// `$GRADLE_USER_HOME/caches`
+    Gradle,
+    /// pip's HTTP and wheel cache
+    Pip,
+    /// `$GOCACHE`
+    Go,
+}
+
+impl CacheKind {
+    /// Every kind of cache, in the order they are reported
+    pub fn all() -> Vec<CacheKind> {
+        vec![
+            CacheKind::Cargo,
+            CacheKind::Npm,
+            CacheKind::Maven,
+            CacheKind::Gradle,
+            CacheKind::Pip,
+            CacheKind::Go,
+        ]
+    }
+
+    /// Short identifier used on the command line
+    pub fn key(&self) -> &'static str {
+        match self {
+            CacheKind::Cargo => "cargo",
+            CacheKind::Npm => "npm",
+            CacheKind::Maven => "maven",
+            CacheKind::Gradle => "gradle",
+            CacheKind::Pip => "pip",
+            CacheKind::Go => "go",
+        }
+    }
+
+    /// Human-readable name
+    pub fn name(&self) -> &'static str {
+        match self {
+            CacheKind::Cargo => "Cargo registry",
+            CacheKind::Npm => "npm cache",
+            CacheKind::Maven => "Maven repository",
+            CacheKind::Gradle => "Gradle caches",
+            CacheKind::Pip => "pip cache",
+            CacheKind::Go => "Go build cache",
+        }
+    }
+
+    /// What one of its [entries](CacheEntry) is, in the plural; `None` if
+    /// the cache can only be removed whole
+    pub fn entries_name(&self) -> Option<&'static str> {
+        match self {
+            CacheKind::Cargo => Some("crate versions"),
+            CacheKind::Maven => Some("artifact versions"),
+            CacheKind::Gradle => Some("Gradle versions"),
+            CacheKind::Go => Some("entries"),
+            CacheKind::Npm | CacheKind::Pip => None,
+        }
+    }
+
+    /// Files or directories one of these caches always has
+    fn markers(&self) -> &'static [&'static str] {
+        match self {
+            CacheKind::Cargo => &["cache", "index", "src"],
+            CacheKind::Npm => &["index-v5", "content-v2"],
+            CacheKind::Pip => &["http", "http-v2", "wheels", "selfcheck"],
+            CacheKind::Go => &["README", "trim.txt"],
+            // The directory names are specific enough
+            CacheKind::Maven | CacheKind::Gradle => &[],
+        }
+    }
+}
+
+impl fmt::Display for CacheKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.key())
+    }
+}
+
+/// The parts of the environment that decide where the caches are
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct CacheEnv {
+    /// The user's home directory
+    pub home: Option<PathBuf>,
+    /// The platform's per-user cache directory: `$XDG_CACHE_HOME` or
+    /// `~/.cache`, `~/Library/Caches` on macOS, `%LOCALAPPDATA%` on Windows
+    pub cache_home: Option<PathBuf>,
+    /// `$CARGO_HOME`
+    pub cargo_home: Option<PathBuf>,
+    /// `$npm_config_cache`
+    pub npm_cache: Option<PathBuf>,
+    /// `$GRADLE_USER_HOME`
+    pub gradle_home: Option<PathBuf>,
+    /// `$PIP_CACHE_DIR`
+    pub pip_cache: Option<PathBuf>,
+    /// `$GOCACHE`
+    pub go_cache: Option<PathBuf>,
+}
+
+impl CacheEnv {
+    /// Read the environment of this process
+    pub fn from_env() -> Self {
+        let var = |name: &str| {
+            std::env::var_os(name)
+                .filter(|v| !v.is_empty())
+                .map(PathBuf::from)
+        };
+        let home = var(if cfg!(windows) { "USERPROFILE" } else { "HOME" });
+        let cache_home = var("XDG_CACHE_HOME").or_else(|| {
+            if cfg!(windows) {
+                var("LOCALAPPDATA")
+            } else if cfg!(target_os = "macos") {
+                home.as_ref().map(|h| h.join("Library/Caches"))
+            } else {
+                home.as_ref().map(|h| h.join(".cache"))
+            }
+        });
+        Self {
+            cargo_home: var("CARGO_HOME"),
+            npm_cache: var("npm_config_cache"),
+            gradle_home: var("GRADLE_USER_HOME"),
+            pip_cache: var("PIP_CACHE_DIR"),
+            go_cache: var("GOCACHE").filter(|dir| dir != Path::new("off")),
+            home,
+            cache_home,
+        }
+    }
+
+    /// Environment with only a home directory, everything else at its
+    /// default below it
+    pub fn with_home(home: impl Into<PathBuf>) -> Self {
+        let home = home.into();
+        Self {
+            cache_home: Some(home.join(".cache")),
+            home: Some(home),
+            ..Self::default()
+        }
+    }
+
+    /// Where the cache of `kind` would be
+    pub fn path(&self, kind: CacheKind) -> Option<PathBuf> {
+        let home = self.home.as_deref();
+        let cache_home = self.cache_home.as_deref();
+        match kind {
+            CacheKind::Cargo => self
+                .cargo_home
+                .clone()
+                .or_else(|| Some(home?.join(".cargo")))
+                .map(|dir| dir.join("registry")),
+            CacheKind::Npm => self
+                .npm_cache
+                .clone()
+                .or_else(|| match cfg!(windows) {
+                    true => Some(cache_home?.join("npm-cache")),
+                    false => Some(home?.join(".npm")),
+                })
+                .map(|dir| dir.join("_cacache")),
+            CacheKind::Maven => Some(home?.join(".m2").join("repository")),
+            CacheKind::Gradle => self
+                .gradle_home
+                .clone()
+                .or_else(|| Some(home?.join(".gradle")))
+                .map(|dir| dir.join("caches")),
+            CacheKind::Pip => self.pip_cache.clone().or_else(|| match cfg!(windows) {
+                true => Some(cache_home?.join("pip").join("Cache")),
+                false => Some(cache_home?.join("pip")),
+            }),
+            CacheKind::Go => self
+                .go_cache
+                .clone()
+                .or_else(|| Some(cache_home?.join("go-build"))),
+        }
+    }
+}
+
+/// A cache that exists on this machine
+#[derive(Debug, Clone, Serialize)]
+#[non_exhaustive]
+pub struct GlobalCache {
+    /// Which tool it belongs to
+    pub kind: CacheKind,
+    /// Its directory
+    #[serde(with = "crate::report::os_path")]
+    pub path: PathBuf,
+    /// Total size in bytes, once [measured](measure)
+    pub size: u64,
+    /// Number of files, once [measured](measure)
+    pub files: usize,
+}
+
+impl GlobalCache {
+    /// Entries not used since `older_than` before `now`, sized
+    ///
+    /// Always empty for caches that can only be removed whole. Entries whose
+    /// last use cannot be told are kept.
+    pub fn stale_entries(&self, older_than: Duration, now: SystemTime) -> Vec<CacheEntry> {
+        let cutoff = now.checked_sub(older_than).unwrap_or(UNIX_EPOCH);
+        let entries = match self.kind {
+            CacheKind::Cargo => cargo_entries(&self.path),
+            CacheKind::Maven => {
+                let mut entries = Vec::new();
+                maven_entries(&self.path, &self.path, &mut entries);
+                entries
+            }
+            CacheKind::Gradle => gradle_entries(&self.path),
+            CacheKind::Go => go_entries(&self.path),
+            CacheKind::Npm | CacheKind::Pip => Vec::new(),
+        };
+        entries
+            .into_par_iter()
+            .filter(|entry| entry.last_used.is_some_and(|used| used < cutoff))
+            .map(|mut entry| {
+                entry.measure();
+                entry
+            })
+            .collect()
+    }
+
+    /// Delete the whole cache
+    pub fn remove(&self) -> Result<()> {
+        remove_dir_all(&self.path)
+    }
+}
+
+/// Find the caches present for `env`
+///
+/// A directory only counts if it has the layout of the tool's cache. The
+/// caches are not sized yet.
+pub fn locate(env: &CacheEnv) -> Vec<GlobalCache> {
+    CacheKind::all()
+        .into_iter()
+        .filter_map(|kind| {
+            let path = env.path(kind)?;
+            let markers = kind.markers();
+            let recognised = path.is_dir()
+                && (markers.is_empty() || markers.iter().any(|m| path.join(m).exists()));
+            recognised.then_some(GlobalCache {
+                kind,
+                path,
+                size: 0,
+                files: 0,
+            })
+        })
+        .collect()
+}
+
+/// Size the caches in parallel
+pub fn measure(caches: &mut [GlobalCache]) {
+    caches.par_iter_mut().for_each(|cache| {
+        if let Ok(usage) = measure_dir(&cache.path) {
+            cache.size = usage.size;
+            cache.files = usage.files;
+        }
+    });
+}
+
+/// One thing that can be pruned from a cache
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheEntry {
+    /// What it is: `serde-1.0.200`, `org/slf4j/slf4j-api/2.0.9`, `8.5`
+    pub name: String,
+    /// Files and directories removed together
+    #[serde(serialize_with = "serialize_paths")]
+    pub paths: Vec<PathBuf>,
+    /// Total size in bytes
+    pub size: u64,
+    /// Number of files
+    pub files: usize,
+    /// When it was last used, as seconds since the Unix epoch
+    #[serde(with = "crate::report::unix_time")]
+    pub last_used: Option<SystemTime>,
+}
+
+impl CacheEntry {
+    fn new(name: String, paths: Vec<PathBuf>, last_used: Option<SystemTime>) -> Self {
+        Self {
+            name,
+            paths,
+            size: 0,
+            files: 0,
+            last_used,
+        }
+    }
+
+    fn measure(&mut self) {
+        for path in &self.paths {
+            if let Ok(usage) = measure_dir(path) {
+                self.size += usage.size;
+                self.files += usage.files;
+            }
+        }
+    }
+
+    /// Delete everything the entry covers
+    pub fn remove(&self) -> Result<()> {
+        remove_paths(&self.paths)
+    }
+}
+
+/// The newer of the access and modification times of a file; only the
+/// modification time of a directory, since sizing it lists it, which
+/// updates its access time
+fn used(path: &Path) -> Option<SystemTime> {
+    let metadata = fs::symlink_metadata(path).ok()?;
+    if metadata.is_dir() {
+        return metadata.modified().ok();
+    }
+    metadata.accessed().ok().max(metadata.modified().ok())
+}
+
+fn read_dir(dir: &Path) -> impl Iterator<Item = fs::DirEntry> {
+    fs::read_dir(dir).into_iter().flatten().flatten()
+}
+
+/// `cache/<index>/<crate>-<version>.crate` with `src/<index>/<crate>-<version>`
+fn cargo_entries(registry: &Path) -> Vec<CacheEntry> {
+    let mut versions: BTreeMap<(String, String), Vec<PathBuf>> = BTreeMap::new();
+    for (part, suffix, want_dir) in [("cache", ".crate", false), ("src", "", true)] {
+        for index in read_dir(&registry.join(part)) {
+            let index_name = index.file_name().to_string_lossy().into_owned();
+            for entry in read_dir(&index.path()) {
+                if entry.file_type().is_ok_and(|t| t.is_dir()) != want_dir {
+                    continue;
+                }
+                let name = entry.file_name().to_string_lossy().into_owned();
+                if let Some(version) = name.strip_suffix(suffix) {
+                    versions
+                        .entry((index_name.clone(), version.to_string()))
+                        .or_default()
+                        .push(entry.path());
+                }
+            }
+        }
+    }
+    versions
+        .into_iter()
+        .map(|((_, version), paths)| {
+            let last_used = paths
+                .iter()
+                .flat_map(|path| [used(path), used(&path.join(".cargo-ok"))])
+                .max()
+                .flatten();
+            CacheEntry::new(version, paths, last_used)
+        })
+        .collect()
+}
+
+/// Directories holding a `.pom`, last used when any file in them was
+fn maven_entries(repository: &Path, dir: &Path, entries: &mut Vec<CacheEntry>) {
+    let mut files = Vec::new();
+    let mut dirs = Vec::new();
+    for entry in read_dir(dir) {
+        match entry.file_type() {
+            Ok(t) if t.is_dir() => dirs.push(entry.path()),
+            Ok(t) if t.is_file() => files.push(entry.path()),
+            _ => {}
+        }
+    }
+    if files
+        .iter()
+        .any(|f| f.extension().is_some_and(|e| e == "pom"))
+    {
+        let name = dir.strip_prefix(repository).unwrap_or(dir);
+        let last_used = files.iter().filter_map(|f| used(f)).max();
+        entries.push(CacheEntry::new(
+            name.to_string_lossy().replace('\\', "/"),
+            vec![dir.to_path_buf()],
+            last_used,
+        ));
+        return;
+    }
+    for dir in dirs {
+        maven_entries(repository, &dir, entries);
+    }
+}
+
+/// Version directories such as `caches/8.5`, last used when anything in
+/// them changed; Gradle rewrites their lock files on every run
+fn gradle_entries(caches: &Path) -> Vec<CacheEntry> {
+    read_dir(caches)
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if !name.starts_with(|c: char| c.is_ascii_digit()) {
+                return None;
+            }
+            let path = entry.path();
+            let changed = measure_dir(&path).ok().and_then(|u| u.last_modified);
+            let last_used = used(&path).max(changed);
+            Some(CacheEntry::new(name, vec![path], last_used))
+        })
+        .collect()
+}
+
+/// Files in the 256 `00`..`ff` subdirectories
+fn go_entries(cache: &Path) -> Vec<CacheEntry> {
+    let mut entries = Vec::new();
+    for bucket in read_dir(cache) {
+        let bucket_name = bucket.file_name().to_string_lossy().into_owned();
+        if bucket_name.len() != 2 || !bucket_name.chars().all(|c| c.is_ascii_hexdigit()) {
+            continue;
+        }
+        for entry in read_dir(&bucket.path()) {
+            if entry.file_type().is_ok_and(|t| t.is_file()) {
+                let name = format!("{}/{}", bucket_name, entry.file_name().to_string_lossy());
+                let path = entry.path();
+                let last_used = used(&path);
+                entries.push(CacheEntry::new(name, vec![path], last_used));
+            }
+        }
+    }
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{File, FileTimes};
+    use tempfile::TempDir;
+
+    /// Mark `path` as used in 99 days
+    fn use_later(path: &Path) {
+        let time = SystemTime::now() + Duration::from_secs(99 * 86_400);
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_times(FileTimes::new().set_accessed(time).set_modified(time))
+            .unwrap();
+    }
+
+    fn names(entries: &[CacheEntry]) -> Vec<&str> {
+        entries.iter().map(|e| e.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_locate_and_measure() {
+        let temp_dir = TempDir::new().unwrap();
+        let home = temp_dir.path();
+        fs::create_dir_all(home.join(".cargo/registry/cache")).unwrap();
+        fs::write(home.join(".cargo/registry/cache/a.crate"), [0u8; 100]).unwrap();
+        fs::create_dir_all(home.join(".npm/_cacache/index-v5")).unwrap();
+        fs::create_dir_all(home.join(".m2/repository")).unwrap();
+        // Not laid out like pip's cache
+        fs::create_dir_all(home.join(".cache/pip/unrelated")).unwrap();
+
+        let env = CacheEnv::with_home(home);
+        let mut caches = locate(&env);
+        let kinds: Vec<_> = caches.iter().map(|c| c.kind).collect();
+        assert_eq!(kinds, [CacheKind::Cargo, CacheKind::Npm, CacheKind::Maven]);
+
+        measure(&mut caches);
+        assert_eq!((caches[0].size, caches[0].files), (100, 1));
+
+        let env = CacheEnv {
+            go_cache: Some(home.join("go")),
+            ..env
+        };
+        assert_eq!(env.path(CacheKind::Go).unwrap(), home.join("go"));
+        assert_eq!(
+            env.path(CacheKind::Gradle).unwrap(),
+            home.join(".gradle/caches")
+        );
+    }
+
+    #[test]
+    fn test_stale_entries() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let later = SystemTime::now() + Duration::from_secs(100 * 86_400);
+        let month = Duration::from_secs(30 * 86_400);
+        let cache = |kind, dir: &str| GlobalCache {
+            kind,
+            path: root.join(dir),
+            size: 0,
+            files: 0,
+        };
+
+        let registry = root.join("registry");
+        for version in ["serde-1.0.100", "serde-1.0.200"] {
+            let src = registry.join("src/index.crates.io-1").join(version);
+            fs::create_dir_all(&src).unwrap();
+            fs::write(src.join(".cargo-ok"), "{\"v\":1}").unwrap();
+            fs::create_dir_all(registry.join("cache/index.crates.io-1")).unwrap();
+            fs::write(
+                registry.join(format!("cache/index.crates.io-1/{}.crate", version)),
+                [0u8; 10],
+            )
+            .unwrap();
+        }
+        use_later(&registry.join("src/index.crates.io-1/serde-1.0.200/.cargo-ok"));
+        let stale = cache(CacheKind::Cargo, "registry").stale_entries(month, later);
+        assert_eq!(names(&stale), ["serde-1.0.100"]);
+        assert_eq!(stale[0].paths.len(), 2);
+        assert_eq!(stale[0].size, 17);
+        stale[0].remove().unwrap();
+        assert!(!registry
+            .join("src/index.crates.io-1/serde-1.0.100")
+            .exists());
+
+        let repository = root.join("repository");
+        for version in ["org/slf4j/slf4j-api/1.7.36", "org/slf4j/slf4j-api/2.0.9"] {
+            fs::create_dir_all(repository.join(version)).unwrap();
+            fs::write(repository.join(version).join("slf4j-api.pom"), "").unwrap();
+        }
+        use_later(&repository.join("org/slf4j/slf4j-api/2.0.9/slf4j-api.pom"));
+        let stale = cache(CacheKind::Maven, "repository").stale_entries(month, later);
+        assert_eq!(names(&stale), ["org/slf4j/slf4j-api/1.7.36"]);
+
+        let caches = root.join("gradle");
+        for dir in ["7.6", "8.5", "modules-2"] {
+            fs::create_dir_all(caches.join(dir)).unwrap();
+            fs::write(caches.join(dir).join("x.lock"), "").unwrap();
+        }
+        use_later(&caches.join("8.5/x.lock"));
+        let stale = cache(CacheKind::Gradle, "gradle").stale_entries(month, later);
+        assert_eq!(names(&stale), ["7.6"]);
+
+        let go = root.join("go");
+        fs::create_dir_all(go.join("ab")).unwrap();
+        fs::write(go.join("trim.txt"), "").unwrap();
+        fs::write(go.join("ab/ab01-a"), "").unwrap();
+        fs::write(go.join("ab/ab02-d"), "").unwrap();
+        use_later(&go.join("ab/ab02-d"));
+        let stale = cache(CacheKind::Go, "go").stale_entries(month, later);
+        assert_eq!(names(&stale), ["ab/ab01-a"]);
+
+        fs::create_dir_all(root.join("npm/index-v5")).unwrap();
+        let npm = cache(CacheKind::Npm, "npm");
+        assert!(npm.stale_entries(month, later).is_empty());
+        assert!(npm.kind.entries_name().is_none());
+    }
+}
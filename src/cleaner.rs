@@ -1,27 +1,53 @@
+//! Verification and deletion of scan results.
+
+use crate::detectors::DetectorRegistry;
+use crate::events::{CleanEvent, CleanObserver, NullObserver, SkipReason};
+use crate::filter::{Filter, Selection};
+use crate::git::{GitGuard, GitStatus};
+use crate::goal::{FreeSpaceGoal, GoalOutcome};
 use crate::platform::remove_dir_all;
+use crate::trash::Trash;
 use crate::types::{CleanStats, ScanResult};
-use crate::utils::format_size;
-use anyhow::Result;
-use colored::*;
-use indicatif::{ProgressBar, ProgressStyle};
+use anyhow::{bail, Result};
 use rayon::prelude::*;
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 
+/// Deletes the directories found by a [`Scanner`](crate::Scanner)
+///
+/// Every directory is re-verified right before deletion, so results that
+/// changed since the scan are skipped instead of removed. Progress is
+/// reported to a [`CleanObserver`]; the cleaner itself prints nothing.
 pub struct Cleaner {
     dry_run: bool,
     verbose: bool,
     interrupt_flag: Option<Arc<AtomicBool>>,
     parallel: bool,
+    registry: DetectorRegistry,
+    observer: Arc<dyn CleanObserver>,
+    trash: bool,
+    trash_dir: Option<Trash>,
+    filter: Filter,
+    space_goal: Option<FreeSpaceGoal>,
+    git: Option<GitGuard>,
 }
 
 impl Cleaner {
+    /// Create a cleaner; with `dry_run` nothing is deleted
     pub fn new(dry_run: bool, verbose: bool) -> Self {
         Self {
             dry_run,
             verbose,
             interrupt_flag: None,
             parallel: true, // Enable parallel processing by default
+            registry: DetectorRegistry::builtin(),
+            observer: Arc::new(NullObserver),
+            trash: false,
+            trash_dir: None,
+            filter: Filter::new(),
+            space_goal: None,
+            git: None,
         }
     }
 
@@ -37,8 +63,80 @@ impl Cleaner {
         self
     }
 
+    /// Verify targets with a custom set of detectors
+    ///
+    /// Use the same registry that was given to the scanner, otherwise
+    /// results from custom detectors fail verification.
+    pub fn with_registry(mut self, registry: DetectorRegistry) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    /// Send progress events to `observer`
+    pub fn with_observer(mut self, observer: Arc<dyn CleanObserver>) -> Self {
+        self.observer = observer;
+        self
+    }
+
+    /// Move targets to the freedesktop.org trash instead of deleting them
+    ///
+    /// See [`trash`](crate::trash) for where each target ends up.
+    pub fn with_trash(mut self, trash: bool) -> Self {
+        self.trash = trash;
+        self
+    }
+
+    /// Move targets into the trash directory `dir` (holding `files/` and
+    /// `info/`) instead of the one [`Trash::for_path`] picks
+    pub fn with_trash_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.trash = true;
+        self.trash_dir = Some(Trash::open(dir.into(), None));
+        self
+    }
+
+    /// Only clean targets the filter selects; the rest are reported as
+    /// [`CleanEvent::Excluded`] and left alone
+    pub fn with_filter(mut self, filter: Filter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Only clean as much as needed to reach a free-space goal
+    ///
+    /// After the filter, the goal picks the fewest targets that should reach
+    /// it and leaves out the rest. They are then processed one at a time,
+    /// re-reading the free space before each, and a
+    /// [`CleanEvent::SpaceGoal`] reports the outcome. Moving to the trash
+    /// frees nothing, so the two cannot be combined.
+    pub fn with_space_goal(mut self, goal: FreeSpaceGoal) -> Self {
+        self.space_goal = Some(goal);
+        self
+    }
+
+    /// Only clean targets that git ignores and that hold no tracked files
+    ///
+    /// Targets inside a git work tree that fail this are skipped with
+    /// [`SkipReason::NotIgnored`] or [`SkipReason::Tracked`], in a dry run
+    /// too; targets outside any repository are unaffected. See
+    /// [`git`](crate::git).
+    pub fn with_git_aware(mut self, enabled: bool) -> Self {
+        self.git = enabled.then(GitGuard::new);
+        self
+    }
+
+    /// Whether selection needs every result up front, so targets cannot be
+    /// streamed through [`clean_one`](Cleaner::clean_one)
+    pub(crate) fn needs_complete_scan(&self) -> bool {
+        self.filter.needs_all_results() || self.space_goal.is_some()
+    }
+
+    /// Whether this cleaner only pretends to delete
+    pub(crate) fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
     /// Check if the operation has been interrupted
-    fn is_interrupted(&self) -> bool {
+    pub(crate) fn is_interrupted(&self) -> bool {
         self.interrupt_flag
             .as_ref()
             .is_some_and(|flag| flag.load(Ordering::SeqCst))
@@ -47,7 +145,6 @@ impl Cleaner {
     /// Verify directory before deletion to prevent race conditions
     fn verify_before_delete(&self, result: &ScanResult) -> Result<(), String> {
         use crate::platform::can_delete;
-        use crate::types::CleanTarget;
 
         // Check if directory still exists
         if !result.path.exists() {
@@ -73,126 +170,183 @@ impl Cleaner {
             ));
         }
 
-        // Verify marker files based on target type
-        let parent = match result.path.parent() {
-            Some(p) => p,
-            None => return Ok(()), // Root-level directory, skip marker check
-        };
-
-        let verified = match result.target_type {
-            CleanTarget::NodeModules => {
-                // Verify package.json exists for node_modules
-                parent.join("package.json").exists()
-            }
-            CleanTarget::RustTarget => {
-                // Verify Cargo.toml exists for Rust target
-                parent.join("Cargo.toml").exists()
-            }
-            CleanTarget::JavaTarget => {
-                // Verify pom.xml or build.gradle exists for Java targets
-                parent.join("pom.xml").exists()
-                    || parent.join("build.gradle").exists()
-                    || parent.join("build.gradle.kts").exists()
-            }
-            CleanTarget::PythonCache => {
-                // Python cache doesn't require marker file verification
-                true
-            }
-            CleanTarget::All => true,
-        };
-
-        if !verified {
+        // Verify marker files with the detector that found the directory
+        if let Err(reason) = self.registry.verify(result) {
             return Err(format!(
-                "Marker file verification failed for {}: {}",
-                result.target_type.name(),
+                "Marker file verification failed for {} ({}): {}",
+                self.registry.display_name(result),
+                reason,
                 result.path.display()
             ));
         }
 
+        // The project may have asked to keep it since the scan
+        if let Some(marker) = self.registry.keep_marker(result) {
+            return Err(format!("Kept by {}: {}", marker, result.path.display()));
+        }
+
         Ok(())
     }
 
+    /// Why git-aware mode refuses to delete `result`, if it does
+    fn git_refusal(&self, result: &ScanResult) -> Option<SkipReason> {
+        match self.git.as_ref()?.status(&result.path) {
+            Ok(GitStatus::Outside | GitStatus::Ignored) => None,
+            Ok(GitStatus::NotIgnored) => Some(SkipReason::NotIgnored),
+            Ok(GitStatus::Tracked(files)) => Some(SkipReason::Tracked(files)),
+            Err(e) => Some(SkipReason::Verification(format!(
+                "Could not check git status: {:#}",
+                e
+            ))),
+        }
+    }
+
     /// Clean the directories found by the scanner
+    ///
+    /// Unless this is a dry run, the observer is asked to confirm first.
     pub fn clean(&self, results: Vec<ScanResult>) -> Result<CleanStats> {
-        self.clean_internal(results, true)
+        self.clean_internal(Selection::from(results), true)
     }
 
     /// Clean without confirmation (for --yes flag)
     pub fn clean_without_confirmation(&self, results: Vec<ScanResult>) -> Result<CleanStats> {
-        self.clean_internal(results, false)
+        self.clean_internal(Selection::from(results), false)
+    }
+
+    /// Clean a selection made earlier, such as the targets picked
+    /// interactively from what the filter selected, without confirmation
+    ///
+    /// Its exclusions are reported as [`CleanEvent::Excluded`] and counted,
+    /// just as if this cleaner's filter had turned them down.
+    pub fn clean_selection(&self, selection: Selection) -> Result<CleanStats> {
+        self.clean_internal(selection, false)
     }
 
     /// Internal clean method with optional confirmation
     fn clean_internal(
         &self,
-        results: Vec<ScanResult>,
+        selection: Selection,
         require_confirmation: bool,
     ) -> Result<CleanStats> {
         let mut stats = CleanStats::default();
 
+        // Leave out what the filter turns down before anything is confirmed
+        let Selection {
+            selected: results,
+            mut excluded,
+        } = selection;
+        let Selection {
+            selected: results,
+            excluded: filtered,
+        } = self.filter.apply(results);
+        excluded.extend(filtered);
+        for excluded in excluded {
+            stats.add_excluded(&excluded.target);
+            self.emit(CleanEvent::Excluded {
+                target: excluded.target,
+                reason: excluded.reason,
+            });
+        }
+
+        // Then keep only what the free-space goal needs
+        let (results, goal) = match &self.space_goal {
+            Some(goal) => {
+                if self.trash {
+                    bail!(
+                        "Moving to the trash frees no space, so it cannot reach a free-space goal"
+                    );
+                }
+                let space = goal.space()?;
+                let Selection { selected, excluded } = goal.choose(results, space);
+                for excluded in excluded {
+                    stats.add_excluded(&excluded.target);
+                    self.emit(CleanEvent::Excluded {
+                        target: excluded.target,
+                        reason: excluded.reason,
+                    });
+                }
+                let required = goal.goal().required(space);
+                (selected, Some((goal, required, space.available)))
+            }
+            None => (results, None),
+        };
+
+        // Announce what will be cleaned
+        self.emit(CleanEvent::Planned {
+            targets: results.clone(),
+            dry_run: self.dry_run,
+        });
+
         if results.is_empty() {
-            println!("{}", "No directories found to clean.".yellow());
+            self.finish_goal(goal, &stats)?;
+            self.emit(CleanEvent::Finished {
+                stats: stats.clone(),
+            });
             return Ok(stats);
         }
 
-        // Show what will be cleaned
-        self.print_summary(&results);
-
         // Ask for confirmation if not dry run
-        if !self.dry_run && require_confirmation && !self.confirm_deletion() {
-            println!("{}", "Cleanup cancelled.".yellow());
+        if !self.dry_run && require_confirmation && !self.observer.confirm(&results) {
+            self.emit(CleanEvent::Cancelled);
             return Ok(stats);
         }
 
-        // Get total count for progress bar
-        let total = results.len();
-
-        // Create progress bar (for both dry-run and real mode if not verbose)
-        let pb = if !self.verbose {
-            let pb = ProgressBar::new(total as u64);
-            pb.set_style(
-                ProgressStyle::default_bar()
-                    .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}")
-                    .unwrap()
-                    .progress_chars("#>-"),
-            );
-            Some(pb)
-        } else {
-            None
-        };
-
-        // Process results - use parallel processing if enabled and not in verbose mode
-        if self.parallel && !self.verbose && results.len() > 1 {
+        // Process results - use parallel processing if enabled and not in verbose mode;
+        // a free-space goal needs one target at a time to notice when it is reached
+        if let Some((goal, required, _)) = goal {
+            self.process_sequential(results, &mut stats, Some((goal, required)))?;
+        } else if self.parallel && !self.verbose && results.len() > 1 {
             // Parallel processing for better performance with many directories
-            self.process_parallel(results, &pb, &mut stats)?;
+            self.process_parallel(results, &mut stats)?;
         } else {
             // Sequential processing for verbose mode or single directory
-            self.process_sequential(results, &pb, &mut stats)?;
+            self.process_sequential(results, &mut stats, None)?;
         }
 
-        // Finish progress bar with appropriate message
-        if let Some(pb) = pb {
-            if self.dry_run {
-                pb.finish_with_message("Dry run complete!");
-            } else if stats.failed_dirs > 0 || stats.skipped_dirs > 0 {
-                pb.finish_with_message(format!(
-                    "Done with {} warnings",
-                    stats.failed_dirs + stats.skipped_dirs
-                ));
-            } else {
-                pb.finish_with_message("Successfully completed!");
-            }
-        }
+        self.finish_goal(goal, &stats)?;
+        self.emit(CleanEvent::Finished {
+            stats: stats.clone(),
+        });
 
         Ok(stats)
     }
 
+    /// Report how a free-space goal turned out
+    fn finish_goal(
+        &self,
+        goal: Option<(&FreeSpaceGoal, u64, u64)>,
+        stats: &CleanStats,
+    ) -> Result<()> {
+        let Some((goal, required, available_before)) = goal else {
+            return Ok(());
+        };
+        // A dry run frees nothing, so estimate from the target sizes
+        let available = if self.dry_run {
+            available_before.saturating_add(stats.total_size)
+        } else {
+            goal.space()?.available
+        };
+        self.emit(CleanEvent::SpaceGoal {
+            outcome: GoalOutcome {
+                required,
+                available_before,
+                available,
+                met: available >= required,
+                estimated: self.dry_run,
+            },
+        });
+        Ok(())
+    }
+
     /// Process results sequentially (for verbose mode or when parallel is disabled)
+    ///
+    /// With a goal and the bytes it requires, targets left after the free
+    /// space reaches it are skipped.
     fn process_sequential(
         &self,
         results: Vec<ScanResult>,
-        pb: &Option<ProgressBar>,
         stats: &mut CleanStats,
+        goal: Option<(&FreeSpaceGoal, u64)>,
     ) -> Result<()> {
         let total = results.len();
 
@@ -205,272 +359,183 @@ impl Cleaner {
                     stats.add_skipped();
                 }
 
-                if let Some(ref pb) = pb {
-                    pb.finish_with_message("Interrupted!");
-                }
-
-                println!(
-                    "\n{}",
-                    "⚠️  Cleanup interrupted by user. Remaining directories skipped."
-                        .yellow()
-                        .bold()
-                );
+                self.emit(CleanEvent::Interrupted { remaining });
                 break;
             }
 
-            if self.dry_run {
-                // In dry-run mode, count everything as it would be deleted
-                stats.add_result(&result);
-
-                if self.verbose {
-                    println!(
-                        "{} {} {} ({})",
-                        "[DRY RUN]".yellow(),
-                        "Would delete:".white(),
-                        result.path.display(),
-                        format_size(result.size).cyan()
-                    );
-                } else if let Some(ref pb) = pb {
-                    let dir_name = result
-                        .path
-                        .file_name()
-                        .and_then(|n| n.to_str())
-                        .unwrap_or("unknown");
-                    pb.set_message(format!("Checking: {}", dir_name));
-                    pb.inc(1);
-                }
-            } else {
-                // Verify before deletion to prevent race conditions
-                if let Err(e) = self.verify_before_delete(&result) {
-                    stats.add_skipped();
-
-                    if self.verbose {
-                        eprintln!("{} Skipped {}: {}", "⚠️".yellow(), result.path.display(), e);
-                    }
-
-                    if let Some(ref pb) = pb {
-                        pb.inc(1);
-                    }
+            // Sizes are estimates (hard links, open files), so measure again
+            if let Some((goal, required)) = goal {
+                if !self.dry_run && goal.space()?.available >= required {
+                    stats.add_excluded(&result);
+                    self.emit(CleanEvent::Skipped {
+                        target: result,
+                        reason: SkipReason::GoalReached,
+                    });
                     continue;
                 }
-
-                if self.verbose {
-                    println!("{} {}", "Deleting:".red(), result.path.display());
-                }
-
-                // Update progress bar with current directory name
-                if let Some(ref pb) = pb {
-                    let dir_name = result
-                        .path
-                        .file_name()
-                        .and_then(|n| n.to_str())
-                        .unwrap_or("unknown");
-                    pb.set_message(format!("Deleting: {}", dir_name));
-                }
-
-                // Only add to stats if deletion succeeds
-                match remove_dir_all(&result.path) {
-                    Ok(_) => {
-                        stats.add_result(&result);
-
-                        if self.verbose {
-                            println!(
-                                "  {} {} freed",
-                                "✓".green(),
-                                format_size(result.size).cyan()
-                            );
-                        }
-
-                        if let Some(ref pb) = pb {
-                            pb.inc(1);
-                        }
-                    }
-                    Err(e) => {
-                        stats.add_failed();
-
-                        eprintln!(
-                            "{} Failed to delete {}: {}",
-                            "✗".red(),
-                            result.path.display(),
-                            e
-                        );
-
-                        if let Some(ref pb) = pb {
-                            pb.inc(1);
-                        }
-                    }
-                }
             }
+
+            self.process_one(&result).record(&result, stats);
         }
 
         Ok(())
     }
 
     /// Process results in parallel for better performance
-    fn process_parallel(
-        &self,
-        results: Vec<ScanResult>,
-        pb: &Option<ProgressBar>,
-        stats: &mut CleanStats,
-    ) -> Result<()> {
-        let stats_mutex = Arc::new(Mutex::new(CleanStats::default()));
-        let pb_arc = pb.as_ref().map(|p| Arc::new(p.clone()));
-        let processed = Arc::new(AtomicUsize::new(0));
+    fn process_parallel(&self, results: Vec<ScanResult>, stats: &mut CleanStats) -> Result<()> {
+        let stats_mutex = Mutex::new(CleanStats::default());
+        let processed = AtomicUsize::new(0);
         let total = results.len();
 
         // Process in parallel using rayon
-        results.par_iter().try_for_each(|result| -> Result<()> {
+        results.par_iter().for_each(|result| {
             // Check for interruption
             if self.is_interrupted() {
-                return Ok(());
-            }
-
-            if self.dry_run {
-                // In dry-run mode, count everything as it would be deleted
-                let mut stats_guard = stats_mutex.lock().unwrap();
-                stats_guard.add_result(result);
-
-                if let Some(ref pb) = pb_arc {
-                    let dir_name = result
-                        .path
-                        .file_name()
-                        .and_then(|n| n.to_str())
-                        .unwrap_or("unknown");
-                    pb.set_message(format!("Checking: {}", dir_name));
-                    pb.inc(1);
-                }
-            } else {
-                // Verify before deletion to prevent race conditions
-                if let Err(_e) = self.verify_before_delete(result) {
-                    let mut stats_guard = stats_mutex.lock().unwrap();
-                    stats_guard.add_skipped();
-
-                    if let Some(ref pb) = pb_arc {
-                        pb.inc(1);
-                    }
-                    processed.fetch_add(1, Ordering::SeqCst);
-                    return Ok(());
-                }
-
-                // Update progress bar with current directory name
-                if let Some(ref pb) = pb_arc {
-                    let dir_name = result
-                        .path
-                        .file_name()
-                        .and_then(|n| n.to_str())
-                        .unwrap_or("unknown");
-                    pb.set_message(format!("Deleting: {}", dir_name));
-                }
-
-                // Only add to stats if deletion succeeds
-                match remove_dir_all(&result.path) {
-                    Ok(_) => {
-                        let mut stats_guard = stats_mutex.lock().unwrap();
-                        stats_guard.add_result(result);
-
-                        if let Some(ref pb) = pb_arc {
-                            pb.inc(1);
-                        }
-                    }
-                    Err(e) => {
-                        let mut stats_guard = stats_mutex.lock().unwrap();
-                        stats_guard.add_failed();
-
-                        eprintln!(
-                            "{} Failed to delete {}: {}",
-                            "✗".red(),
-                            result.path.display(),
-                            e
-                        );
-
-                        if let Some(ref pb) = pb_arc {
-                            pb.inc(1);
-                        }
-                    }
-                }
+                return;
             }
 
+            let outcome = self.process_one(result);
+            outcome.record(result, &mut stats_mutex.lock().unwrap());
             processed.fetch_add(1, Ordering::SeqCst);
-            Ok(())
-        })?;
+        });
+
+        let mut final_stats = stats_mutex.into_inner().unwrap();
 
         // Count skipped directories if interrupted
         let processed_count = processed.load(Ordering::SeqCst);
         if processed_count < total {
-            let mut stats_guard = stats_mutex.lock().unwrap();
-            for _ in 0..(total - processed_count) {
-                stats_guard.add_skipped();
-            }
-
-            if let Some(ref pb) = pb {
-                pb.finish_with_message("Interrupted!");
+            let remaining = total - processed_count;
+            for _ in 0..remaining {
+                final_stats.add_skipped();
             }
 
-            println!(
-                "\n{}",
-                "⚠️  Cleanup interrupted by user. Remaining directories skipped."
-                    .yellow()
-                    .bold()
-            );
+            self.emit(CleanEvent::Interrupted { remaining });
         }
 
-        // Merge parallel stats back into main stats
-        let final_stats = Arc::try_unwrap(stats_mutex).unwrap().into_inner().unwrap();
-        *stats = final_stats;
+        stats.merge(&final_stats);
 
         Ok(())
     }
 
-    /// Print a summary of what will be cleaned
-    fn print_summary(&self, results: &[ScanResult]) {
-        println!("\n{}", "=".repeat(60).cyan());
+    /// Verify and delete a single target, reporting progress as events
+    ///
+    /// Returns the statistics for this one target, ready to be
+    /// [merged](CleanStats::merge) into a running total. This is what the
+    /// streaming [`Pipeline`](crate::pipeline::Pipeline) calls for each
+    /// target; no confirmation is asked. Targets the filter turns down are
+    /// reported as excluded and left alone.
+    pub fn clean_one(&self, result: &ScanResult) -> CleanStats {
+        let mut stats = CleanStats::default();
+        if let Err(reason) = self.filter.check(result) {
+            stats.add_excluded(result);
+            self.emit(CleanEvent::Excluded {
+                target: result.clone(),
+                reason,
+            });
+            return stats;
+        }
+        self.process_one(result).record(result, &mut stats);
+        stats
+    }
+
+    /// Verify and delete a single target, reporting progress as events
+    fn process_one(&self, result: &ScanResult) -> Outcome {
+        if let Some(reason) = self.git_refusal(result) {
+            self.emit(CleanEvent::Skipped {
+                target: result.clone(),
+                reason,
+            });
+            return Outcome::Skipped;
+        }
+
         if self.dry_run {
-            println!("{}", "DRY RUN - No files will be deleted".yellow().bold());
-        } else {
-            println!("{}", "Cleanup Summary".cyan().bold());
+            // In dry-run mode, count everything as it would be deleted
+            self.emit(CleanEvent::WouldDelete {
+                target: result.clone(),
+            });
+            return Outcome::Removed;
         }
-        println!("{}\n", "=".repeat(60).cyan());
 
-        let total_size: u64 = results.iter().map(|r| r.size).sum();
-        let total_files: usize = results.iter().map(|r| r.file_count).sum();
+        // Verify before deletion to prevent race conditions
+        self.emit(CleanEvent::Verifying {
+            target: result.clone(),
+        });
+        if let Err(e) = self.verify_before_delete(result) {
+            self.emit(CleanEvent::Skipped {
+                target: result.clone(),
+                reason: SkipReason::Verification(e),
+            });
+            return Outcome::Skipped;
+        }
 
-        println!(
-            "Found {} directories to clean:",
-            results.len().to_string().green().bold()
-        );
-        println!("Total size: {}", format_size(total_size).cyan().bold());
-        println!("Total files: {}", total_files.to_string().yellow().bold());
-        println!();
-
-        if self.verbose {
-            for result in results {
-                println!(
-                    "  {} {} {} ({}, {} files)",
-                    "•".cyan(),
-                    result.target_type.name().white().bold(),
-                    result.path.display().to_string().dimmed(),
-                    format_size(result.size).cyan(),
-                    result.file_count.to_string().yellow()
-                );
-            }
-            println!();
+        self.emit(CleanEvent::Deleting {
+            target: result.clone(),
+        });
+
+        if self.trash {
+            let trash = match &self.trash_dir {
+                Some(trash) => Ok(trash.clone()),
+                None => Trash::for_path(&result.path),
+            };
+            let moved = trash.and_then(|t| t.put(&result.path, result.size));
+            return match moved {
+                Ok(location) => {
+                    self.emit(CleanEvent::Trashed {
+                        target: result.clone(),
+                        location,
+                    });
+                    Outcome::Trashed
+                }
+                Err(e) => {
+                    self.emit(CleanEvent::Failed {
+                        target: result.clone(),
+                        error: format!("{:#}", e),
+                    });
+                    Outcome::Failed
+                }
+            };
         }
 
-        println!("{}", "=".repeat(60).cyan());
-        println!();
+        // Only count as removed if deletion succeeds
+        match remove_dir_all(&result.path) {
+            Ok(_) => {
+                self.emit(CleanEvent::Deleted {
+                    target: result.clone(),
+                    bytes: result.size,
+                });
+                Outcome::Removed
+            }
+            Err(e) => {
+                self.emit(CleanEvent::Failed {
+                    target: result.clone(),
+                    error: format!("{:#}", e),
+                });
+                Outcome::Failed
+            }
+        }
     }
 
-    /// Ask user for confirmation
-    fn confirm_deletion(&self) -> bool {
-        use std::io::{self, Write};
-
-        print!("{}", "Do you want to proceed? [y/N]: ".yellow().bold());
-        io::stdout().flush().unwrap();
+    pub(crate) fn emit(&self, event: CleanEvent) {
+        self.observer.on_event(&event);
+    }
+}
 
-        let mut input = String::new();
-        io::stdin().read_line(&mut input).unwrap();
+/// What happened to a single target
+enum Outcome {
+    Removed,
+    Trashed,
+    Skipped,
+    Failed,
+}
 
-        matches!(input.trim().to_lowercase().as_str(), "y" | "yes")
+impl Outcome {
+    fn record(&self, result: &ScanResult, stats: &mut CleanStats) {
+        match self {
+            Outcome::Removed => stats.add_result(result),
+            Outcome::Trashed => stats.add_trashed(result),
+            Outcome::Skipped => stats.add_skipped(),
+            Outcome::Failed => stats.add_failed(),
+        }
     }
 }
 
@@ -535,7 +600,7 @@ mod tests {
 
         // Real deletion (dry_run=false), skip confirmation for test
         let cleaner = Cleaner::new(false, false);
-        let stats = cleaner.clean_internal(vec![result], false).unwrap();
+        let stats = cleaner.clean_internal(vec![result].into(), false).unwrap();
 
         // Verify directory was actually deleted
         assert!(!test_dir.exists(), "Directory should be deleted");
@@ -581,7 +646,7 @@ mod tests {
 
         let cleaner = Cleaner::new(false, false);
         let stats = cleaner
-            .clean_internal(vec![result1, result2], false)
+            .clean_internal(vec![result1, result2].into(), false)
             .unwrap();
 
         // Both deletions should be counted
@@ -596,4 +661,274 @@ mod tests {
         assert!(!dir1.exists());
         assert!(!dir2.exists());
     }
+
+    #[test]
+    fn test_cleaner_emits_events() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("package.json"), "{}").unwrap();
+        let node_modules = temp_dir.path().join("node_modules");
+        fs::create_dir(&node_modules).unwrap();
+
+        // No package.json next to this one, so verification fails
+        let orphan = temp_dir.path().join("orphan").join("node_modules");
+        fs::create_dir_all(&orphan).unwrap();
+
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let cleaner = Cleaner::new(false, false)
+            .with_parallel(false)
+            .with_observer(Arc::new(tx));
+        let results = vec![
+            ScanResult::new(node_modules.clone(), CleanTarget::NodeModules)
+                .with_detector("node-modules")
+                .with_size(42, 1),
+            ScanResult::new(orphan.clone(), CleanTarget::NodeModules).with_detector("node-modules"),
+        ];
+        let stats = cleaner.clean_without_confirmation(results).unwrap();
+        drop(cleaner);
+
+        let events: Vec<CleanEvent> = rx.iter().collect();
+        assert!(
+            matches!(&events[0], CleanEvent::Planned { targets, dry_run: false } if targets.len() == 2)
+        );
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, CleanEvent::Deleted { bytes: 42, .. })));
+        assert!(events.iter().any(|e| matches!(
+            e,
+            CleanEvent::Skipped { target, reason: SkipReason::Verification(_) } if target.path == orphan
+        )));
+        assert!(matches!(events.last(), Some(CleanEvent::Finished { .. })));
+
+        assert_eq!(stats.total_dirs, 1);
+        assert_eq!(stats.skipped_dirs, 1);
+        assert!(!node_modules.exists());
+        assert!(orphan.exists());
+    }
+
+    #[test]
+    fn test_filter_excludes_recent_targets() {
+        use crate::filter::Filter;
+        use std::time::{Duration, SystemTime};
+
+        let now = SystemTime::now();
+        let old = ScanResult::new("/p/old/node_modules".into(), CleanTarget::NodeModules)
+            .with_size(10, 1)
+            .with_last_activity(now - Duration::from_secs(86_400 * 40));
+        let recent = ScanResult::new("/p/new/node_modules".into(), CleanTarget::NodeModules)
+            .with_size(5, 1)
+            .with_last_activity(now);
+
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let cleaner = Cleaner::new(true, false)
+            .with_filter(Filter::new().with_older_than(Duration::from_secs(86_400 * 30)))
+            .with_observer(Arc::new(tx));
+        let stats = cleaner.clean(vec![old, recent.clone()]).unwrap();
+        assert_eq!(cleaner.clean_one(&recent).excluded_dirs, 1);
+        drop(cleaner);
+
+        let events: Vec<CleanEvent> = rx.iter().collect();
+        assert!(
+            matches!(&events[0], CleanEvent::Excluded { target, .. } if target.path == recent.path)
+        );
+        assert!(matches!(&events[1], CleanEvent::Planned { targets, .. } if targets.len() == 1));
+        assert_eq!(stats.total_dirs, 1);
+        assert_eq!(stats.total_size, 10);
+        assert_eq!(stats.excluded_dirs, 1);
+        assert_eq!(stats.excluded_size, 5);
+    }
+
+    #[test]
+    fn test_clean_selection_reports_its_exclusions() {
+        use crate::filter::Filter;
+
+        let small =
+            ScanResult::new("/p/a/node_modules".into(), CleanTarget::NodeModules).with_size(5, 1);
+        let large =
+            ScanResult::new("/p/b/node_modules".into(), CleanTarget::NodeModules).with_size(50, 1);
+        let filter = Filter::new().with_min_size(10);
+        let selection = filter.apply(vec![small.clone(), large]);
+        assert_eq!(selection.excluded.len(), 1);
+
+        // As when the picker hands back what it was offered
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let cleaner = Cleaner::new(true, false)
+            .with_filter(filter)
+            .with_observer(Arc::new(tx));
+        let stats = cleaner.clean_selection(selection).unwrap();
+        drop(cleaner);
+
+        let events: Vec<CleanEvent> = rx.iter().collect();
+        assert!(
+            matches!(&events[0], CleanEvent::Excluded { target, .. } if target.path == small.path)
+        );
+        assert_eq!(stats.total_dirs, 1);
+        assert_eq!(stats.excluded_dirs, 1);
+        assert_eq!(stats.excluded_size, 5);
+    }
+
+    #[test]
+    fn test_keep_marker_added_after_scan() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("package.json"),
+            r#"{"cleanFiles": {"keep": true}}"#,
+        )
+        .unwrap();
+        let node_modules = temp_dir.path().join("node_modules");
+        fs::create_dir(&node_modules).unwrap();
+
+        // The scan did not see the marker, but deletion checks again
+        let result = ScanResult::new(node_modules.clone(), CleanTarget::NodeModules);
+        let stats = Cleaner::new(false, false)
+            .clean_without_confirmation(vec![result])
+            .unwrap();
+        assert!(node_modules.exists());
+        assert_eq!(stats.skipped_dirs, 1);
+        assert_eq!(stats.total_dirs, 0);
+    }
+
+    #[test]
+    fn test_git_aware_skips_unignored_targets() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir(root.join(".git")).unwrap();
+        fs::write(root.join(".gitignore"), "node_modules/\n").unwrap();
+        let results: Vec<_> = ["a", "b"]
+            .iter()
+            .flat_map(|project| {
+                let project = root.join(project);
+                fs::create_dir_all(project.join("node_modules")).unwrap();
+                fs::write(project.join("package.json"), "{}").unwrap();
+                fs::create_dir_all(project.join("target")).unwrap();
+                fs::write(project.join("Cargo.toml"), "[package]").unwrap();
+                [
+                    ScanResult::new(project.join("node_modules"), CleanTarget::NodeModules),
+                    ScanResult::new(project.join("target"), CleanTarget::RustTarget),
+                ]
+            })
+            .collect();
+
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let cleaner = Cleaner::new(true, false)
+            .with_git_aware(true)
+            .with_observer(Arc::new(tx));
+        let stats = cleaner.clean(results.clone()).unwrap();
+        drop(cleaner);
+        assert_eq!(stats.total_dirs, 2);
+        assert_eq!(stats.skipped_dirs, 2);
+        let skipped = rx
+            .iter()
+            .filter(|e| {
+                matches!(
+                    e,
+                    CleanEvent::Skipped {
+                        reason: SkipReason::NotIgnored,
+                        ..
+                    }
+                )
+            })
+            .count();
+        assert_eq!(skipped, 2);
+
+        // Without the flag, git is not consulted
+        let stats = Cleaner::new(true, false).clean(results).unwrap();
+        assert_eq!(stats.total_dirs, 4);
+    }
+
+    #[test]
+    fn test_space_goal() {
+        use crate::goal::{FreeSpaceGoal, SpaceGoal};
+
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("package.json"), "{}").unwrap();
+        let node_modules = temp_dir.path().join("node_modules");
+        fs::create_dir(&node_modules).unwrap();
+        let results =
+            vec![ScanResult::new(node_modules.clone(), CleanTarget::NodeModules).with_size(3, 1)];
+
+        // Already met: nothing is planned and nothing is deleted
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let stats = Cleaner::new(false, false)
+            .with_space_goal(FreeSpaceGoal::new(temp_dir.path(), SpaceGoal::Bytes(0)))
+            .with_observer(Arc::new(tx))
+            .clean_without_confirmation(results.clone())
+            .unwrap();
+        assert_eq!(stats.total_dirs, 0);
+        assert_eq!(stats.excluded_dirs, 1);
+        assert!(node_modules.exists());
+        let events: Vec<CleanEvent> = rx.iter().collect();
+        assert!(events.iter().any(|e| matches!(
+            e,
+            CleanEvent::SpaceGoal { outcome } if outcome.met && !outcome.estimated
+        )));
+
+        // Out of reach: everything is chosen and the goal is reported as missed
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let stats = Cleaner::new(true, false)
+            .with_space_goal(FreeSpaceGoal::new(
+                temp_dir.path(),
+                SpaceGoal::Percent(100.0),
+            ))
+            .with_observer(Arc::new(tx))
+            .clean(results.clone())
+            .unwrap();
+        assert_eq!(stats.total_dirs, 1);
+        let events: Vec<CleanEvent> = rx.iter().collect();
+        assert!(events.iter().any(|e| matches!(
+            e,
+            CleanEvent::SpaceGoal { outcome } if !outcome.met && outcome.estimated
+        )));
+
+        // The trash frees nothing, so it cannot serve a goal
+        assert!(Cleaner::new(false, false)
+            .with_trash(true)
+            .with_space_goal(FreeSpaceGoal::new(temp_dir.path(), SpaceGoal::Bytes(0)))
+            .clean_without_confirmation(results)
+            .is_err());
+    }
+
+    #[test]
+    fn test_clean_requires_observer_confirmation() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("package.json"), "{}").unwrap();
+        let node_modules = temp_dir.path().join("node_modules");
+        fs::create_dir(&node_modules).unwrap();
+
+        // The default observer declines, so nothing is deleted
+        let cleaner = Cleaner::new(false, false);
+        let stats = cleaner
+            .clean(vec![ScanResult::new(
+                node_modules.clone(),
+                CleanTarget::NodeModules,
+            )])
+            .unwrap();
+
+        assert!(node_modules.exists());
+        assert_eq!(stats.total_dirs, 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_clean_to_trash() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("package.json"), "{}").unwrap();
+        let node_modules = temp_dir.path().join("node_modules");
+        fs::create_dir(&node_modules).unwrap();
+
+        let result = ScanResult::new(node_modules.clone(), CleanTarget::NodeModules)
+            .with_detector("node-modules")
+            .with_size(7, 1);
+        let stats = Cleaner::new(false, false)
+            .with_trash_dir(temp_dir.path().join("data/Trash"))
+            .clean_one(&result);
+
+        assert!(!node_modules.exists());
+        assert!(temp_dir
+            .path()
+            .join("data/Trash/files/node_modules")
+            .is_dir());
+        assert_eq!(stats.total_dirs, 1);
+        assert_eq!(stats.trashed_dirs, 1);
+        assert_eq!(stats.total_size, 7);
+    }
 }
@@ -1,13 +1,40 @@
-use crate::types::CleanTarget;
-use clap::{Parser, ValueEnum};
+use clap::{ArgGroup, Args, Parser, Subcommand, ValueEnum};
+use clean_files::caches::CacheKind;
+use clean_files::filter::Ranking;
+use clean_files::goal::{SpaceGoal, Strategy};
+use clean_files::utils::{parse_duration, parse_size};
+use clean_files::CleanTarget;
 use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
 
 #[derive(Parser, Debug)]
 #[command(name = "clean-files")]
 #[command(author = "Clean Files Contributors")]
 #[command(version = "0.1.0")]
 #[command(about = "Clean development directories (node_modules, target, __pycache__, etc.)", long_about = None)]
+#[command(args_conflicts_with_subcommands = true)]
 pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Without a subcommand, the flags below mean `clean`
+    #[command(flatten)]
+    pub clean: CleanArgs,
+
+    /// Output format: colored text, one JSON document at the end, or one
+    /// JSON event per line while running
+    #[arg(long, value_enum, default_value = "human", global = true)]
+    pub format: OutputFormat,
+
+    /// Config file with custom targets (default: ~/.config/clean-files/config.toml)
+    #[arg(long, value_name = "FILE", global = true)]
+    pub config: Option<PathBuf>,
+}
+
+/// What to scan and how
+#[derive(Args, Debug, Clone)]
+pub struct ScanArgs {
     /// Directory to scan (defaults to current directory)
     #[arg(value_name = "PATH", default_value = ".")]
     pub path: PathBuf,
@@ -16,10 +43,6 @@ pub struct Cli {
     #[arg(short, long, value_enum, default_value = "all")]
     pub target: TargetType,
 
-    /// Perform a dry run without actually deleting anything
-    #[arg(short = 'n', long)]
-    pub dry_run: bool,
-
     /// Show verbose output
     #[arg(short, long)]
     pub verbose: bool,
@@ -28,25 +51,349 @@ pub struct Cli {
     #[arg(short = 'd', long)]
     pub max_depth: Option<usize>,
 
+    /// Never enter or clean directories matching this glob (gitignore
+    /// syntax, relative to PATH; may be repeated)
+    #[arg(short = 'x', long, value_name = "GLOB")]
+    pub exclude: Vec<String>,
+
+    /// Number of threads used to scan the tree (default: one per CPU)
+    #[arg(long, value_name = "N", default_value_t = 0, hide_default_value = true)]
+    pub threads: usize,
+
+    /// Only select targets with no changes for at least this long, e.g.
+    /// 30d, 12h or 2w (a bare number means days)
+    #[arg(long, value_name = "AGE", value_parser = parse_duration)]
+    pub older_than: Option<Duration>,
+
+    /// With --older-than, also count edits to the project's own files
+    /// (everything next to the target) as activity
+    #[arg(long, requires = "older_than")]
+    pub include_sources: bool,
+
+    /// Also report build directories outside PATH that projects inside it
+    /// build into, such as a shared CARGO_TARGET_DIR or a Bazel output base
+    #[arg(long)]
+    pub outside_root: bool,
+
+    /// Only select targets at least this large, e.g. 100M or 1.5G
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    pub min_size: Option<u64>,
+
+    /// Only select targets at most this large
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    pub max_size: Option<u64>,
+
+    /// Only select targets holding at least this many files
+    #[arg(long, value_name = "N")]
+    pub min_files: Option<usize>,
+
+    /// Only select the N largest targets, ranked by --rank-by
+    #[arg(long, value_name = "N")]
+    pub top: Option<usize>,
+
+    /// What --top ranks by
+    #[arg(long, value_enum, default_value = "size", requires = "top")]
+    pub rank_by: RankBy,
+}
+
+/// Options for deleting what a scan found
+#[derive(Args, Debug, Clone)]
+pub struct CleanArgs {
+    #[command(flatten)]
+    pub scan: ScanArgs,
+
+    /// Perform a dry run without actually deleting anything
+    #[arg(short = 'n', long)]
+    pub dry_run: bool,
+
     /// Skip confirmation prompt (use with caution!)
     #[arg(short = 'y', long)]
     pub yes: bool,
 
+    /// Pick the targets to delete in a full-screen list instead of
+    /// confirming all of them at once
+    #[arg(short = 'i', long, conflicts_with_all = ["yes", "pipeline", "ensure_free"])]
+    pub interactive: bool,
+
     /// Use parallel processing for faster deletion (default: enabled)
     #[arg(short = 'j', long = "parallel", default_value = "true")]
     pub parallel: bool,
+
+    /// Move directories to the trash (freedesktop.org spec) instead of
+    /// deleting them; undo with `clean-files restore`
+    #[arg(long)]
+    pub trash: bool,
+
+    /// Only delete targets that git ignores and that contain no tracked
+    /// files; targets outside a git repository are not affected
+    #[arg(long)]
+    pub git_aware: bool,
+
+    /// Stream targets from the scan straight into sizing and deletion
+    /// instead of scanning everything first (requires --yes or --dry-run)
+    #[arg(long, conflicts_with_all = ["plan", "top"])]
+    pub pipeline: bool,
+
+    /// Only delete as much as needed until the filesystem has this much
+    /// free space, e.g. 50G or 20%
+    #[arg(long, value_name = "SIZE|PERCENT", value_parser = SpaceGoal::from_str, conflicts_with_all = ["trash", "pipeline"])]
+    pub ensure_free: Option<SpaceGoal>,
+
+    /// Which targets --ensure-free deletes first
+    #[arg(long, value_enum, default_value = "largest", requires = "ensure_free")]
+    pub strategy: GoalStrategy,
+
+    /// Clean the targets listed in a plan saved by `scan --save-plan`
+    /// instead of scanning; PATH is then only used to find .clean-files.toml
+    #[arg(long, value_name = "FILE")]
+    pub plan: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Find and size cleanable directories without deleting anything
+    Scan {
+        #[command(flatten)]
+        scan: ScanArgs,
+
+        /// Also write the result as a plan for `clean --plan`
+        #[arg(short = 'o', long, value_name = "FILE")]
+        save_plan: Option<PathBuf>,
+    },
+    /// Delete cleanable directories (the default when no subcommand is given)
+    Clean(CleanArgs),
+    /// Remove parts of Cargo target directories, leaving the rest usable
+    Sweep(SweepArgs),
+    /// Size and prune the per-user caches of cargo, npm, Maven, Gradle, pip
+    /// and Go
+    Caches(CachesArgs),
+    /// Summarise a plan or a report written with --format json
+    Report {
+        /// JSON plan or report
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+    },
+    /// Move directories that were cleaned with --trash back into place
+    Restore {
+        /// Restore directories trashed from this directory or below it
+        #[arg(value_name = "PATH", default_value = ".")]
+        path: PathBuf,
+
+        /// Only list what could be restored
+        #[arg(short, long)]
+        list: bool,
+
+        /// Skip confirmation prompt
+        #[arg(short = 'y', long)]
+        yes: bool,
+    },
+    /// Show or validate the effective configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Explain why a directory is or is not selected for cleaning
+    Explain {
+        /// Directory to explain
+        #[arg(value_name = "PATH")]
+        path: PathBuf,
+
+        /// Scan root to evaluate it against (default: the current directory
+        /// if PATH is inside it, otherwise the parent of PATH)
+        #[arg(long, value_name = "DIR")]
+        root: Option<PathBuf>,
+
+        /// Type of directories to clean
+        #[arg(short, long, value_enum, default_value = "all")]
+        target: TargetType,
+
+        /// Maximum depth to scan (default: unlimited)
+        #[arg(short = 'd', long)]
+        max_depth: Option<usize>,
+    },
+    /// List every active target detector and where it was defined
+    Targets {
+        /// Directory whose .clean-files.toml is included
+        #[arg(value_name = "PATH", default_value = ".")]
+        path: PathBuf,
+    },
+}
+
+/// What `sweep` removes from the Cargo target directories it finds
+#[derive(Args, Debug, Clone)]
+#[command(group(
+    ArgGroup::new("parts")
+        .required(true)
+        .multiple(true)
+        .args(["incremental", "doc", "profile", "triple", "older_than", "uninstalled_toolchains"])
+))]
+pub struct SweepArgs {
+    /// Directory to scan (defaults to current directory)
+    #[arg(value_name = "PATH", default_value = ".")]
+    pub path: PathBuf,
+
+    /// Remove the incremental compilation caches
+    #[arg(long)]
+    pub incremental: bool,
+
+    /// Remove generated documentation
+    #[arg(long)]
+    pub doc: bool,
+
+    /// Remove everything built with this profile, e.g. release or dev (may
+    /// be repeated)
+    #[arg(long, value_name = "NAME")]
+    pub profile: Vec<String>,
+
+    /// Remove everything built for this target triple (may be repeated)
+    #[arg(long, value_name = "TRIPLE")]
+    pub triple: Vec<String>,
+
+    /// Remove compiled crates no build has used for this long, e.g. 30d
+    #[arg(long, value_name = "AGE", value_parser = parse_duration)]
+    pub older_than: Option<Duration>,
+
+    /// Remove compiled crates built by a rustc that is no longer installed
+    #[arg(long)]
+    pub uninstalled_toolchains: bool,
+
+    /// List every compiled crate removed, not just how many
+    #[arg(short, long)]
+    pub verbose: bool,
+
+    /// Maximum depth to scan (default: unlimited)
+    #[arg(short = 'd', long)]
+    pub max_depth: Option<usize>,
+
+    /// Never enter directories matching this glob (may be repeated)
+    #[arg(short = 'x', long, value_name = "GLOB")]
+    pub exclude: Vec<String>,
+
+    /// Only show what would be removed
+    #[arg(short = 'n', long)]
+    pub dry_run: bool,
+
+    /// Skip confirmation prompt
+    #[arg(short = 'y', long)]
+    pub yes: bool,
+}
+
+/// Which per-user caches `caches` looks at and what it removes
+#[derive(Args, Debug, Clone)]
+pub struct CachesArgs {
+    /// Only look at this cache (may be repeated; default: all of them)
+    #[arg(short, long = "cache", value_enum, value_name = "CACHE")]
+    pub caches: Vec<CacheType>,
+
+    /// Remove entries no build has used for this long, e.g. 90d, from the
+    /// caches whose layout allows it
+    #[arg(long, value_name = "AGE", value_parser = parse_duration)]
+    pub older_than: Option<Duration>,
+
+    /// Delete this whole cache (may be repeated); asks for its name to be
+    /// typed, even with --yes
+    #[arg(long, value_enum, value_name = "CACHE")]
+    pub drop: Vec<CacheType>,
+
+    /// List every stale entry, not just how many
+    #[arg(short, long)]
+    pub verbose: bool,
+
+    /// Only show what would be removed
+    #[arg(short = 'n', long)]
+    pub dry_run: bool,
+
+    /// Skip confirmation prompt for removing stale entries
+    #[arg(short = 'y', long)]
+    pub yes: bool,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigAction {
+    /// List the configuration files in effect and the detectors they define
+    Show {
+        /// Directory whose .clean-files.toml is included
+        #[arg(value_name = "PATH", default_value = ".")]
+        path: PathBuf,
+    },
+    /// Check configuration files for errors
+    Validate {
+        /// Files to check (default: the files that apply to the current directory)
+        #[arg(value_name = "FILE")]
+        files: Vec<PathBuf>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Colored text and progress bars
+    Human,
+    /// A versioned JSON report printed when the run is over
+    Json,
+    /// Newline-delimited JSON events printed as they happen
+    Ndjson,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GoalStrategy {
+    /// Biggest targets first
+    Largest,
+    /// Longest untouched first
+    Oldest,
+    /// Size weighted by days since the last change
+    Mixed,
+}
+
+impl From<GoalStrategy> for Strategy {
+    fn from(strategy: GoalStrategy) -> Self {
+        match strategy {
+            GoalStrategy::Largest => Strategy::Largest,
+            GoalStrategy::Oldest => Strategy::Oldest,
+            GoalStrategy::Mixed => Strategy::Mixed,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum RankBy {
+    /// Bytes on disk
+    Size,
+    /// Number of files
+    Files,
+}
+
+impl From<RankBy> for Ranking {
+    fn from(rank_by: RankBy) -> Self {
+        match rank_by {
+            RankBy::Size => Ranking::Size,
+            RankBy::Files => Ranking::Files,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
 pub enum TargetType {
     /// Node.js node_modules directories
     Node,
+    /// JS framework build caches (.next, .nuxt, .svelte-kit, .turbo,
+    /// .parcel-cache, .angular)
+    Framework,
     /// Rust target directories
     Rust,
     /// Python __pycache__ directories
     Python,
-    /// Java/Maven/Gradle target/build directories
+    /// Python virtual environments (any directory with a pyvenv.cfg)
+    Venv,
+    /// JVM build output: Maven, Gradle, sbt and Clojure target/build
+    /// directories and project caches such as .gradle and .kotlin
     Java,
+    /// .NET bin/obj directories next to a project file
+    Dotnet,
+    /// C/C++ build trees of CMake, Meson, Autotools and Bazel, whatever
+    /// their name
+    Native,
+    /// Targets declared in configuration files
+    Custom,
     /// All supported directory types
     All,
 }
@@ -55,17 +402,52 @@ impl From<TargetType> for CleanTarget {
     fn from(target: TargetType) -> Self {
         match target {
             TargetType::Node => CleanTarget::NodeModules,
+            TargetType::Framework => CleanTarget::FrameworkCache,
             TargetType::Rust => CleanTarget::RustTarget,
             TargetType::Python => CleanTarget::PythonCache,
+            TargetType::Venv => CleanTarget::PythonVenv,
             TargetType::Java => CleanTarget::JavaTarget,
+            TargetType::Dotnet => CleanTarget::DotnetBuild,
+            TargetType::Native => CleanTarget::NativeBuild,
+            TargetType::Custom => CleanTarget::Custom,
             TargetType::All => CleanTarget::All,
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CacheType {
+    /// Cargo registry: downloaded and extracted crates
+    Cargo,
+    /// npm's _cacache
+    Npm,
+    /// The local Maven repository
+    Maven,
+    /// Gradle's dependency and build caches
+    Gradle,
+    /// pip's HTTP and wheel cache
+    Pip,
+    /// The Go build cache
+    Go,
+}
+
+impl From<CacheType> for CacheKind {
+    fn from(cache: CacheType) -> Self {
+        match cache {
+            CacheType::Cargo => CacheKind::Cargo,
+            CacheType::Npm => CacheKind::Npm,
+            CacheType::Maven => CacheKind::Maven,
+            CacheType::Gradle => CacheKind::Gradle,
+            CacheType::Pip => CacheKind::Pip,
+            CacheType::Go => CacheKind::Go,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::path::Path;
 
     #[test]
     fn test_target_type_conversion() {
@@ -73,12 +455,216 @@ mod tests {
             CleanTarget::from(TargetType::Node),
             CleanTarget::NodeModules
         );
+        assert_eq!(
+            CleanTarget::from(TargetType::Framework),
+            CleanTarget::FrameworkCache
+        );
         assert_eq!(CleanTarget::from(TargetType::Rust), CleanTarget::RustTarget);
         assert_eq!(
             CleanTarget::from(TargetType::Python),
             CleanTarget::PythonCache
         );
+        assert_eq!(CleanTarget::from(TargetType::Venv), CleanTarget::PythonVenv);
         assert_eq!(CleanTarget::from(TargetType::Java), CleanTarget::JavaTarget);
+        assert_eq!(
+            CleanTarget::from(TargetType::Dotnet),
+            CleanTarget::DotnetBuild
+        );
+        assert_eq!(
+            CleanTarget::from(TargetType::Native),
+            CleanTarget::NativeBuild
+        );
+        assert_eq!(CleanTarget::from(TargetType::Custom), CleanTarget::Custom);
         assert_eq!(CleanTarget::from(TargetType::All), CleanTarget::All);
     }
+
+    #[test]
+    fn test_targets_subcommand() {
+        let cli = Cli::parse_from(["clean-files", "targets", "/tmp"]);
+        assert!(
+            matches!(cli.command, Some(Command::Targets { path }) if path == Path::new("/tmp"))
+        );
+
+        // A plain path still means "clean this directory"
+        let cli = Cli::parse_from(["clean-files", "/tmp", "--dry-run"]);
+        assert!(cli.command.is_none());
+        assert!(cli.clean.dry_run);
+        assert_eq!(cli.clean.scan.path, Path::new("/tmp"));
+        assert_eq!(cli.format, OutputFormat::Human);
+    }
+
+    #[test]
+    fn test_format_flag() {
+        let cli = Cli::parse_from(["clean-files", "--format", "ndjson", "-n"]);
+        assert_eq!(cli.format, OutputFormat::Ndjson);
+        let cli = Cli::parse_from(["clean-files", "targets", "--format", "json"]);
+        assert_eq!(cli.format, OutputFormat::Json);
+    }
+
+    #[test]
+    fn test_older_than_flag() {
+        let cli = Cli::parse_from(["clean-files", "--older-than", "2w", "--include-sources"]);
+        assert_eq!(
+            cli.clean.scan.older_than,
+            Some(Duration::from_secs(14 * 86_400))
+        );
+        assert!(cli.clean.scan.include_sources);
+        assert!(!cli.clean.scan.outside_root);
+
+        assert!(Cli::try_parse_from(["clean-files", "--older-than", "soon"]).is_err());
+        assert!(Cli::try_parse_from(["clean-files", "--include-sources"]).is_err());
+    }
+
+    #[test]
+    fn test_exclude_flag() {
+        let cli = Cli::parse_from([
+            "clean-files",
+            "scan",
+            "-x",
+            "vendor",
+            "--exclude",
+            "/srv/vm",
+        ]);
+        match cli.command {
+            Some(Command::Scan { scan, .. }) => assert_eq!(scan.exclude, ["vendor", "/srv/vm"]),
+            other => panic!("unexpected command: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_size_and_top_flags() {
+        let cli = Cli::parse_from([
+            "clean-files",
+            "scan",
+            "--min-size",
+            "1G",
+            "--min-files",
+            "10",
+            "--top",
+            "5",
+            "--rank-by",
+            "files",
+        ]);
+        match cli.command {
+            Some(Command::Scan { scan, .. }) => {
+                assert_eq!(scan.min_size, Some(1024 * 1024 * 1024));
+                assert_eq!(scan.max_size, None);
+                assert_eq!(scan.min_files, Some(10));
+                assert_eq!(scan.top, Some(5));
+                assert_eq!(scan.rank_by, RankBy::Files);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        // Ranking needs every result, which streaming never has
+        assert!(Cli::try_parse_from(["clean-files", "--top", "3", "--pipeline", "-y"]).is_err());
+        assert!(Cli::try_parse_from(["clean-files", "--rank-by", "files"]).is_err());
+    }
+
+    #[test]
+    fn test_interactive_flag() {
+        let cli = Cli::parse_from(["clean-files", "clean", "-i", "--top", "10"]);
+        assert!(matches!(cli.command, Some(Command::Clean(args)) if args.interactive));
+        assert!(Cli::try_parse_from(["clean-files", "-i", "-y"]).is_err());
+        assert!(Cli::try_parse_from(["clean-files", "-i", "--pipeline", "-n"]).is_err());
+    }
+
+    #[test]
+    fn test_sweep_subcommand() {
+        let cli = Cli::parse_from([
+            "clean-files",
+            "sweep",
+            "--incremental",
+            "--profile",
+            "release",
+            "--older-than",
+            "30d",
+        ]);
+        match cli.command {
+            Some(Command::Sweep(args)) => {
+                assert!(args.incremental && !args.doc);
+                assert_eq!(args.profile, ["release"]);
+                assert_eq!(args.older_than, Some(Duration::from_secs(30 * 86_400)));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        // Sweeping nothing in particular is a mistake, not "sweep everything"
+        assert!(Cli::try_parse_from(["clean-files", "sweep", "-n"]).is_err());
+    }
+
+    #[test]
+    fn test_caches_subcommand() {
+        let cli = Cli::parse_from([
+            "clean-files",
+            "caches",
+            "-c",
+            "cargo",
+            "--cache",
+            "go",
+            "--older-than",
+            "90d",
+            "--drop",
+            "npm",
+        ]);
+        match cli.command {
+            Some(Command::Caches(args)) => {
+                assert_eq!(args.caches, [CacheType::Cargo, CacheType::Go]);
+                assert_eq!(args.older_than, Some(Duration::from_secs(90 * 86_400)));
+                assert_eq!(CacheKind::from(args.drop[0]), CacheKind::Npm);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_ensure_free_flag() {
+        let cli = Cli::parse_from(["clean-files", "--ensure-free", "20%", "--strategy", "mixed"]);
+        assert_eq!(cli.clean.ensure_free, Some(SpaceGoal::Percent(20.0)));
+        assert_eq!(cli.clean.strategy, GoalStrategy::Mixed);
+
+        assert!(Cli::try_parse_from(["clean-files", "--ensure-free", "50G", "--trash"]).is_err());
+        assert!(Cli::try_parse_from(["clean-files", "--strategy", "oldest"]).is_err());
+    }
+
+    #[test]
+    fn test_subcommands() {
+        let cli = Cli::parse_from(["clean-files", "clean", "/tmp", "-n", "-t", "rust"]);
+        match cli.command {
+            Some(Command::Clean(args)) => {
+                assert!(args.dry_run);
+                assert_eq!(args.scan.path, Path::new("/tmp"));
+                assert!(matches!(args.scan.target, TargetType::Rust));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let cli = Cli::parse_from(["clean-files", "scan", "-o", "plan.json", "--threads", "2"]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Scan { scan, save_plan: Some(_) }) if scan.threads == 2
+        ));
+
+        let cli = Cli::parse_from(["clean-files", "config", "validate", "a.toml", "b.toml"]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Config { action: ConfigAction::Validate { files } }) if files.len() == 2
+        ));
+
+        // A plan replaces the scan, so streaming it makes no sense
+        assert!(
+            Cli::try_parse_from(["clean-files", "clean", "--plan", "p", "--pipeline"]).is_err()
+        );
+        assert!(Cli::try_parse_from(["clean-files", "explain"]).is_err());
+
+        let cli = Cli::parse_from(["clean-files", "restore", "--list"]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Restore {
+                list: true,
+                yes: false,
+                ..
+            })
+        ));
+    }
 }
@@ -0,0 +1,919 @@
+//! Subcommands other than `clean`.
+
+use crate::cli::{CachesArgs, ConfigAction, OutputFormat, ScanArgs, SweepArgs};
+use crate::output::print_report;
+use crate::terminal::{
+    ask_to_proceed, ask_to_type, print_left_out, print_target, print_workspaces,
+};
+use crate::{build_filter, build_scanner, list_targets, load_registry, validate_dir};
+use anyhow::Result;
+use clean_files::caches::{locate, measure, CacheEntry, CacheEnv, CacheKind, GlobalCache};
+use clean_files::config::{user_config_path, Config, PROJECT_CONFIG_FILE};
+use clean_files::detectors::DetectorRegistry;
+use clean_files::report::{event_line, Report, TargetStatus};
+use clean_files::sweep::{installed_toolchains, Sweep, SweepItem};
+use clean_files::trash::Trash;
+use clean_files::utils::{format_age, format_size};
+use clean_files::{CleanEvent, CleanTarget, Scanner};
+use colored::*;
+use rayon::prelude::*;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::{BTreeMap, HashSet};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// `scan`: discover and size targets without touching them
+pub fn scan(
+    args: &ScanArgs,
+    save_plan: Option<&Path>,
+    config: Option<&Path>,
+    format: OutputFormat,
+) -> Result<()> {
+    validate_dir(&args.path);
+    let registry = load_registry(config, &args.path)?;
+    let scanner = build_scanner(args, &registry);
+
+    if format == OutputFormat::Human {
+        println!(
+            "Scanning directory: {}",
+            args.path.display().to_string().cyan().bold()
+        );
+        println!();
+    }
+
+    let selection = build_filter(args).apply(scanner.scan(&args.path)?);
+    let plan = Report::from_selection(&selection);
+    let results = &selection.selected;
+
+    match format {
+        OutputFormat::Human => {
+            if results.is_empty() && selection.excluded.is_empty() {
+                println!("{}", "No directories found to clean.".yellow());
+            }
+            for result in results {
+                print_target(result, &registry);
+            }
+            let total_size: u64 = results.iter().map(|r| r.size).sum();
+            let total_files: usize = results.iter().map(|r| r.file_count).sum();
+            println!();
+            println!(
+                "Found {} directories, {} in {} files",
+                results.len().to_string().green().bold(),
+                format_size(total_size).cyan().bold(),
+                total_files.to_string().yellow().bold()
+            );
+            print_workspaces(results);
+
+            if !selection.excluded.is_empty() {
+                println!();
+                print_left_out(&selection.excluded, args.verbose, &registry);
+            }
+        }
+        OutputFormat::Json => print_report(&plan)?,
+        OutputFormat::Ndjson => {
+            for excluded in &selection.excluded {
+                println!(
+                    "{}",
+                    event_line(&CleanEvent::Excluded {
+                        target: excluded.target.clone(),
+                        reason: excluded.reason.clone(),
+                    })?
+                );
+            }
+            for target in selection.selected {
+                println!("{}", event_line(&CleanEvent::Found { target })?);
+            }
+        }
+    }
+
+    if let Some(file) = save_plan {
+        plan.save(file)?;
+        if format == OutputFormat::Human {
+            println!(
+                "Plan saved to {}; run `clean-files clean --plan {}` to delete these",
+                file.display(),
+                file.display()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// `report`: summarise a saved plan or JSON report
+pub fn report(file: &Path, format: OutputFormat) -> Result<()> {
+    let report = Report::load(file)?;
+
+    // Count and size per status and per target type
+    let mut by_status: BTreeMap<String, (usize, u64)> = BTreeMap::new();
+    let mut by_type: BTreeMap<String, (usize, u64)> = BTreeMap::new();
+    let mut by_workspace: BTreeMap<String, (usize, u64)> = BTreeMap::new();
+    for entry in &report.targets {
+        let workspace = entry
+            .target
+            .workspace
+            .as_ref()
+            .map(|root| (&mut by_workspace, root.display().to_string()));
+        for (map, key) in [
+            (&mut by_status, entry.status.key().to_string()),
+            (&mut by_type, entry.target.target_type.to_string()),
+        ]
+        .into_iter()
+        .chain(workspace)
+        {
+            let slot = map.entry(key).or_default();
+            slot.0 += 1;
+            slot.1 += entry.target.size;
+        }
+    }
+
+    if format != OutputFormat::Human {
+        let counts = |map: &BTreeMap<String, (usize, u64)>| -> serde_json::Value {
+            map.iter()
+                .map(|(k, (count, size))| (k.clone(), json!({ "count": count, "size": size })))
+                .collect::<serde_json::Map<_, _>>()
+                .into()
+        };
+        let summary = json!({
+            "version": report.version,
+            "dry_run": report.dry_run,
+            "targets": report.targets.len(),
+            "size": report.targets.iter().map(|t| t.target.size).sum::<u64>(),
+            "by_status": counts(&by_status),
+            "by_type": counts(&by_type),
+            "by_workspace": counts(&by_workspace),
+            "stats": report.stats,
+        });
+        if format == OutputFormat::Json {
+            println!("{}", serde_json::to_string_pretty(&summary)?);
+        } else {
+            println!("{}", summary);
+        }
+        return Ok(());
+    }
+
+    println!(
+        "{} {}{}",
+        "Report:".cyan().bold(),
+        file.display(),
+        if report.dry_run { " (dry run)" } else { "" }
+    );
+    println!();
+    let total: u64 = report.targets.iter().map(|t| t.target.size).sum();
+    println!(
+        "{} targets, {}",
+        report.targets.len().to_string().green().bold(),
+        format_size(total).cyan().bold()
+    );
+    for (title, map) in [
+        ("By status:", &by_status),
+        ("By type:", &by_type),
+        ("By workspace:", &by_workspace),
+    ] {
+        if map.is_empty() {
+            continue;
+        }
+        println!();
+        println!("{}", title);
+        for (key, (count, size)) in map {
+            println!(
+                "  • {:<13} {:>6}  {}",
+                key,
+                count,
+                format_size(*size).cyan()
+            );
+        }
+    }
+
+    let problems: Vec<_> = report
+        .targets
+        .iter()
+        .filter(|t| matches!(t.status, TargetStatus::Skipped | TargetStatus::Failed))
+        .collect();
+    if !problems.is_empty() {
+        println!();
+        println!("⚠️  Not deleted:");
+        for entry in problems {
+            println!(
+                "  • {}: {}",
+                entry.target.path.display(),
+                entry.reason.as_deref().unwrap_or("no reason recorded")
+            );
+        }
+    }
+    if report.cancelled {
+        println!();
+        println!(
+            "{}",
+            "The run was cancelled before anything was deleted.".yellow()
+        );
+    }
+    if report.interrupted {
+        println!();
+        println!("{}", "The run was interrupted.".yellow());
+    }
+
+    Ok(())
+}
+
+/// `config show` and `config validate`
+pub fn config(action: &ConfigAction, explicit: Option<&Path>, format: OutputFormat) -> Result<()> {
+    match action {
+        ConfigAction::Show { path } => {
+            validate_dir(path);
+            let files = Config::discover_paths(explicit, path);
+            let registry = load_registry(explicit, path)?;
+
+            if format != OutputFormat::Human {
+                let detectors: Vec<_> = registry
+                    .sources()
+                    .map(|(d, source)| {
+                        json!({
+                            "id": d.id(),
+                            "name": d.display_name(),
+                            "category": d.category(),
+                            "source": source.to_string(),
+                        })
+                    })
+                    .collect();
+                let files: Vec<_> = files.iter().map(|f| f.display().to_string()).collect();
+                let shown = json!({ "files": files, "detectors": detectors });
+                if format == OutputFormat::Json {
+                    println!("{}", serde_json::to_string_pretty(&shown)?);
+                } else {
+                    println!("{}", shown);
+                }
+                return Ok(());
+            }
+
+            println!("{}", "Configuration files:".cyan().bold());
+            if explicit.is_none() {
+                if let Some(user) = user_config_path().filter(|p| !p.is_file()) {
+                    println!("  {} {} (not found)", "-".dimmed(), user.display());
+                }
+            }
+            if !path.join(PROJECT_CONFIG_FILE).is_file() {
+                println!(
+                    "  {} {} (not found)",
+                    "-".dimmed(),
+                    path.join(PROJECT_CONFIG_FILE).display()
+                );
+            }
+            for file in &files {
+                println!("  {} {}", "✓".green(), file.display());
+            }
+            println!();
+            list_targets(explicit, path)
+        }
+        ConfigAction::Validate { files } => {
+            let files = if files.is_empty() {
+                Config::discover_paths(explicit, Path::new("."))
+            } else {
+                files.clone()
+            };
+            if files.is_empty() {
+                println!("{}", "No configuration files found.".yellow());
+                return Ok(());
+            }
+
+            // Files are applied in order, exactly as they would be loaded
+            let mut registry = DetectorRegistry::builtin();
+            let mut failed = false;
+            for file in &files {
+                let result = Config::load(file).and_then(|c| {
+                    c.apply(&mut registry)?;
+                    Ok(c.targets.len())
+                });
+                match result {
+                    Ok(count) => println!("{} {}: {} targets", "✓".green(), file.display(), count),
+                    Err(e) => {
+                        failed = true;
+                        println!("{} {}: {:#}", "✗".red(), file.display(), e);
+                    }
+                }
+            }
+
+            if failed {
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+    }
+}
+
+/// `explain`: why a directory is or is not selected
+pub fn explain(
+    path: &Path,
+    root: Option<&Path>,
+    target: CleanTarget,
+    max_depth: Option<usize>,
+    config: Option<&Path>,
+    format: OutputFormat,
+) -> Result<()> {
+    let path = path.canonicalize()?;
+    let root = match root {
+        Some(root) => root.canonicalize()?,
+        None => {
+            let cwd = std::env::current_dir()?.canonicalize()?;
+            if path.starts_with(&cwd) && path != cwd {
+                cwd
+            } else {
+                path.parent().unwrap_or(&path).to_path_buf()
+            }
+        }
+    };
+
+    let registry = load_registry(config, &root)?;
+    let mut scanner = Scanner::new(target).with_registry(registry);
+    if let Some(depth) = max_depth {
+        scanner = scanner.with_max_depth(depth);
+    }
+    let explanation = scanner.explain(&root, &path)?;
+
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&explanation)?),
+        OutputFormat::Ndjson => println!("{}", serde_json::to_string(&explanation)?),
+        OutputFormat::Human => {
+            println!("{} {}", "Path:".cyan().bold(), explanation.path.display());
+            println!("{} {}", "Root:".cyan().bold(), root.display());
+            println!();
+            for check in &explanation.checks {
+                match &check.error {
+                    None => println!("  {} {}", "✓".green(), check.detector),
+                    Some(error) => println!("  {} {}: {}", "✗".red(), check.detector, error),
+                }
+            }
+            if !explanation.checks.is_empty() {
+                println!();
+            }
+            if explanation.matched.is_some() {
+                println!("{} {}", "Selected:".green().bold(), explanation.reason);
+            } else {
+                println!("{} {}", "Not selected:".yellow().bold(), explanation.reason);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// One row of `restore` output
+#[derive(Serialize)]
+struct Restored {
+    #[serde(with = "clean_files::report::os_path")]
+    path: PathBuf,
+    deleted_at: String,
+    restored: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// `restore`: move trashed directories back
+pub fn restore(path: &Path, list: bool, yes: bool, format: OutputFormat) -> Result<()> {
+    // The directory itself may be in the trash; resolve what still exists
+    let absolute = std::path::absolute(path)?;
+    let existing = absolute
+        .ancestors()
+        .find(|p| p.exists())
+        .unwrap_or(Path::new("/"));
+    let scope = existing
+        .canonicalize()?
+        .join(absolute.strip_prefix(existing)?);
+
+    let mut trashes = vec![Trash::for_path(existing)?];
+    let home = Trash::home()?;
+    if !trashes.contains(&home) {
+        trashes.push(home);
+    }
+
+    // Newest first, and only the newest copy of each original path
+    let mut entries = Vec::new();
+    for trash in &trashes {
+        for entry in trash.entries()? {
+            if entry.original_path.starts_with(&scope) {
+                entries.push((trash, entry));
+            }
+        }
+    }
+    entries.sort_by(|a, b| b.1.deleted_at.cmp(&a.1.deleted_at));
+    let mut seen = HashSet::new();
+    entries.retain(|(_, entry)| seen.insert(entry.original_path.clone()));
+
+    let human = format == OutputFormat::Human;
+    if human {
+        if entries.is_empty() {
+            println!(
+                "{} {}",
+                "Nothing in the trash came from".yellow(),
+                scope.display()
+            );
+            return Ok(());
+        }
+        println!("{}", "Trashed directories:".cyan().bold());
+        for (_, entry) in &entries {
+            println!(
+                "  {} {} {}",
+                "•".cyan(),
+                entry.original_path.display(),
+                format!("(trashed {})", entry.deleted_at).dimmed()
+            );
+        }
+        println!();
+    }
+
+    let proceed = !list
+        && !entries.is_empty()
+        && (yes
+            || if human {
+                ask_to_proceed(&mut io::stdout())
+            } else {
+                ask_to_proceed(&mut io::stderr())
+            });
+    if !list && !proceed && human && !entries.is_empty() {
+        println!("{}", "Restore cancelled.".yellow());
+    }
+
+    let mut rows = Vec::new();
+    for (trash, entry) in &entries {
+        let error = if proceed {
+            trash.restore(entry).err().map(|e| format!("{:#}", e))
+        } else {
+            None
+        };
+        if human && proceed {
+            match &error {
+                None => println!("{} {}", "✓".green(), entry.original_path.display()),
+                Some(e) => println!("{} {}: {}", "✗".red(), entry.original_path.display(), e),
+            }
+        }
+        rows.push(Restored {
+            path: entry.original_path.clone(),
+            deleted_at: entry.deleted_at.clone(),
+            restored: proceed && error.is_none(),
+            error,
+        });
+    }
+
+    match format {
+        OutputFormat::Human => {}
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&rows)?),
+        OutputFormat::Ndjson => {
+            for row in &rows {
+                println!("{}", serde_json::to_string(row)?);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// One row of `sweep` output
+#[derive(Serialize)]
+struct Swept<'a> {
+    #[serde(with = "clean_files::report::os_path")]
+    target: PathBuf,
+    #[serde(flatten)]
+    item: &'a SweepItem,
+    removed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// `sweep`: remove parts of Cargo target directories
+pub fn sweep(args: &SweepArgs, config: Option<&Path>, format: OutputFormat) -> Result<()> {
+    validate_dir(&args.path);
+    let registry = load_registry(config, &args.path)?;
+    let mut scanner = Scanner::new(CleanTarget::RustTarget)
+        .with_registry(registry)
+        .with_excludes(args.exclude.clone());
+    if let Some(depth) = args.max_depth {
+        scanner = scanner.with_max_depth(depth);
+    }
+
+    let mut sweep = Sweep::new()
+        .with_incremental(args.incremental)
+        .with_doc(args.doc);
+    for profile in &args.profile {
+        sweep = sweep.with_profile(profile);
+    }
+    for triple in &args.triple {
+        sweep = sweep.with_triple(triple.clone());
+    }
+    if let Some(age) = args.older_than {
+        sweep = sweep.with_older_than(age);
+    }
+    if args.uninstalled_toolchains {
+        sweep = sweep.with_installed_toolchains(installed_toolchains()?);
+    }
+
+    let human = format == OutputFormat::Human;
+    if human {
+        println!(
+            "Sweeping Cargo target directories in: {}",
+            args.path.display().to_string().cyan().bold()
+        );
+        if args.dry_run {
+            println!(
+                "{}",
+                "Mode: DRY RUN (no files will be deleted)".yellow().bold()
+            );
+        }
+        println!();
+    }
+
+    // Only the parts being removed are sized, never whole targets
+    let found = Mutex::new(Vec::new());
+    scanner.discover(&args.path, |target| {
+        found.lock().unwrap().push(target);
+        true
+    })?;
+    let mut found = found.into_inner().unwrap();
+    found.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let mut plans = Vec::new();
+    for target in found {
+        if let Some(marker) = &target.kept_by {
+            if human {
+                println!(
+                    "  {} {} (kept by {})",
+                    "-".dimmed(),
+                    target.path.display(),
+                    marker
+                );
+            }
+            continue;
+        }
+        match sweep.plan(&target.path) {
+            Ok(items) if items.is_empty() => {}
+            Ok(items) => plans.push((target.path, items)),
+            Err(e) => eprintln!("{} {:#}", "Warning:".yellow().bold(), e),
+        }
+    }
+
+    let items = || plans.iter().flat_map(|(_, items)| items);
+    let total: u64 = items().map(|i| i.size).sum();
+    if human {
+        if plans.is_empty() {
+            println!("{}", "Nothing to sweep.".yellow());
+            return Ok(());
+        }
+        for (target, items) in &plans {
+            println!("{}", target.display().to_string().bold());
+            print_sweep_items(items, args.verbose);
+        }
+        println!();
+        println!(
+            "{} to remove from {} target directories",
+            format_size(total).cyan().bold(),
+            plans.len().to_string().green().bold()
+        );
+        println!();
+    }
+
+    let proceed = !args.dry_run
+        && !plans.is_empty()
+        && (args.yes
+            || if human {
+                ask_to_proceed(&mut io::stdout())
+            } else {
+                ask_to_proceed(&mut io::stderr())
+            });
+    if human && !args.dry_run && !proceed {
+        println!("{}", "Sweep cancelled.".yellow());
+    }
+
+    let mut rows = Vec::new();
+    for (target, items) in &plans {
+        for item in items {
+            let error = if proceed {
+                item.remove().err().map(|e| format!("{:#}", e))
+            } else {
+                None
+            };
+            if let (true, Some(e)) = (human, &error) {
+                println!("{} {}: {}", "✗".red(), item.name, e);
+            }
+            rows.push(Swept {
+                target: target.clone(),
+                item,
+                removed: proceed && error.is_none(),
+                error,
+            });
+        }
+    }
+
+    match format {
+        OutputFormat::Human if proceed => {
+            let freed: u64 = rows.iter().filter(|r| r.removed).map(|r| r.item.size).sum();
+            println!(
+                "{} {}",
+                "Freed".green().bold(),
+                format_size(freed).cyan().bold()
+            );
+        }
+        OutputFormat::Human => {}
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&rows)?),
+        OutputFormat::Ndjson => {
+            for row in &rows {
+                println!("{}", serde_json::to_string(row)?);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Directories one per line; compiled crates counted per reason unless
+/// `verbose`, since there are usually hundreds
+fn print_sweep_items(items: &[SweepItem], verbose: bool) {
+    let mut units: BTreeMap<&str, (usize, u64)> = BTreeMap::new();
+    for item in items {
+        let kind = item.reason.kind();
+        if !verbose && matches!(kind, "unused" | "old_toolchain") {
+            let slot = units.entry(kind).or_default();
+            slot.0 += 1;
+            slot.1 += item.size;
+            continue;
+        }
+        println!(
+            "  {} {} {} ({})",
+            "•".cyan(),
+            item.name.white().bold(),
+            item.reason.to_string().dimmed(),
+            format_size(item.size).cyan()
+        );
+    }
+    for (kind, (count, size)) in units {
+        let what = match kind {
+            "unused" => "unused compiled crates",
+            _ => "compiled crates from uninstalled toolchains",
+        };
+        println!(
+            "  {} {} {} ({})",
+            "•".cyan(),
+            count.to_string().white().bold(),
+            what,
+            format_size(size).cyan()
+        );
+    }
+}
+
+/// One cache in `caches` output
+#[derive(Serialize)]
+struct CacheRow<'a> {
+    #[serde(flatten)]
+    cache: &'a GlobalCache,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    stale: Vec<PrunedEntry<'a>>,
+    dropped: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// One stale entry of a cache in `caches` output
+#[derive(Serialize)]
+struct PrunedEntry<'a> {
+    #[serde(flatten)]
+    entry: &'a CacheEntry,
+    removed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// `caches`: size the per-user caches, prune their stale entries and drop
+/// whole caches
+pub fn caches(args: &CachesArgs, format: OutputFormat) -> Result<()> {
+    let dropping: Vec<CacheKind> = args.drop.iter().map(|&c| c.into()).collect();
+    let wanted: Vec<CacheKind> = args.caches.iter().map(|&c| c.into()).collect();
+    let mut found: Vec<GlobalCache> = locate(&CacheEnv::from_env())
+        .into_iter()
+        .filter(|c| wanted.is_empty() || wanted.contains(&c.kind) || dropping.contains(&c.kind))
+        .collect();
+    for kind in &dropping {
+        if !found.iter().any(|c| c.kind == *kind) {
+            eprintln!("{} no {} found", "Warning:".yellow().bold(), kind.name());
+        }
+    }
+    measure(&mut found);
+
+    let human = format == OutputFormat::Human;
+    if human {
+        if args.dry_run {
+            println!(
+                "{}",
+                "Mode: DRY RUN (no files will be deleted)".yellow().bold()
+            );
+            println!();
+        }
+        if found.is_empty() {
+            println!("{}", "No caches found.".yellow());
+            return Ok(());
+        }
+        println!("Per-user caches:");
+        for cache in &found {
+            println!(
+                "  {} {} {} ({}, {} files)",
+                "•".cyan(),
+                format!("{:<16}", cache.kind.name()).white().bold(),
+                cache.path.display(),
+                format_size(cache.size).cyan(),
+                cache.files
+            );
+        }
+        println!(
+            "{} in {} caches",
+            format_size(found.iter().map(|c| c.size).sum())
+                .cyan()
+                .bold(),
+            found.len().to_string().green().bold()
+        );
+    }
+
+    let now = SystemTime::now();
+    let stale: Vec<Vec<CacheEntry>> = match args.older_than {
+        Some(age) => found
+            .par_iter()
+            .map(|cache| cache.stale_entries(age, now))
+            .collect(),
+        None => vec![Vec::new(); found.len()],
+    };
+    let dropped = |cache: &GlobalCache| dropping.contains(&cache.kind);
+
+    if human {
+        if let Some(age) = args.older_than {
+            println!();
+            println!("Unused for {}:", format_age(age));
+            for (cache, entries) in found.iter().zip(&stale) {
+                print_stale_entries(cache, entries, now, args.verbose);
+            }
+        }
+        if found.iter().any(dropped) {
+            println!();
+            println!("{}", "Whole caches to delete:".red().bold());
+            for cache in found.iter().filter(|c| dropped(c)) {
+                println!(
+                    "  {} {} {} ({})",
+                    "•".red(),
+                    cache.kind.name().white().bold(),
+                    cache.path.display(),
+                    format_size(cache.size).cyan()
+                );
+            }
+        }
+        if args.older_than.is_none() && dropping.is_empty() {
+            println!();
+            println!(
+                "{}",
+                "Use --older-than AGE to prune unused entries, or --drop CACHE to delete a whole cache."
+                    .dimmed()
+            );
+        }
+        println!();
+    }
+
+    let mut out: Box<dyn io::Write> = if human {
+        Box::new(io::stdout())
+    } else {
+        Box::new(io::stderr())
+    };
+    let has_stale = stale.iter().any(|entries| !entries.is_empty());
+    let prune = !args.dry_run && has_stale && (args.yes || ask_to_proceed(&mut *out));
+    if human && !args.dry_run && has_stale && !prune {
+        println!("{}", "Pruning cancelled.".yellow());
+    }
+    // Never skipped by --yes: a whole cache takes long to download again
+    let confirmed: Vec<bool> = found
+        .iter()
+        .map(|cache| {
+            !args.dry_run
+                && dropped(cache)
+                && ask_to_type(
+                    &mut *out,
+                    &format!(
+                        "Delete the whole {} at {} ({})?",
+                        cache.kind.name(),
+                        cache.path.display(),
+                        format_size(cache.size)
+                    ),
+                    cache.kind.key(),
+                )
+        })
+        .collect();
+
+    let mut rows = Vec::new();
+    for ((cache, entries), drop) in found.iter().zip(&stale).zip(confirmed) {
+        let mut row = CacheRow {
+            cache,
+            stale: Vec::new(),
+            dropped: false,
+            error: None,
+        };
+        if drop {
+            row.error = cache.remove().err().map(|e| format!("{:#}", e));
+            row.dropped = row.error.is_none();
+        } else if human && dropped(cache) && !args.dry_run {
+            println!("Kept the {}.", cache.kind.name());
+        }
+        for entry in entries {
+            // Entries of a dropped cache are gone with it
+            let error = if prune && !drop {
+                entry.remove().err().map(|e| format!("{:#}", e))
+            } else {
+                None
+            };
+            row.stale.push(PrunedEntry {
+                entry,
+                removed: prune && !drop && error.is_none(),
+                error,
+            });
+        }
+        if human {
+            let errors = row
+                .error
+                .iter()
+                .chain(row.stale.iter().filter_map(|e| e.error.as_ref()));
+            for error in errors {
+                println!("{} {}: {}", "✗".red(), cache.kind.name(), error);
+            }
+        }
+        rows.push(row);
+    }
+
+    match format {
+        OutputFormat::Human => {
+            let freed: u64 = rows
+                .iter()
+                .map(|row| {
+                    let pruned: u64 = row
+                        .stale
+                        .iter()
+                        .filter(|e| e.removed)
+                        .map(|e| e.entry.size)
+                        .sum();
+                    pruned + if row.dropped { row.cache.size } else { 0 }
+                })
+                .sum();
+            if freed > 0 {
+                println!(
+                    "{} {}",
+                    "Freed".green().bold(),
+                    format_size(freed).cyan().bold()
+                );
+            }
+        }
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&rows)?),
+        OutputFormat::Ndjson => {
+            for row in &rows {
+                println!("{}", serde_json::to_string(row)?);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// How many entries of `cache` are stale and how large they are; each
+/// entry when `verbose`
+fn print_stale_entries(
+    cache: &GlobalCache,
+    entries: &[CacheEntry],
+    now: SystemTime,
+    verbose: bool,
+) {
+    let Some(what) = cache.kind.entries_name() else {
+        println!(
+            "  {} {}: no usage records; delete it whole with --drop {}",
+            "-".dimmed(),
+            cache.kind.name(),
+            cache.kind.key()
+        );
+        return;
+    };
+    println!(
+        "  {} {}: {} {} ({})",
+        "•".cyan(),
+        cache.kind.name().white().bold(),
+        entries.len().to_string().green(),
+        what,
+        format_size(entries.iter().map(|e| e.size).sum()).cyan()
+    );
+    if !verbose {
+        return;
+    }
+    for entry in entries {
+        let age = entry
+            .last_used
+            .and_then(|used| now.duration_since(used).ok())
+            .map(format_age)
+            .unwrap_or_default();
+        println!(
+            "      {} ({}, last used {} ago)",
+            entry.name,
+            format_size(entry.size).cyan(),
+            age
+        );
+    }
+}
@@ -0,0 +1,339 @@
+//! User configuration loaded from TOML files.
+//!
+//! Configuration is read from the user's config file
+//! (`$XDG_CONFIG_HOME/clean-files/config.toml`, falling back to
+//! `~/.config/clean-files/config.toml`) and from a `.clean-files.toml` at the
+//! root of the scanned tree. Each `[[target]]` table declares a custom
+//! cleaning target. A project file comes with the tree being scanned, so its
+//! targets are only used if the user config lists the tree in
+//! `trusted-roots`:
+//!
+//! ```toml
+//! [[target]]
+//! id = "gn-out"
+//! name = "GN/Ninja output"
+//! patterns = ["out", "out-*"]
+//! category = "custom"
+//! markers = { any-of = [".gn", "BUILD.gn"] }
+//!
+//! [[target.content]]
+//! file = "args.gn"
+//! contains = "is_debug"
+//! ```
+//!
+//! `patterns` are globs matched against the directory name. `markers` name
+//! files that must exist next to the directory (`any-of` / `all-of`, globs
+//! allowed), and each `content` check names a file inside the directory that
+//! must exist and optionally contain a string.
+//!
+//! ```toml
+//! trusted-roots = ["/home/me/work"]
+//! ```
+
+use crate::detectors::{CustomDetector, DetectorRegistry, DetectorSource};
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Name of the per-tree configuration file looked up at the scan root
+pub const PROJECT_CONFIG_FILE: &str = ".clean-files.toml";
+
+/// One parsed configuration file
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[non_exhaustive]
+pub struct Config {
+    /// Custom cleaning targets, declared as `[[target]]` tables
+    #[serde(default, rename = "target")]
+    pub targets: Vec<CustomTarget>,
+
+    /// Absolute paths whose project config files may declare targets
+    #[serde(default, rename = "trusted-roots")]
+    pub trusted_roots: Vec<PathBuf>,
+
+    /// File this configuration was read from
+    #[serde(skip)]
+    pub source: Option<PathBuf>,
+}
+
+/// A user-defined cleaning target
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+#[non_exhaustive]
+pub struct CustomTarget {
+    /// Unique detector id
+    pub id: String,
+    /// Display name; defaults to the id
+    pub name: Option<String>,
+    /// Globs matched against the directory name
+    pub patterns: Vec<String>,
+    /// Category key such as `custom`, `java` or `node`
+    #[serde(default = "default_category")]
+    pub category: String,
+    /// Files required next to the directory
+    #[serde(default)]
+    pub markers: Markers,
+    /// Checks on files inside the directory
+    #[serde(default, rename = "content")]
+    pub contents: Vec<ContentCheck>,
+}
+
+/// Sibling marker files; globs such as `*.csproj` are allowed
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+#[non_exhaustive]
+pub struct Markers {
+    /// At least one of these must exist
+    #[serde(default)]
+    pub any_of: Vec<String>,
+    /// All of these must exist
+    #[serde(default)]
+    pub all_of: Vec<String>,
+}
+
+/// A file inside the matched directory that must exist
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[non_exhaustive]
+pub struct ContentCheck {
+    /// Path relative to the matched directory
+    pub file: String,
+    /// Text the file must contain
+    pub contains: Option<String>,
+}
+
+fn default_category() -> String {
+    "custom".to_string()
+}
+
+impl Config {
+    /// Parse a configuration file
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file: {}", path.display()))?;
+        let mut config: Config = toml::from_str(&text)
+            .with_context(|| format!("Invalid config file: {}", path.display()))?;
+        if let Some(relative) = config.trusted_roots.iter().find(|p| !p.is_absolute()) {
+            bail!(
+                "Trusted root '{}' in {} is not an absolute path",
+                relative.display(),
+                path.display()
+            );
+        }
+        config.source = Some(path.to_path_buf());
+        Ok(config)
+    }
+
+    /// Find and load the configuration files that apply to a scan of `root`
+    ///
+    /// An `explicit` path replaces the user config file and must exist. The
+    /// project file at `root` may only declare targets if the user config
+    /// trusts `root`, and may never extend `trusted-roots` itself.
+    pub fn discover(explicit: Option<&Path>, root: &Path) -> Result<Vec<Self>> {
+        let mut configs: Vec<Self> = Vec::new();
+        for path in Self::discover_paths(explicit, root) {
+            let config = Self::load(&path)?;
+            if Some(path.as_path()) != explicit && path == root.join(PROJECT_CONFIG_FILE) {
+                if !config.trusted_roots.is_empty() {
+                    bail!(
+                        "{} sets trusted-roots, which only the user config may do",
+                        path.display()
+                    );
+                }
+                if !config.targets.is_empty() && !configs.iter().any(|c| c.trusts(root)) {
+                    bail!(
+                        "{} declares targets, but {} is not in trusted-roots of your config \
+                         (add it there, or pass the file with --config)",
+                        path.display(),
+                        root.display()
+                    );
+                }
+            }
+            configs.push(config);
+        }
+        Ok(configs)
+    }
+
+    /// Whether `trusted-roots` covers `root`
+    fn trusts(&self, root: &Path) -> bool {
+        let Ok(root) = root.canonicalize() else {
+            return false;
+        };
+        self.trusted_roots
+            .iter()
+            .filter_map(|trusted| trusted.canonicalize().ok())
+            .any(|trusted| root.starts_with(trusted))
+    }
+
+    /// The files [`discover`](Config::discover) considers, in order
+    pub fn discover_paths(explicit: Option<&Path>, root: &Path) -> Vec<PathBuf> {
+        let mut paths = Vec::new();
+
+        match explicit {
+            Some(path) => paths.push(path.to_path_buf()),
+            None => paths.extend(user_config_path().filter(|p| p.is_file())),
+        }
+
+        // Passing the project file with --config trusts it; don't load it twice
+        let project = root.join(PROJECT_CONFIG_FILE);
+        let explicit = explicit.and_then(|path| path.canonicalize().ok());
+        if project.is_file() && project.canonicalize().ok() != explicit {
+            paths.push(project);
+        }
+
+        paths
+    }
+
+    /// Register this file's custom targets after the existing detectors
+    pub fn apply(&self, registry: &mut DetectorRegistry) -> Result<()> {
+        let source = match &self.source {
+            Some(path) => DetectorSource::Config(path.clone()),
+            None => DetectorSource::Library,
+        };
+
+        for target in &self.targets {
+            if let Some((_, existing)) = registry.sources().find(|(d, _)| d.id() == target.id) {
+                bail!(
+                    "Target id '{}' in {} is already defined ({})",
+                    target.id,
+                    source,
+                    existing
+                );
+            }
+
+            let detector = CustomDetector::from_config(target)
+                .with_context(|| format!("Invalid target '{}' in {}", target.id, source))?;
+            registry.register_from(detector, source.clone());
+        }
+
+        Ok(())
+    }
+}
+
+/// Location of the per-user configuration file
+pub fn user_config_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            if cfg!(windows) {
+                std::env::var_os("APPDATA").map(PathBuf::from)
+            } else {
+                std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config"))
+            }
+        })?;
+
+    Some(base.join("clean-files").join("config.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::CleanTarget;
+    use tempfile::TempDir;
+
+    const SAMPLE: &str = r#"
+[[target]]
+id = "gn-out"
+name = "GN output"
+patterns = ["out", "out-*"]
+markers = { any-of = [".gn", "BUILD.gn"] }
+
+[[target.content]]
+file = "args.gn"
+contains = "is_debug"
+"#;
+
+    #[test]
+    fn test_load_and_apply() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        fs::write(&path, SAMPLE).unwrap();
+
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.targets.len(), 1);
+        assert_eq!(config.targets[0].markers.any_of, vec![".gn", "BUILD.gn"]);
+
+        let mut registry = DetectorRegistry::builtin();
+        config.apply(&mut registry).unwrap();
+        let (detector, source) = registry.sources().last().unwrap();
+        assert_eq!(detector.id(), "gn-out");
+        assert_eq!(detector.display_name(), "GN output");
+        assert_eq!(detector.category(), CleanTarget::Custom);
+        assert_eq!(source, &DetectorSource::Config(path));
+    }
+
+    #[test]
+    fn test_rejects_bad_config() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.toml");
+
+        // Unknown keys are errors rather than silently ignored
+        fs::write(
+            &path,
+            "[[target]]\nid = \"x\"\npatterns = [\"x\"]\nmarker = 1\n",
+        )
+        .unwrap();
+        assert!(Config::load(&path).is_err());
+
+        // Ids must not shadow built-in detectors
+        fs::write(
+            &path,
+            "[[target]]\nid = \"rust-target\"\npatterns = [\"x\"]\nmarkers = { all-of = [\"a\"] }\n",
+        )
+        .unwrap();
+        let config = Config::load(&path).unwrap();
+        assert!(config.apply(&mut DetectorRegistry::builtin()).is_err());
+    }
+
+    #[test]
+    fn test_discover_project_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let explicit = temp_dir.path().join("explicit.toml");
+        fs::write(&explicit, "").unwrap();
+        fs::write(temp_dir.path().join(PROJECT_CONFIG_FILE), SAMPLE).unwrap();
+
+        // A cloned tree cannot declare what gets deleted in it
+        let error = Config::discover(Some(&explicit), temp_dir.path()).unwrap_err();
+        assert!(error.to_string().contains("not in trusted-roots"));
+
+        fs::write(
+            &explicit,
+            format!(
+                "trusted-roots = [{:?}]\n",
+                temp_dir.path().to_str().unwrap()
+            ),
+        )
+        .unwrap();
+        let configs = Config::discover(Some(&explicit), temp_dir.path()).unwrap();
+        assert_eq!(configs.len(), 2);
+        assert!(configs[0].targets.is_empty());
+        assert_eq!(configs[1].targets[0].id, "gn-out");
+
+        // Nor trust itself
+        fs::write(
+            temp_dir.path().join(PROJECT_CONFIG_FILE),
+            format!(
+                "trusted-roots = [{:?}]\n",
+                temp_dir.path().to_str().unwrap()
+            ),
+        )
+        .unwrap();
+        fs::write(&explicit, "").unwrap();
+        assert!(Config::discover(Some(&explicit), temp_dir.path()).is_err());
+
+        fs::write(&explicit, "trusted-roots = [\"work\"]\n").unwrap();
+        assert!(Config::load(&explicit).is_err());
+
+        // Passing it explicitly is the other way to opt in
+        let project = temp_dir.path().join(PROJECT_CONFIG_FILE);
+        fs::write(&project, SAMPLE).unwrap();
+        let configs = Config::discover(Some(&project), temp_dir.path()).unwrap();
+        assert_eq!(configs.len(), 1);
+        assert_eq!(configs[0].targets[0].id, "gn-out");
+
+        let missing = temp_dir.path().join("missing.toml");
+        assert!(Config::discover(Some(&missing), temp_dir.path()).is_err());
+    }
+}
@@ -0,0 +1,166 @@
+use super::TargetDetector;
+use crate::config::{ContentCheck, CustomTarget};
+use crate::types::CleanTarget;
+use anyhow::{bail, Result};
+use globset::{Glob, GlobMatcher, GlobSet, GlobSetBuilder};
+use std::fs;
+use std::path::Path;
+
+/// Detector built from a `[[target]]` table in a configuration file
+pub struct CustomDetector {
+    id: String,
+    name: String,
+    category: CleanTarget,
+    patterns: GlobSet,
+    any_of: Vec<GlobMatcher>,
+    all_of: Vec<GlobMatcher>,
+    contents: Vec<ContentCheck>,
+}
+
+impl CustomDetector {
+    /// Validate a configured target and compile its patterns
+    pub fn from_config(target: &CustomTarget) -> Result<Self> {
+        if target.patterns.is_empty() {
+            bail!("at least one directory name pattern is required");
+        }
+        // Without any marker a pattern like `out` would match far too much
+        if target.markers.any_of.is_empty()
+            && target.markers.all_of.is_empty()
+            && target.contents.is_empty()
+        {
+            bail!("at least one marker file or content check is required");
+        }
+
+        let category: CleanTarget = target.category.parse().map_err(anyhow::Error::msg)?;
+        if category == CleanTarget::All {
+            bail!("'all' is not a category");
+        }
+
+        let mut patterns = GlobSetBuilder::new();
+        for pattern in &target.patterns {
+            patterns.add(Glob::new(pattern)?);
+        }
+
+        let compile = |markers: &[String]| -> Result<Vec<GlobMatcher>> {
+            markers
+                .iter()
+                .map(|m| Ok(Glob::new(m)?.compile_matcher()))
+                .collect()
+        };
+
+        Ok(Self {
+            id: target.id.clone(),
+            name: target.name.clone().unwrap_or_else(|| target.id.clone()),
+            category,
+            patterns: patterns.build()?,
+            any_of: compile(&target.markers.any_of)?,
+            all_of: compile(&target.markers.all_of)?,
+            contents: target.contents.clone(),
+        })
+    }
+}
+
+impl TargetDetector for CustomDetector {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn display_name(&self) -> &str {
+        &self.name
+    }
+
+    fn category(&self) -> CleanTarget {
+        self.category
+    }
+
+    fn matches_name(&self, dir_name: &str) -> bool {
+        self.patterns.is_match(dir_name)
+    }
+
+    fn verify(&self, path: &Path) -> Result<(), String> {
+        let parent = path
+            .parent()
+            .ok_or_else(|| "directory has no parent".to_string())?;
+
+        if !self.any_of.is_empty() || !self.all_of.is_empty() {
+            let siblings: Vec<String> = fs::read_dir(parent)
+                .map_err(|e| format!("cannot list {}: {}", parent.display(), e))?
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .collect();
+            let present = |m: &GlobMatcher| siblings.iter().any(|name| m.is_match(name));
+
+            if !self.any_of.is_empty() && !self.any_of.iter().any(present) {
+                let names: Vec<_> = self.any_of.iter().map(|m| m.glob().glob()).collect();
+                return Err(format!("no {} next to it", names.join(" or ")));
+            }
+            if let Some(missing) = self.all_of.iter().find(|m| !present(m)) {
+                return Err(format!("no {} next to it", missing.glob().glob()));
+            }
+        }
+
+        for check in &self.contents {
+            let file = path.join(&check.file);
+            if !file.is_file() {
+                return Err(format!("{} does not exist", check.file));
+            }
+            if let Some(needle) = &check.contains {
+                let bytes =
+                    fs::read(&file).map_err(|e| format!("cannot read {}: {}", check.file, e))?;
+                if !String::from_utf8_lossy(&bytes).contains(needle.as_str()) {
+                    return Err(format!("{} does not contain '{}'", check.file, needle));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use tempfile::TempDir;
+
+    fn detector(toml: &str) -> CustomDetector {
+        let config: Config = toml::from_str(toml).unwrap();
+        CustomDetector::from_config(&config.targets[0]).unwrap()
+    }
+
+    #[test]
+    fn test_custom_markers_and_contents() {
+        let detector = detector(
+            r#"
+[[target]]
+id = "cache"
+patterns = [".build-cache"]
+markers = { any-of = ["*.bzl", "BUILD"], all-of = ["WORKSPACE"] }
+content = [{ file = "VERSION", contains = "cache-v2" }]
+"#,
+        );
+
+        let temp_dir = TempDir::new().unwrap();
+        let cache = temp_dir.path().join(".build-cache");
+        fs::create_dir(&cache).unwrap();
+        assert!(detector.matches_name(".build-cache"));
+        assert!(!detector.matches_name("build-cache"));
+
+        assert!(detector.verify(&cache).is_err());
+        fs::write(temp_dir.path().join("defs.bzl"), "").unwrap();
+        assert!(detector.verify(&cache).unwrap_err().contains("WORKSPACE"));
+        fs::write(temp_dir.path().join("WORKSPACE"), "").unwrap();
+        assert!(detector.verify(&cache).unwrap_err().contains("VERSION"));
+        fs::write(cache.join("VERSION"), "cache-v1").unwrap();
+        assert!(detector.verify(&cache).is_err());
+        fs::write(cache.join("VERSION"), "cache-v2").unwrap();
+        assert!(detector.verify(&cache).is_ok());
+    }
+
+    #[test]
+    fn test_custom_requires_a_marker() {
+        let config: Config =
+            toml::from_str("[[target]]\nid = \"out\"\npatterns = [\"out\"]\n").unwrap();
+        assert!(CustomDetector::from_config(&config.targets[0]).is_err());
+    }
+}
@@ -0,0 +1,232 @@
+use super::TargetDetector;
+use crate::types::CleanTarget;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Project files whose directory gets `bin` and `obj`
+const PROJECT_EXTENSIONS: &[&str] = &["csproj", "fsproj", "vbproj"];
+
+/// .NET `bin` and `obj` next to a project file or `Directory.Build.props`
+///
+/// Only output of a restored SDK build verifies: `obj` must hold NuGet's
+/// `project.assets.json` or a `*.nuget.g.props`, and `bin` needs such an
+/// `obj` beside it. With `UseArtifactsOutput` both live in an `artifacts`
+/// directory next to `Directory.Build.props`, and the restore output sits
+/// one level down, in `obj/<project>`. Projects listed in a solution
+/// (`.sln` or `.slnx`) are reported with the solution's directory as their
+/// workspace.
+pub struct DotnetDetector;
+
+impl TargetDetector for DotnetDetector {
+    fn id(&self) -> &str {
+        "dotnet-build"
+    }
+
+    fn display_name(&self) -> &str {
+        ".NET bin/obj"
+    }
+
+    fn category(&self) -> CleanTarget {
+        CleanTarget::DotnetBuild
+    }
+
+    fn matches_name(&self, dir_name: &str) -> bool {
+        matches!(dir_name, "bin" | "obj")
+    }
+
+    fn verify(&self, path: &Path) -> Result<(), String> {
+        let project = path.parent().ok_or("directory has no parent")?;
+        let build_props = |dir: &Path| dir.join("Directory.Build.props").is_file();
+        // `UseArtifactsOutput` builds into `artifacts/` beside the props file
+        let artifacts_root = project.parent().filter(|_| project.ends_with("artifacts"));
+        if project_files(project).is_empty()
+            && !build_props(project)
+            && !artifacts_root.is_some_and(build_props)
+        {
+            return Err(
+                "no *.csproj, *.fsproj, *.vbproj or Directory.Build.props next to it".to_string(),
+            );
+        }
+
+        let obj = project.join("obj");
+        if restored(&obj) {
+            Ok(())
+        } else if path == obj {
+            Err("no project.assets.json or *.nuget.g.props inside".to_string())
+        } else {
+            Err("no restored obj (project.assets.json or *.nuget.g.props) next to it".to_string())
+        }
+    }
+
+    fn workspace(&self, target: &Path) -> Option<PathBuf> {
+        solution_dir(target.parent()?)
+    }
+}
+
+/// The project files in `dir`
+fn project_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| PROJECT_EXTENSIONS.contains(&e))
+                && path.is_file()
+        })
+        .collect()
+}
+
+/// Whether `obj` (or a project directory in it) holds NuGet restore output
+fn restored(obj: &Path) -> bool {
+    let has_restore_output = |dir: &Path| {
+        dir.join("project.assets.json").is_file()
+            || fs::read_dir(dir).is_ok_and(|entries| {
+                entries
+                    .flatten()
+                    .any(|e| e.file_name().to_string_lossy().ends_with(".nuget.g.props"))
+            })
+    };
+    if has_restore_output(obj) {
+        return true;
+    }
+    fs::read_dir(obj).is_ok_and(|entries| {
+        entries
+            .flatten()
+            .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+            .any(|e| has_restore_output(&e.path()))
+    })
+}
+
+/// Directory of the nearest solution listing a project in `project_dir`
+fn solution_dir(project_dir: &Path) -> Option<PathBuf> {
+    let projects: Vec<PathBuf> = project_files(project_dir)
+        .into_iter()
+        .filter_map(|p| p.canonicalize().ok())
+        .collect();
+    if projects.is_empty() {
+        return None;
+    }
+
+    for dir in project_dir.ancestors() {
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let listed = match path.extension().and_then(|e| e.to_str()) {
+                Some("sln") | Some("slnx") => solution_projects(&path),
+                _ => continue,
+            };
+            if listed
+                .iter()
+                .filter_map(|p| dir.join(p).canonicalize().ok())
+                .any(|p| projects.contains(&p))
+            {
+                return Some(dir.to_path_buf());
+            }
+        }
+    }
+    None
+}
+
+/// Project paths listed in a `.sln` or `.slnx`, relative to its directory
+///
+/// `.sln` lines look like
+/// `Project("{GUID}") = "Api", "src\Api\Api.csproj", "{GUID}"`;
+/// `.slnx` files have `<Project Path="src/Api/Api.csproj" />`.
+fn solution_projects(solution: &Path) -> Vec<PathBuf> {
+    let Ok(text) = fs::read_to_string(solution) else {
+        return Vec::new();
+    };
+    let mut projects = Vec::new();
+    for line in text.lines().map(str::trim) {
+        let path = if line.starts_with("Project(") {
+            line.split('"').nth(5)
+        } else if line.starts_with("<Project ") {
+            line.split("Path=\"")
+                .nth(1)
+                .and_then(|rest| rest.split('"').next())
+        } else {
+            None
+        };
+        if let Some(path) = path {
+            projects.push(PathBuf::from(path.replace('\\', "/")));
+        }
+    }
+    projects
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_bin_and_obj_need_restore_output() {
+        let temp_dir = TempDir::new().unwrap();
+        let project = temp_dir.path().join("Api");
+        fs::create_dir_all(project.join("bin/Debug")).unwrap();
+        fs::create_dir_all(project.join("obj")).unwrap();
+        assert!(DotnetDetector.verify(&project.join("obj")).is_err());
+
+        fs::write(
+            project.join("Api.csproj"),
+            "<Project Sdk=\"Microsoft.NET.Sdk\" />",
+        )
+        .unwrap();
+        let error = DotnetDetector.verify(&project.join("bin")).unwrap_err();
+        assert!(error.contains("no restored obj"));
+
+        fs::write(project.join("obj/Api.csproj.nuget.g.props"), "<Project />").unwrap();
+        assert!(DotnetDetector.verify(&project.join("obj")).is_ok());
+        assert!(DotnetDetector.verify(&project.join("bin")).is_ok());
+
+        // A `bin` of scripts next to something else is not .NET output
+        let scripts = temp_dir.path().join("tools/bin");
+        fs::create_dir_all(&scripts).unwrap();
+        assert!(DotnetDetector.verify(&scripts).is_err());
+    }
+
+    #[test]
+    fn test_artifacts_layout_and_solutions() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("artifacts/obj/Api")).unwrap();
+        fs::create_dir_all(root.join("artifacts/bin/Api")).unwrap();
+        fs::create_dir_all(root.join("output/obj/Api")).unwrap();
+        fs::write(root.join("artifacts/obj/Api/project.assets.json"), "{}").unwrap();
+        fs::write(root.join("output/obj/Api/project.assets.json"), "{}").unwrap();
+        assert!(DotnetDetector.verify(&root.join("artifacts/bin")).is_err());
+
+        fs::write(root.join("Directory.Build.props"), "<Project />").unwrap();
+        assert!(DotnetDetector.verify(&root.join("artifacts/bin")).is_ok());
+        assert!(DotnetDetector.verify(&root.join("artifacts/obj")).is_ok());
+        assert!(DotnetDetector.verify(&root.join("output/obj")).is_err());
+
+        for project in ["src/Api", "src/Web", "tests/Api.Tests"] {
+            fs::create_dir_all(root.join(project)).unwrap();
+        }
+        fs::write(root.join("src/Api/Api.csproj"), "").unwrap();
+        fs::write(root.join("src/Web/Web.fsproj"), "").unwrap();
+        fs::write(root.join("tests/Api.Tests/Api.Tests.csproj"), "").unwrap();
+        fs::write(
+            root.join("App.sln"),
+            "Project(\"{FAE04EC0-301F-11D3-BF4B-00C04F79EFBC}\") = \"Api\", \"src\\Api\\Api.csproj\", \"{1}\"\nEndProject\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("src/Web.slnx"),
+            "<Solution>\n  <Project Path=\"Web/Web.fsproj\" />\n</Solution>\n",
+        )
+        .unwrap();
+
+        let workspace = |dir: &str| DotnetDetector.workspace(&root.join(dir).join("obj"));
+        assert_eq!(workspace("src/Api").as_deref(), Some(root));
+        assert_eq!(workspace("src/Web").as_deref(), Some(&*root.join("src")));
+        assert_eq!(workspace("tests/Api.Tests"), None);
+    }
+}
@@ -0,0 +1,287 @@
+use super::{require_sibling, TargetDetector};
+use crate::types::CleanTarget;
+use std::path::{Path, PathBuf};
+
+/// Gradle settings and build scripts, one of which sits in every Gradle project
+const GRADLE_SCRIPTS: &[&str] = &[
+    "settings.gradle",
+    "settings.gradle.kts",
+    "build.gradle",
+    "build.gradle.kts",
+];
+
+/// Maven `target` next to a `pom.xml`
+pub struct MavenDetector;
+
+impl TargetDetector for MavenDetector {
+    fn id(&self) -> &str {
+        "maven-target"
+    }
+
+    fn display_name(&self) -> &str {
+        "Maven target"
+    }
+
+    fn category(&self) -> CleanTarget {
+        CleanTarget::JavaTarget
+    }
+
+    fn matches_name(&self, dir_name: &str) -> bool {
+        dir_name == "target"
+    }
+
+    fn verify(&self, path: &Path) -> Result<(), String> {
+        require_sibling(path, &["pom.xml"])
+    }
+
+    fn prunes(&self, dir_name: &str) -> bool {
+        self.matches_name(dir_name)
+    }
+}
+
+/// Gradle `build` (or legacy `target`) next to a Gradle build script
+pub struct GradleDetector;
+
+impl TargetDetector for GradleDetector {
+    fn id(&self) -> &str {
+        "gradle-build"
+    }
+
+    fn display_name(&self) -> &str {
+        "Gradle build"
+    }
+
+    fn category(&self) -> CleanTarget {
+        CleanTarget::JavaTarget
+    }
+
+    fn matches_name(&self, dir_name: &str) -> bool {
+        matches!(dir_name, "build" | "target")
+    }
+
+    fn verify(&self, path: &Path) -> Result<(), String> {
+        require_sibling(path, &["build.gradle", "build.gradle.kts"])
+    }
+
+    fn prunes(&self, dir_name: &str) -> bool {
+        self.matches_name(dir_name)
+    }
+}
+
+/// The project-level `.gradle` cache next to a Gradle build script
+///
+/// Refused when it holds `caches/modules-2` or `wrapper/dists`: that is a
+/// Gradle user home, not a project cache.
+pub struct GradleCacheDetector;
+
+impl TargetDetector for GradleCacheDetector {
+    fn id(&self) -> &str {
+        "gradle-cache"
+    }
+
+    fn display_name(&self) -> &str {
+        "Gradle project cache"
+    }
+
+    fn category(&self) -> CleanTarget {
+        CleanTarget::JavaTarget
+    }
+
+    fn matches_name(&self, dir_name: &str) -> bool {
+        dir_name == ".gradle"
+    }
+
+    fn verify(&self, path: &Path) -> Result<(), String> {
+        require_sibling(path, GRADLE_SCRIPTS)?;
+        if path.join("caches/modules-2").is_dir() || path.join("wrapper/dists").is_dir() {
+            return Err("it is a Gradle user home, not a project cache".to_string());
+        }
+        Ok(())
+    }
+
+    fn prunes(&self, dir_name: &str) -> bool {
+        self.matches_name(dir_name)
+    }
+}
+
+/// The `.kotlin` directory the Kotlin Gradle plugin keeps next to the build
+pub struct KotlinCacheDetector;
+
+impl TargetDetector for KotlinCacheDetector {
+    fn id(&self) -> &str {
+        "kotlin-cache"
+    }
+
+    fn display_name(&self) -> &str {
+        "Kotlin project data"
+    }
+
+    fn category(&self) -> CleanTarget {
+        CleanTarget::JavaTarget
+    }
+
+    fn matches_name(&self, dir_name: &str) -> bool {
+        dir_name == ".kotlin"
+    }
+
+    fn verify(&self, path: &Path) -> Result<(), String> {
+        require_sibling(path, GRADLE_SCRIPTS)
+    }
+
+    fn prunes(&self, dir_name: &str) -> bool {
+        self.matches_name(dir_name)
+    }
+}
+
+/// sbt `target` and `project/target`, and the `.bloop` and `.metals`
+/// directories of Scala tooling, in an sbt build
+///
+/// Subprojects have no `build.sbt` of their own, so their `target` verifies
+/// when it holds sbt's `streams` and an enclosing directory is an sbt build.
+pub struct SbtDetector;
+
+impl TargetDetector for SbtDetector {
+    fn id(&self) -> &str {
+        "sbt-build"
+    }
+
+    fn display_name(&self) -> &str {
+        "sbt build"
+    }
+
+    fn category(&self) -> CleanTarget {
+        CleanTarget::JavaTarget
+    }
+
+    fn matches_name(&self, dir_name: &str) -> bool {
+        matches!(dir_name, "target" | ".bloop" | ".metals")
+    }
+
+    fn verify(&self, path: &Path) -> Result<(), String> {
+        let parent = path.parent().ok_or("directory has no parent")?;
+        if is_sbt_build(parent) {
+            return Ok(());
+        }
+        if path.ends_with("target")
+            && path.join("streams").is_dir()
+            && parent.ancestors().skip(1).any(is_sbt_build)
+        {
+            return Ok(());
+        }
+        Err("no build.sbt next to it".to_string())
+    }
+
+    fn prunes(&self, dir_name: &str) -> bool {
+        self.matches_name(dir_name)
+    }
+
+    fn keep_dirs(&self, _target: &Path, project: &Path) -> Vec<PathBuf> {
+        // `project/target` and subprojects belong to the enclosing build
+        let mut dirs = vec![project.to_path_buf()];
+        dirs.extend(
+            project
+                .ancestors()
+                .skip(1)
+                .find(|a| a.join("build.sbt").is_file())
+                .map(Path::to_path_buf),
+        );
+        dirs
+    }
+}
+
+/// An sbt build root, or its `project/` meta-build (which nests)
+fn is_sbt_build(dir: &Path) -> bool {
+    dir.join("build.sbt").is_file()
+        || dir.join("project/build.properties").is_file()
+        || (dir.ends_with("project") && dir.parent().is_some_and(is_sbt_build))
+}
+
+/// Clojure `.cpcache` next to a `deps.edn`, and `target` next to a
+/// `deps.edn` or Leiningen `project.clj`
+pub struct ClojureDetector;
+
+impl TargetDetector for ClojureDetector {
+    fn id(&self) -> &str {
+        "clojure-build"
+    }
+
+    fn display_name(&self) -> &str {
+        "Clojure build"
+    }
+
+    fn category(&self) -> CleanTarget {
+        CleanTarget::JavaTarget
+    }
+
+    fn matches_name(&self, dir_name: &str) -> bool {
+        matches!(dir_name, ".cpcache" | "target")
+    }
+
+    fn verify(&self, path: &Path) -> Result<(), String> {
+        if path.ends_with(".cpcache") {
+            require_sibling(path, &["deps.edn"])
+        } else {
+            require_sibling(path, &["deps.edn", "project.clj"])
+        }
+    }
+
+    fn prunes(&self, dir_name: &str) -> bool {
+        self.matches_name(dir_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_sbt_targets() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        for dir in [
+            "target",
+            "project/target",
+            "project/project/target",
+            "core/target/streams",
+            "docs/target",
+            ".metals",
+        ] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        assert!(SbtDetector.verify(&root.join("target")).is_err());
+
+        fs::write(root.join("build.sbt"), "lazy val core = project\n").unwrap();
+        for dir in [
+            "target",
+            "project/target",
+            "project/project/target",
+            "core/target",
+            ".metals",
+        ] {
+            assert!(SbtDetector.verify(&root.join(dir)).is_ok(), "{}", dir);
+        }
+        // Not written by sbt, so maybe not a build directory at all
+        assert!(SbtDetector.verify(&root.join("docs/target")).is_err());
+    }
+
+    #[test]
+    fn test_gradle_cache_is_not_user_home() {
+        let temp_dir = TempDir::new().unwrap();
+        let project = temp_dir.path();
+        let cache = project.join(".gradle");
+        fs::create_dir_all(cache.join("8.10/checksums")).unwrap();
+        fs::create_dir_all(project.join(".kotlin/sessions")).unwrap();
+        assert!(GradleCacheDetector.verify(&cache).is_err());
+
+        fs::write(project.join("settings.gradle.kts"), "").unwrap();
+        assert!(GradleCacheDetector.verify(&cache).is_ok());
+        assert!(KotlinCacheDetector.verify(&project.join(".kotlin")).is_ok());
+
+        // A build script in the home directory does not make ~/.gradle a cache
+        fs::create_dir_all(cache.join("wrapper/dists")).unwrap();
+        let error = GradleCacheDetector.verify(&cache).unwrap_err();
+        assert!(error.contains("user home"));
+    }
+}
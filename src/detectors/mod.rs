@@ -0,0 +1,446 @@
+//! Pluggable rules that decide which directories are cleanable.
+//!
+//! A [`TargetDetector`] owns everything the tool needs to know about one kind
+//! of artifact directory: which directory names to look at, which marker files
+//! prove the directory is regenerable, whether traversal should skip the
+//! directory's contents, and how to present it. The [`Scanner`](crate::Scanner)
+//! uses detectors to find targets and the [`Cleaner`](crate::Cleaner) asks the
+//! same detector to re-verify a target right before deleting it.
+//!
+//! The built-in Node.js, Rust, Python, JVM, .NET and C/C++ rules are ordinary
+//! detectors registered by [`DetectorRegistry::builtin`]. Targets declared in
+//! a configuration file become [`CustomDetector`]s (see [`crate::config`]),
+//! and embedding code can register its own:
+//!
+//! ```
+//! use clean_files::detectors::{DetectorRegistry, TargetDetector};
+//! use clean_files::CleanTarget;
+//! use std::path::Path;
+//!
+//! struct ZigCache;
+//!
+//! impl TargetDetector for ZigCache {
+//!     fn id(&self) -> &str {
+//!         "zig-cache"
+//!     }
+//!     fn display_name(&self) -> &str {
+//!         "Zig cache"
+//!     }
+//!     fn category(&self) -> CleanTarget {
+//!         CleanTarget::Custom
+//!     }
+//!     fn matches_name(&self, dir_name: &str) -> bool {
+//!         matches!(dir_name, ".zig-cache" | "zig-cache")
+//!     }
+//!     fn verify(&self, path: &Path) -> Result<(), String> {
+//!         clean_files::detectors::require_sibling(path, &["build.zig"])
+//!     }
+//! }
+//!
+//! let registry = DetectorRegistry::builtin().with(ZigCache);
+//! assert!(registry.get("zig-cache").is_some());
+//! ```
+
+mod custom;
+mod dotnet;
+mod java;
+mod native;
+mod node;
+mod python;
+mod rust;
+
+pub use custom::CustomDetector;
+pub use dotnet::DotnetDetector;
+pub use java::{
+    ClojureDetector, GradleCacheDetector, GradleDetector, KotlinCacheDetector, MavenDetector,
+    SbtDetector,
+};
+pub use native::NativeBuildDetector;
+pub use node::{
+    workspace_root as js_workspace_root, FrameworkCacheDetector, NodeModulesDetector,
+    PackageManager, YarnBerryDetector,
+};
+pub use python::{PythonCacheDetector, PythonVenvDetector};
+pub use rust::{CargoEnv, RustTargetDetector};
+
+use crate::keep::{project_keep_marker, KeepMarker};
+use crate::types::{CleanTarget, ScanResult};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Rules for recognising one kind of cleanable directory
+pub trait TargetDetector: Send + Sync {
+    /// Stable identifier recorded in [`ScanResult::detector`]
+    fn id(&self) -> &str;
+
+    /// Human-readable name used in summaries and listings
+    fn display_name(&self) -> &str;
+
+    /// Category used for `--target` filtering and statistics
+    fn category(&self) -> CleanTarget;
+
+    /// Cheap check on the directory name alone
+    ///
+    /// [`verify`](TargetDetector::verify) is only called for directories whose
+    /// name matches, or whose contents do.
+    fn matches_name(&self, dir_name: &str) -> bool;
+
+    /// Cheap check on the contents, for targets recognised by a file inside
+    /// rather than by name
+    fn matches_contents(&self, _path: &Path) -> bool {
+        false
+    }
+
+    /// Check the marker files that prove `path` is a regenerable artifact
+    ///
+    /// Called while scanning and again right before deletion. The error
+    /// explains which marker is missing.
+    fn verify(&self, path: &Path) -> Result<(), String>;
+
+    /// Whether traversal should skip the contents of a directory with this
+    /// name even when it did not verify
+    ///
+    /// Matched targets are never descended into regardless of this setting.
+    fn prunes(&self, _dir_name: &str) -> bool {
+        false
+    }
+
+    /// Target directories outside the usual layout that belong to the
+    /// project in `dir`, such as a build directory set in a config file
+    ///
+    /// Called for the directories the scanner enters that hold one of the
+    /// [`link_markers`](TargetDetector::link_markers). The paths may lie
+    /// outside the scanned tree and are only reported if they
+    /// [`verify`](TargetDetector::verify).
+    fn linked_targets(&self, _dir: &Path) -> Vec<PathBuf> {
+        Vec::new()
+    }
+
+    /// Files that make a directory worth asking for its
+    /// [`linked_targets`](TargetDetector::linked_targets), such as a
+    /// manifest; empty means every directory is asked
+    fn link_markers(&self) -> &[&str] {
+        &[]
+    }
+
+    /// Project directories whose [keep markers](crate::keep) protect
+    /// `target`
+    ///
+    /// `project` is the directory that owns it: its parent, or the project
+    /// a [linked target](TargetDetector::linked_targets) was found from.
+    /// Detectors whose targets are shared by several projects, or sit away
+    /// from the manifest, add the other owners.
+    fn keep_dirs(&self, _target: &Path, project: &Path) -> Vec<PathBuf> {
+        vec![project.to_path_buf()]
+    }
+
+    /// Root of the monorepo the target at `target` belongs to, if any
+    ///
+    /// Reported with each result so that workspaces can be summarised as a
+    /// whole.
+    fn workspace(&self, _target: &Path) -> Option<PathBuf> {
+        None
+    }
+}
+
+/// Where a registered detector was defined
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DetectorSource {
+    /// Shipped with this crate
+    BuiltIn,
+    /// Declared in a configuration file
+    Config(PathBuf),
+    /// Registered through the library API
+    Library,
+}
+
+impl fmt::Display for DetectorSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DetectorSource::BuiltIn => f.write_str("built-in"),
+            DetectorSource::Config(path) => write!(f, "{}", path.display()),
+            DetectorSource::Library => f.write_str("library"),
+        }
+    }
+}
+
+/// Ordered collection of detectors; earlier registrations win
+#[derive(Clone, Default)]
+pub struct DetectorRegistry {
+    detectors: Vec<(Arc<dyn TargetDetector>, DetectorSource)>,
+}
+
+impl DetectorRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry with the Node.js, Yarn, JS framework, Rust, Python,
+    /// virtualenv, JVM, .NET and C/C++ detectors
+    pub fn builtin() -> Self {
+        let mut registry = Self::new();
+        registry.register_from(NodeModulesDetector, DetectorSource::BuiltIn);
+        registry.register_from(YarnBerryDetector, DetectorSource::BuiltIn);
+        registry.register_from(FrameworkCacheDetector, DetectorSource::BuiltIn);
+        registry.register_from(RustTargetDetector::new(), DetectorSource::BuiltIn);
+        registry.register_from(MavenDetector, DetectorSource::BuiltIn);
+        registry.register_from(GradleDetector, DetectorSource::BuiltIn);
+        registry.register_from(GradleCacheDetector, DetectorSource::BuiltIn);
+        registry.register_from(KotlinCacheDetector, DetectorSource::BuiltIn);
+        registry.register_from(SbtDetector, DetectorSource::BuiltIn);
+        registry.register_from(ClojureDetector, DetectorSource::BuiltIn);
+        registry.register_from(DotnetDetector, DetectorSource::BuiltIn);
+        registry.register_from(NativeBuildDetector, DetectorSource::BuiltIn);
+        registry.register_from(PythonCacheDetector, DetectorSource::BuiltIn);
+        registry.register_from(PythonVenvDetector, DetectorSource::BuiltIn);
+        registry
+    }
+
+    /// Add a detector after the existing ones
+    pub fn register(&mut self, detector: impl TargetDetector + 'static) {
+        self.register_from(detector, DetectorSource::Library);
+    }
+
+    /// Add a detector and record where it was defined
+    pub fn register_from(
+        &mut self,
+        detector: impl TargetDetector + 'static,
+        source: DetectorSource,
+    ) {
+        self.detectors.push((Arc::new(detector), source));
+    }
+
+    /// Builder form of [`register`](DetectorRegistry::register)
+    pub fn with(mut self, detector: impl TargetDetector + 'static) -> Self {
+        self.register(detector);
+        self
+    }
+
+    /// Iterate over detectors in priority order
+    pub fn iter(&self) -> impl Iterator<Item = &dyn TargetDetector> {
+        self.detectors.iter().map(|(d, _)| d.as_ref())
+    }
+
+    /// Iterate over detectors together with where they were defined
+    pub fn sources(&self) -> impl Iterator<Item = (&dyn TargetDetector, &DetectorSource)> {
+        self.detectors
+            .iter()
+            .map(|(d, source)| (d.as_ref(), source))
+    }
+
+    /// Look up a detector by its id
+    pub fn get(&self, id: &str) -> Option<&dyn TargetDetector> {
+        self.iter().find(|d| d.id() == id)
+    }
+
+    /// First detector whose name matches and whose markers verify
+    pub fn identify(&self, dir_name: &str, path: &Path) -> Option<&dyn TargetDetector> {
+        self.iter().find(|d| {
+            (d.matches_name(dir_name) || d.matches_contents(path)) && d.verify(path).is_ok()
+        })
+    }
+
+    /// Detectors whose name or contents check accepts the directory at
+    /// `path`, in priority order
+    pub fn candidates<'a>(
+        &'a self,
+        dir_name: &'a str,
+        path: &'a Path,
+    ) -> impl Iterator<Item = &'a dyn TargetDetector> {
+        self.iter()
+            .filter(move |d| d.matches_name(dir_name) || d.matches_contents(path))
+    }
+
+    /// Display name of the detector behind a result, falling back to its
+    /// category name
+    pub fn display_name<'a>(&'a self, result: &'a ScanResult) -> &'a str {
+        result
+            .detector
+            .as_deref()
+            .and_then(|id| self.get(id))
+            .map(|d| d.display_name())
+            .unwrap_or_else(|| result.target_type.name())
+    }
+
+    /// Targets elsewhere that belong to the project in `dir`, with the
+    /// detector that verified each
+    ///
+    /// `has_file` tells whether `dir` holds a file of the given name, so
+    /// that detectors whose [link markers](TargetDetector::link_markers)
+    /// are absent are not asked at all.
+    pub fn linked_targets(
+        &self,
+        dir: &Path,
+        has_file: impl Fn(&str) -> bool,
+    ) -> Vec<(&dyn TargetDetector, PathBuf)> {
+        self.iter()
+            .filter(|d| {
+                let markers = d.link_markers();
+                markers.is_empty() || markers.iter().any(|m| has_file(m))
+            })
+            .flat_map(|d| d.linked_targets(dir).into_iter().map(move |path| (d, path)))
+            .filter(|(d, path)| d.verify(path).is_ok())
+            .collect()
+    }
+
+    /// The keep marker protecting a result, looked up in its parent and in
+    /// every project its detector names in
+    /// [`keep_dirs`](TargetDetector::keep_dirs)
+    pub fn keep_marker(&self, result: &ScanResult) -> Option<KeepMarker> {
+        let parent = result.path.parent()?;
+        let project = result.project.as_deref().unwrap_or(parent);
+        let owners = match result.detector.as_deref().and_then(|id| self.get(id)) {
+            Some(detector) => detector.keep_dirs(&result.path, project),
+            None => vec![project.to_path_buf()],
+        };
+        std::iter::once(parent)
+            .chain(owners.iter().map(PathBuf::as_path))
+            .find_map(project_keep_marker)
+    }
+
+    /// Whether any detector wants the contents of `dir_name` skipped
+    pub fn prunes(&self, dir_name: &str) -> bool {
+        self.iter().any(|d| d.prunes(dir_name))
+    }
+
+    /// Check that a result loaded from a plan file is still what it claims
+    ///
+    /// Plans can be edited by hand, so the path is identified again from
+    /// scratch and must be found by the detector and category it records.
+    /// Entries that name no detector, or one that is not registered, are
+    /// refused.
+    pub fn reidentify(&self, result: &ScanResult) -> Result<(), String> {
+        let id = result
+            .detector
+            .as_deref()
+            .ok_or("the plan entry names no detector")?;
+        if self.get(id).is_none() {
+            return Err(format!("no detector registered with id '{}'", id));
+        }
+        let name = result
+            .path
+            .file_name()
+            .map(|n| n.to_string_lossy())
+            .unwrap_or_default();
+        match self.identify(&name, &result.path) {
+            Some(d) if d.id() == id && d.category() == result.target_type => Ok(()),
+            Some(d) => Err(format!(
+                "it is a {} ({}), not what the plan says",
+                d.display_name(),
+                d.id()
+            )),
+            None => Err(format!("'{}' no longer identifies it", id)),
+        }
+    }
+
+    /// Re-run marker verification for a scan result
+    ///
+    /// Results that do not name their detector are accepted if any detector of
+    /// the same category verifies them, and refused if there is none.
+    pub fn verify(&self, result: &ScanResult) -> Result<(), String> {
+        match &result.detector {
+            Some(id) => match self.get(id) {
+                Some(detector) => detector.verify(&result.path),
+                None => Err(format!("no detector registered with id '{}'", id)),
+            },
+            None => {
+                let mut candidates = self
+                    .iter()
+                    .filter(|d| d.category() == result.target_type)
+                    .peekable();
+                if candidates.peek().is_none() {
+                    return Err(format!(
+                        "no detector for category '{}'",
+                        result.target_type.key()
+                    ));
+                }
+                let mut last_error = String::new();
+                for detector in candidates {
+                    match detector.verify(&result.path) {
+                        Ok(()) => return Ok(()),
+                        Err(e) => last_error = e,
+                    }
+                }
+                Err(last_error)
+            }
+        }
+    }
+}
+
+/// Succeed if the parent of `path` contains any of `markers`
+pub fn require_sibling(path: &Path, markers: &[&str]) -> Result<(), String> {
+    let parent = match path.parent() {
+        Some(p) => p,
+        None => return Err("directory has no parent".to_string()),
+    };
+
+    if markers.iter().any(|m| parent.join(m).exists()) {
+        Ok(())
+    } else {
+        Err(format!("no {} next to it", markers.join(" or ")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_builtin_identify() {
+        let temp_dir = TempDir::new().unwrap();
+        let project = temp_dir.path();
+        fs::write(project.join("pom.xml"), "<project/>").unwrap();
+        let target = project.join("target");
+        fs::create_dir(&target).unwrap();
+
+        let registry = DetectorRegistry::builtin();
+        let detector = registry.identify("target", &target).unwrap();
+        assert_eq!(detector.id(), "maven-target");
+        assert_eq!(detector.category(), CleanTarget::JavaTarget);
+
+        // Rust wins once Cargo.toml shows up, because it is registered first
+        fs::write(project.join("Cargo.toml"), "[package]").unwrap();
+        let detector = registry.identify("target", &target).unwrap();
+        assert_eq!(detector.id(), "rust-target");
+    }
+
+    #[test]
+    fn test_unverified_name_is_pruned_but_not_identified() {
+        let temp_dir = TempDir::new().unwrap();
+        let build = temp_dir.path().join("build");
+        fs::create_dir(&build).unwrap();
+
+        let registry = DetectorRegistry::builtin();
+        assert!(registry.identify("build", &build).is_none());
+        assert!(registry.prunes("build"));
+        assert!(!registry.prunes("src"));
+    }
+
+    #[test]
+    fn test_verify_uses_recorded_detector() {
+        let temp_dir = TempDir::new().unwrap();
+        let node_modules = temp_dir.path().join("node_modules");
+        fs::create_dir(&node_modules).unwrap();
+
+        let registry = DetectorRegistry::builtin();
+        let result = ScanResult::new(node_modules.clone(), CleanTarget::NodeModules)
+            .with_detector("node-modules");
+        assert!(registry.verify(&result).is_err());
+
+        fs::write(temp_dir.path().join("package.json"), "{}").unwrap();
+        assert!(registry.verify(&result).is_ok());
+
+        let unknown = result.with_detector("does-not-exist");
+        assert!(registry.verify(&unknown).is_err());
+
+        // Nothing can vouch for a result without a detector of its category
+        let orphan = ScanResult::new(node_modules, CleanTarget::Custom);
+        let error = registry.verify(&orphan).unwrap_err();
+        assert!(error.contains("no detector for category"));
+    }
+}
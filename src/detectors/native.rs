@@ -0,0 +1,274 @@
+use super::TargetDetector;
+use crate::platform::process_exists;
+use crate::types::CleanTarget;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Files that mark the root of a Bazel workspace
+const BAZEL_WORKSPACE: &[&str] = &["MODULE.bazel", "WORKSPACE.bazel", "WORKSPACE"];
+
+/// Out-of-tree C and C++ build directories, whatever their name
+///
+/// Recognised by what the build system writes into them: CMake's
+/// `CMakeCache.txt`, Meson's `meson-info/`, or the `config.status` of an
+/// Autotools `configure`. Each records its source tree, which must still
+/// exist with its `CMakeLists.txt`, `meson.build` or `configure`, and must
+/// not lie inside the build directory, so in-source builds never verify.
+///
+/// Bazel's output base is reported as a
+/// [linked target](TargetDetector::linked_targets) of the workspace whose
+/// `bazel-*` convenience symlinks point into it. It verifies if its
+/// `DO_NOT_BUILD_HERE` names an existing workspace and no Bazel server is
+/// running for it. Output bases live under `~/.cache/bazel`, outside the
+/// checkout, so a scan only reports them with
+/// [`with_outside_root`](crate::Scanner::with_outside_root).
+pub struct NativeBuildDetector;
+
+impl TargetDetector for NativeBuildDetector {
+    fn id(&self) -> &str {
+        "native-build"
+    }
+
+    fn display_name(&self) -> &str {
+        "C/C++ build tree"
+    }
+
+    fn category(&self) -> CleanTarget {
+        CleanTarget::NativeBuild
+    }
+
+    fn matches_name(&self, _dir_name: &str) -> bool {
+        // Build directories are named anything: `build`, `out`, `_b`...
+        false
+    }
+
+    fn matches_contents(&self, path: &Path) -> bool {
+        path.join("CMakeCache.txt").is_file()
+            || path.join("meson-info").is_dir()
+            || path.join("config.status").is_file()
+            || path.join("DO_NOT_BUILD_HERE").is_file()
+    }
+
+    fn verify(&self, path: &Path) -> Result<(), String> {
+        match recorded_source(path)? {
+            Recorded::Tree(source, marker) => check_source(path, &source, marker),
+            Recorded::Bazel(workspace) => verify_output_base(path, &workspace),
+        }
+    }
+
+    fn keep_dirs(&self, target: &Path, project: &Path) -> Vec<PathBuf> {
+        // The source tree or workspace decides, wherever the build lives
+        let source = match recorded_source(target) {
+            Ok(Recorded::Tree(source, _)) => source,
+            Ok(Recorded::Bazel(workspace)) => workspace,
+            Err(_) => return vec![project.to_path_buf()],
+        };
+        vec![project.to_path_buf(), source]
+    }
+
+    fn link_markers(&self) -> &[&str] {
+        BAZEL_WORKSPACE
+    }
+
+    fn linked_targets(&self, dir: &Path) -> Vec<PathBuf> {
+        if !BAZEL_WORKSPACE.iter().any(|name| dir.join(name).is_file()) {
+            return Vec::new();
+        }
+        // `bazel-out` first, then `bazel-bin`, `bazel-<workspace>`...
+        let mut links: Vec<PathBuf> = fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|e| e.file_name().to_string_lossy().starts_with("bazel-"))
+            .filter(|e| e.file_type().is_ok_and(|t| t.is_symlink()))
+            .map(|e| e.path())
+            .collect();
+        links.sort_by_key(|link| !link.ends_with("bazel-out"));
+        links
+            .iter()
+            .filter_map(|link| fs::read_link(link).ok())
+            .find_map(|target| {
+                let target = dir.join(target);
+                target
+                    .ancestors()
+                    .find(|a| a.join("DO_NOT_BUILD_HERE").is_file())
+                    .and_then(|base| base.canonicalize().ok())
+            })
+            .into_iter()
+            .collect()
+    }
+}
+
+/// Where a build directory says it came from
+enum Recorded {
+    /// A source tree, with the file that must still be in it
+    Tree(PathBuf, &'static str),
+    /// The workspace of a Bazel output base
+    Bazel(PathBuf),
+}
+
+/// Read the source tree or workspace a build directory records
+fn recorded_source(path: &Path) -> Result<Recorded, String> {
+    if let Ok(cache) = fs::read_to_string(path.join("CMakeCache.txt")) {
+        let source = cache
+            .lines()
+            .find_map(|l| l.strip_prefix("CMAKE_HOME_DIRECTORY:INTERNAL="))
+            .ok_or("CMakeCache.txt does not name a source directory")?;
+        Ok(Recorded::Tree(PathBuf::from(source), "CMakeLists.txt"))
+    } else if let Ok(info) = fs::read_to_string(path.join("meson-info/meson-info.json")) {
+        let info = serde_json::from_str::<serde_json::Value>(&info)
+            .map_err(|_| "meson-info.json is not valid JSON".to_string())?;
+        let source = info["directories"]["source"]
+            .as_str()
+            .ok_or("meson-info.json does not name a source directory")?;
+        Ok(Recorded::Tree(PathBuf::from(source), "meson.build"))
+    } else if let Ok(status) = fs::read_to_string(path.join("config.status")) {
+        // Relative to the build directory, `.` for an in-tree build
+        let source = status
+            .lines()
+            .find_map(|l| l.strip_prefix("srcdir="))
+            .map(|s| s.trim_matches(|c| c == '\'' || c == '"'))
+            .ok_or("config.status does not name a source directory")?;
+        Ok(Recorded::Tree(path.join(source), "configure"))
+    } else if let Ok(workspace) = fs::read_to_string(path.join("DO_NOT_BUILD_HERE")) {
+        Ok(Recorded::Bazel(PathBuf::from(workspace.trim())))
+    } else {
+        Err("no CMakeCache.txt, meson-info, config.status or DO_NOT_BUILD_HERE inside".to_string())
+    }
+}
+
+/// Check that `build` was configured from a `source` that is still there
+fn check_source(build: &Path, source: &Path, marker: &str) -> Result<(), String> {
+    let source = source
+        .canonicalize()
+        .map_err(|_| format!("its source directory {} is gone", source.display()))?;
+    if !source.join(marker).is_file() {
+        return Err(format!(
+            "its source directory {} has no {}",
+            source.display(),
+            marker
+        ));
+    }
+    let build = build.canonicalize().map_err(|e| e.to_string())?;
+    if source.starts_with(&build) {
+        return Err(format!(
+            "in-source build: the source directory {} is inside it",
+            source.display()
+        ));
+    }
+    Ok(())
+}
+
+/// Check a Bazel output base against the workspace it was created for
+fn verify_output_base(base: &Path, workspace: &Path) -> Result<(), String> {
+    if !BAZEL_WORKSPACE
+        .iter()
+        .any(|name| workspace.join(name).is_file())
+    {
+        return Err(format!(
+            "DO_NOT_BUILD_HERE names {}, which is not a Bazel workspace",
+            workspace.display()
+        ));
+    }
+    let pid = fs::read_to_string(base.join("server/server.pid.txt"))
+        .ok()
+        .and_then(|pid| pid.trim().parse::<u32>().ok());
+    match pid {
+        Some(pid) if process_exists(pid) => {
+            Err(format!("its Bazel server (pid {}) is running", pid))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_cmake_and_meson_cross_check() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let source = root.join("engine");
+        let build = root.join("out-debug");
+        fs::create_dir_all(&source).unwrap();
+        fs::create_dir_all(&build).unwrap();
+        let cache = format!(
+            "# This is the CMakeCache file.\nCMAKE_HOME_DIRECTORY:INTERNAL={}\n",
+            source.display()
+        );
+        fs::write(build.join("CMakeCache.txt"), &cache).unwrap();
+        assert!(NativeBuildDetector.matches_contents(&build));
+        let error = NativeBuildDetector.verify(&build).unwrap_err();
+        assert!(error.contains("has no CMakeLists.txt"));
+
+        fs::write(source.join("CMakeLists.txt"), "project(engine)\n").unwrap();
+        assert!(NativeBuildDetector.verify(&build).is_ok());
+
+        // Configured in the source tree: deleting it would delete the sources
+        fs::write(source.join("CMakeCache.txt"), &cache).unwrap();
+        let error = NativeBuildDetector.verify(&source).unwrap_err();
+        assert!(error.contains("in-source build"));
+
+        let meson = source.join("builddir");
+        fs::create_dir_all(meson.join("meson-info")).unwrap();
+        fs::write(
+            meson.join("meson-info/meson-info.json"),
+            format!(r#"{{"directories": {{"source": "{}"}}}}"#, source.display()),
+        )
+        .unwrap();
+        assert!(NativeBuildDetector.verify(&meson).is_err());
+        fs::write(source.join("meson.build"), "project('engine')\n").unwrap();
+        assert!(NativeBuildDetector.verify(&meson).is_ok());
+    }
+
+    #[test]
+    fn test_autotools_out_of_tree_only() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("libfoo");
+        let build = temp_dir.path().join("_build");
+        fs::create_dir_all(&source).unwrap();
+        fs::create_dir_all(&build).unwrap();
+        fs::write(source.join("configure"), "#!/bin/sh\n").unwrap();
+        fs::write(
+            build.join("config.status"),
+            "#! /bin/sh\nsrcdir='../libfoo'\n",
+        )
+        .unwrap();
+        assert!(NativeBuildDetector.verify(&build).is_ok());
+
+        fs::write(source.join("config.status"), "#! /bin/sh\nsrcdir='.'\n").unwrap();
+        assert!(NativeBuildDetector.verify(&source).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_bazel_output_base() {
+        let temp_dir = TempDir::new().unwrap();
+        let workspace = temp_dir.path().join("repo");
+        let base = temp_dir.path().join("cache/_bazel_me/0123abcd");
+        let out = base.join("execroot/_main/bazel-out");
+        fs::create_dir_all(&workspace).unwrap();
+        fs::create_dir_all(&out).unwrap();
+        fs::write(workspace.join("MODULE.bazel"), "").unwrap();
+        fs::write(base.join("DO_NOT_BUILD_HERE"), workspace.to_str().unwrap()).unwrap();
+        std::os::unix::fs::symlink(&out, workspace.join("bazel-out")).unwrap();
+
+        let base = base.canonicalize().unwrap();
+        assert_eq!(
+            NativeBuildDetector.linked_targets(&workspace),
+            std::slice::from_ref(&base)
+        );
+        assert!(NativeBuildDetector.verify(&base).is_ok());
+
+        fs::create_dir_all(base.join("server")).unwrap();
+        fs::write(
+            base.join("server/server.pid.txt"),
+            std::process::id().to_string(),
+        )
+        .unwrap();
+        let error = NativeBuildDetector.verify(&base).unwrap_err();
+        assert!(error.contains("server"));
+    }
+}
@@ -0,0 +1,534 @@
+use super::{require_sibling, TargetDetector};
+use crate::git::Repository;
+use crate::types::CleanTarget;
+use globset::GlobBuilder;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// What npm, pnpm, Yarn and Bun leave inside a `node_modules` they installed
+const INSTALL_STATE: &[&str] = &[
+    ".package-lock.json",
+    ".modules.yaml",
+    ".pnpm",
+    ".yarn-integrity",
+    ".yarn-state.yml",
+    ".bun",
+];
+
+/// `node_modules` next to a `package.json`, or holding a package manager's
+/// install state inside a workspace member
+///
+/// The install state (npm's `.package-lock.json`, pnpm's `.modules.yaml` and
+/// `.pnpm` virtual store, Yarn's `.yarn-integrity` or `.yarn-state.yml`,
+/// Bun's `.bun` store) catches installs whose manifest is elsewhere, but
+/// only where a workspace root above lists the directory as a member:
+/// global installs such as `/usr/lib/node_modules` carry the same state.
+/// Targets inside an npm, Yarn, pnpm or Bun workspace are reported with
+/// the workspace root.
+pub struct NodeModulesDetector;
+
+impl TargetDetector for NodeModulesDetector {
+    fn id(&self) -> &str {
+        "node-modules"
+    }
+
+    fn display_name(&self) -> &str {
+        "Node.js node_modules"
+    }
+
+    fn category(&self) -> CleanTarget {
+        CleanTarget::NodeModules
+    }
+
+    fn matches_name(&self, dir_name: &str) -> bool {
+        dir_name == "node_modules"
+    }
+
+    fn verify(&self, path: &Path) -> Result<(), String> {
+        require_sibling(path, &["package.json"]).or_else(|sibling| {
+            if !INSTALL_STATE.iter().any(|name| path.join(name).exists()) {
+                return Err(format!(
+                    "{}, and no package manager's install state inside",
+                    sibling
+                ));
+            }
+            match path.parent().and_then(workspace_root) {
+                Some(_) => Ok(()),
+                None => Err(format!(
+                    "{}, and no workspace lists it as a member",
+                    sibling
+                )),
+            }
+        })
+    }
+
+    fn prunes(&self, dir_name: &str) -> bool {
+        self.matches_name(dir_name)
+    }
+
+    fn workspace(&self, target: &Path) -> Option<PathBuf> {
+        workspace_root(target.parent()?)
+    }
+}
+
+/// Yarn Berry's `.yarn/cache` and `.yarn/unplugged`
+///
+/// The project next to `.yarn` needs a `package.json` and a `.yarnrc.yml`
+/// or Plug'n'Play loader (`.pnp.cjs`, `.pnp.js`). A cache checked into git
+/// for zero-installs is not a build output and never verifies.
+///
+/// The loaders themselves are only markers. They are files that
+/// `yarn install` rewrites, not directories, and are never reported.
+pub struct YarnBerryDetector;
+
+impl TargetDetector for YarnBerryDetector {
+    fn id(&self) -> &str {
+        "yarn-berry"
+    }
+
+    fn display_name(&self) -> &str {
+        "Yarn Berry cache"
+    }
+
+    fn category(&self) -> CleanTarget {
+        CleanTarget::NodeModules
+    }
+
+    fn matches_name(&self, dir_name: &str) -> bool {
+        matches!(dir_name, "cache" | "unplugged")
+    }
+
+    fn verify(&self, path: &Path) -> Result<(), String> {
+        let Some(yarn_dir) = path.parent().filter(|p| p.ends_with(".yarn")) else {
+            return Err("not inside a .yarn directory".to_string());
+        };
+        require_sibling(yarn_dir, &["package.json"])?;
+        require_sibling(yarn_dir, &[".yarnrc.yml", ".pnp.cjs", ".pnp.js"])?;
+
+        match Repository::discover(path) {
+            Ok(Some(repo)) => match repo.tracked_below(path) {
+                0 => Ok(()),
+                files => Err(format!(
+                    "{} files in it are checked into git (zero-installs)",
+                    files
+                )),
+            },
+            Ok(None) => Ok(()),
+            Err(e) => Err(format!("could not check git: {:#}", e)),
+        }
+    }
+
+    fn workspace(&self, target: &Path) -> Option<PathBuf> {
+        workspace_root(target.parent()?.parent()?)
+    }
+}
+
+/// A framework or build tool that keeps a cache next to `package.json`
+struct Framework {
+    /// The cache directory
+    dir: &'static str,
+    /// Packages that bring it in
+    packages: &'static [&'static str],
+    /// Config files that prove it is used; a trailing `*` matches any
+    /// extension
+    configs: &'static [&'static str],
+}
+
+const FRAMEWORKS: &[Framework] = &[
+    Framework {
+        dir: ".next",
+        packages: &["next"],
+        configs: &["next.config.*"],
+    },
+    Framework {
+        dir: ".nuxt",
+        packages: &["nuxt"],
+        configs: &["nuxt.config.*"],
+    },
+    Framework {
+        dir: ".svelte-kit",
+        packages: &["@sveltejs/kit"],
+        configs: &["svelte.config.*"],
+    },
+    Framework {
+        dir: ".turbo",
+        packages: &["turbo"],
+        configs: &["turbo.json"],
+    },
+    Framework {
+        dir: ".parcel-cache",
+        packages: &["parcel", "@parcel/core"],
+        configs: &[".parcelrc"],
+    },
+    Framework {
+        dir: ".angular",
+        packages: &["@angular/cli", "@angular/core"],
+        configs: &["angular.json"],
+    },
+];
+
+/// Build caches of Next.js (`.next`), Nuxt (`.nuxt`), SvelteKit
+/// (`.svelte-kit`), Turborepo (`.turbo`), Parcel (`.parcel-cache`) and the
+/// Angular CLI (`.angular`)
+///
+/// The project next to the cache must have the framework's config file, or
+/// a `package.json` depending on it. Turborepo writes `.turbo` into every
+/// workspace member, so for it the workspace root counts as well.
+pub struct FrameworkCacheDetector;
+
+impl TargetDetector for FrameworkCacheDetector {
+    fn id(&self) -> &str {
+        "js-framework-cache"
+    }
+
+    fn display_name(&self) -> &str {
+        "JS framework cache"
+    }
+
+    fn category(&self) -> CleanTarget {
+        CleanTarget::FrameworkCache
+    }
+
+    fn matches_name(&self, dir_name: &str) -> bool {
+        FRAMEWORKS.iter().any(|f| f.dir == dir_name)
+    }
+
+    fn verify(&self, path: &Path) -> Result<(), String> {
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        let framework = FRAMEWORKS
+            .iter()
+            .find(|f| f.dir == name)
+            .ok_or("not a framework cache")?;
+        let project = path.parent().ok_or("directory has no parent")?;
+        let uses =
+            |dir: &Path| has_config(dir, framework.configs) || depends_on(dir, framework.packages);
+        let root = (framework.dir == ".turbo")
+            .then(|| workspace_root(project))
+            .flatten();
+        if uses(project) || root.is_some_and(|root| uses(&root)) {
+            Ok(())
+        } else {
+            Err(format!(
+                "no {} next to it, and package.json does not depend on {}",
+                framework.configs.join(" or "),
+                framework.packages.join(" or ")
+            ))
+        }
+    }
+
+    fn prunes(&self, dir_name: &str) -> bool {
+        self.matches_name(dir_name)
+    }
+
+    fn workspace(&self, target: &Path) -> Option<PathBuf> {
+        workspace_root(target.parent()?)
+    }
+}
+
+fn has_config(dir: &Path, configs: &[&str]) -> bool {
+    configs.iter().any(|config| match config.strip_suffix('*') {
+        Some(prefix) => fs::read_dir(dir).is_ok_and(|entries| {
+            entries
+                .flatten()
+                .any(|e| e.file_name().to_string_lossy().starts_with(prefix))
+        }),
+        None => dir.join(config).is_file(),
+    })
+}
+
+/// Whether the `package.json` in `dir` lists any of `packages` as a
+/// dependency of any kind
+fn depends_on(dir: &Path, packages: &[&str]) -> bool {
+    let Ok(text) = fs::read_to_string(dir.join("package.json")) else {
+        return false;
+    };
+    let Ok(package) = serde_json::from_str::<serde_json::Value>(&text) else {
+        return false;
+    };
+    [
+        "dependencies",
+        "devDependencies",
+        "peerDependencies",
+        "optionalDependencies",
+    ]
+    .iter()
+    .filter_map(|field| package.get(field)?.as_object())
+    .any(|deps| packages.iter().any(|p| deps.contains_key(*p)))
+}
+
+/// Package manager a JavaScript project uses, judged by its lock file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PackageManager {
+    /// `package-lock.json`
+    Npm,
+    /// `yarn.lock` without a `.yarnrc.yml`
+    YarnClassic,
+    /// `yarn.lock` with a `.yarnrc.yml`
+    YarnBerry,
+    /// `pnpm-lock.yaml`
+    Pnpm,
+    /// `bun.lock` or `bun.lockb`
+    Bun,
+}
+
+impl PackageManager {
+    /// The package manager of the project in `dir`, if it has a lock file
+    pub fn detect(dir: &Path) -> Option<Self> {
+        let has = |name: &str| dir.join(name).is_file();
+        if has("pnpm-lock.yaml") {
+            Some(PackageManager::Pnpm)
+        } else if has("bun.lock") || has("bun.lockb") {
+            Some(PackageManager::Bun)
+        } else if has("yarn.lock") && has(".yarnrc.yml") {
+            Some(PackageManager::YarnBerry)
+        } else if has("yarn.lock") {
+            Some(PackageManager::YarnClassic)
+        } else if has("package-lock.json") {
+            Some(PackageManager::Npm)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for PackageManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PackageManager::Npm => "npm",
+            PackageManager::YarnClassic => "Yarn",
+            PackageManager::YarnBerry => "Yarn Berry",
+            PackageManager::Pnpm => "pnpm",
+            PackageManager::Bun => "Bun",
+        })
+    }
+}
+
+/// Root of the JavaScript workspace the project in `dir` belongs to
+///
+/// The nearest directory at or above `dir` that declares workspaces, with
+/// `pnpm-workspace.yaml` or the `workspaces` field of `package.json`,
+/// decides: it is the root if it is `dir` itself or lists `dir` as a
+/// member. Projects outside any workspace have no root.
+pub fn workspace_root(dir: &Path) -> Option<PathBuf> {
+    for root in dir.ancestors() {
+        let Some(patterns) = workspace_patterns(root) else {
+            continue;
+        };
+        let relative = dir.strip_prefix(root).ok()?;
+        if relative.as_os_str().is_empty() {
+            return Some(root.to_path_buf());
+        }
+        let listed = |negated: bool| {
+            patterns
+                .iter()
+                .filter(|p| p.starts_with('!') == negated)
+                .map(|p| p.trim_start_matches('!').trim_start_matches("./"))
+                .filter_map(|p| {
+                    GlobBuilder::new(p.trim_end_matches('/'))
+                        .literal_separator(true)
+                        .build()
+                        .ok()
+                })
+                .any(|glob| glob.compile_matcher().is_match(relative))
+        };
+        return (listed(false) && !listed(true)).then(|| root.to_path_buf());
+    }
+    None
+}
+
+/// Member globs declared in `dir`, if it is a workspace root
+fn workspace_patterns(dir: &Path) -> Option<Vec<String>> {
+    if let Ok(text) = fs::read_to_string(dir.join("pnpm-workspace.yaml")) {
+        return Some(pnpm_packages(&text));
+    }
+
+    let text = fs::read_to_string(dir.join("package.json")).ok()?;
+    let package = serde_json::from_str::<serde_json::Value>(&text).ok()?;
+    // An array, or Yarn's `{"packages": [...], "nohoist": [...]}`
+    let workspaces = match &package["workspaces"] {
+        serde_json::Value::Object(object) => object.get("packages")?,
+        other => other,
+    };
+    let patterns = workspaces
+        .as_array()?
+        .iter()
+        .filter_map(|p| p.as_str())
+        .map(str::to_string)
+        .collect();
+    Some(patterns)
+}
+
+/// The `packages` list of a `pnpm-workspace.yaml`
+///
+/// Only the two forms pnpm documents are understood: a block sequence of
+/// (optionally quoted) globs, or a flow sequence on the same line.
+fn pnpm_packages(text: &str) -> Vec<String> {
+    let unquote = |s: &str| {
+        let s = s.split(" #").next().unwrap_or(s).trim();
+        s.trim_matches(|c| c == '\'' || c == '"').to_string()
+    };
+    let mut packages = Vec::new();
+    let mut lines = text.lines();
+    while let Some(line) = lines.next() {
+        let Some(rest) = line.strip_prefix("packages:") else {
+            continue;
+        };
+        let rest = rest.trim();
+        if let Some(flow) = rest.strip_prefix('[') {
+            let flow = flow.split(']').next().unwrap_or(flow);
+            packages.extend(flow.split(',').map(unquote).filter(|p| !p.is_empty()));
+            break;
+        }
+        for item in lines.by_ref() {
+            let trimmed = item.trim();
+            if let Some(glob) = trimmed.strip_prefix('-') {
+                packages.push(unquote(glob));
+            } else if !trimmed.is_empty() && !trimmed.starts_with('#') {
+                break;
+            }
+        }
+        break;
+    }
+    packages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_install_state_needs_workspace_member() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let node_modules = root.join("packages/ui/node_modules");
+        fs::create_dir_all(&node_modules).unwrap();
+        assert!(NodeModulesDetector.verify(&node_modules).is_err());
+
+        // Like /usr/lib/node_modules: install state, but no project
+        fs::create_dir(node_modules.join(".pnpm")).unwrap();
+        let error = NodeModulesDetector.verify(&node_modules).unwrap_err();
+        assert!(error.contains("no workspace lists it"));
+
+        fs::write(
+            root.join("package.json"),
+            r#"{"workspaces": ["packages/*"]}"#,
+        )
+        .unwrap();
+        assert!(NodeModulesDetector.verify(&node_modules).is_ok());
+    }
+
+    #[test]
+    fn test_workspace_roots() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        for dir in ["packages/ui", "packages/internal", "apps/web", "tools/x"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+            fs::write(root.join(dir).join("package.json"), "{}").unwrap();
+        }
+        fs::write(
+            root.join("package.json"),
+            r#"{"workspaces": {"packages": ["packages/*", "!packages/internal", "./apps/*/"]}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(workspace_root(root).as_deref(), Some(root));
+        assert_eq!(
+            workspace_root(&root.join("packages/ui")).as_deref(),
+            Some(root)
+        );
+        assert_eq!(
+            workspace_root(&root.join("apps/web")).as_deref(),
+            Some(root)
+        );
+        assert_eq!(workspace_root(&root.join("packages/internal")), None);
+        assert_eq!(workspace_root(&root.join("tools/x")), None);
+
+        // pnpm's own file takes precedence over package.json
+        fs::write(
+            root.join("pnpm-workspace.yaml"),
+            "# monorepo\npackages:\n  - 'tools/*'   # scripts\n  - \"apps/**\"\ncatalog:\n  react: ^18\n",
+        )
+        .unwrap();
+        assert_eq!(workspace_root(&root.join("tools/x")).as_deref(), Some(root));
+        assert_eq!(workspace_root(&root.join("packages/ui")), None);
+        assert_eq!(pnpm_packages("packages: ['a/*', \"b\"]\n"), ["a/*", "b"]);
+    }
+
+    #[test]
+    fn test_framework_caches() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        for dir in [
+            "web/.next",
+            "docs/.next",
+            "ng/.angular",
+            "packages/ui/.turbo",
+        ] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        fs::write(
+            root.join("web/package.json"),
+            r#"{"devDependencies": {"next": "14"}}"#,
+        )
+        .unwrap();
+        fs::write(
+            root.join("docs/package.json"),
+            r#"{"dependencies": {"react": "18"}}"#,
+        )
+        .unwrap();
+        fs::write(root.join("ng/angular.json"), "{}").unwrap();
+        fs::write(root.join("packages/ui/package.json"), "{}").unwrap();
+
+        assert!(FrameworkCacheDetector
+            .verify(&root.join("web/.next"))
+            .is_ok());
+        let error = FrameworkCacheDetector
+            .verify(&root.join("docs/.next"))
+            .unwrap_err();
+        assert!(error.contains("next.config.*"));
+        fs::write(root.join("docs/next.config.mjs"), "").unwrap();
+        assert!(FrameworkCacheDetector
+            .verify(&root.join("docs/.next"))
+            .is_ok());
+        assert!(FrameworkCacheDetector
+            .verify(&root.join("ng/.angular"))
+            .is_ok());
+
+        // Turborepo is configured once, at the workspace root
+        let turbo = root.join("packages/ui/.turbo");
+        assert!(FrameworkCacheDetector.verify(&turbo).is_err());
+        fs::write(
+            root.join("package.json"),
+            r#"{"workspaces": ["packages/*"]}"#,
+        )
+        .unwrap();
+        fs::write(root.join("turbo.json"), "{}").unwrap();
+        assert!(FrameworkCacheDetector.verify(&turbo).is_ok());
+    }
+
+    #[test]
+    fn test_yarn_berry_cache() {
+        let temp_dir = TempDir::new().unwrap();
+        let project = temp_dir.path();
+        let cache = project.join(".yarn/cache");
+        fs::create_dir_all(&cache).unwrap();
+        fs::create_dir_all(project.join("other/cache")).unwrap();
+        fs::write(project.join("package.json"), "{}").unwrap();
+        assert!(YarnBerryDetector.verify(&cache).is_err());
+
+        fs::write(project.join(".pnp.cjs"), "").unwrap();
+        fs::write(project.join("yarn.lock"), "").unwrap();
+        fs::write(project.join(".yarnrc.yml"), "").unwrap();
+        assert!(YarnBerryDetector.verify(&cache).is_ok());
+        assert!(YarnBerryDetector
+            .verify(&project.join("other/cache"))
+            .is_err());
+        assert_eq!(
+            PackageManager::detect(project),
+            Some(PackageManager::YarnBerry)
+        );
+    }
+}
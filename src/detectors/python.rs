@@ -0,0 +1,231 @@
+use super::{require_sibling, TargetDetector};
+use crate::platform::process_running_from;
+use crate::types::CleanTarget;
+use std::fs;
+use std::path::Path;
+
+/// `__pycache__` and the caches of pytest, tox and mypy
+///
+/// A `__pycache__` must hold `.pyc` files or nothing at all, and `.tox`
+/// needs a `tox.ini`, `pyproject.toml` or `setup.cfg` next to it.
+pub struct PythonCacheDetector;
+
+impl TargetDetector for PythonCacheDetector {
+    fn id(&self) -> &str {
+        "python-cache"
+    }
+
+    fn display_name(&self) -> &str {
+        "Python cache"
+    }
+
+    fn category(&self) -> CleanTarget {
+        CleanTarget::PythonCache
+    }
+
+    fn matches_name(&self, dir_name: &str) -> bool {
+        matches!(
+            dir_name,
+            "__pycache__" | ".pytest_cache" | ".tox" | ".mypy_cache"
+        )
+    }
+
+    fn verify(&self, path: &Path) -> Result<(), String> {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy())
+            .unwrap_or_default();
+        match name.as_ref() {
+            "__pycache__" => {
+                let mut entries = fs::read_dir(path)
+                    .map_err(|e| e.to_string())?
+                    .flatten()
+                    .peekable();
+                let empty = entries.peek().is_none();
+                if empty || entries.any(|e| e.path().extension().is_some_and(|ext| ext == "pyc")) {
+                    Ok(())
+                } else {
+                    Err("no .pyc files inside".to_string())
+                }
+            }
+            ".tox" => require_sibling(path, &["tox.ini", "pyproject.toml", "setup.cfg"]),
+            // pytest and mypy rebuild these on the next run
+            ".pytest_cache" | ".mypy_cache" => Ok(()),
+            _ => Err(format!("'{}' is not a Python cache directory", name)),
+        }
+    }
+
+    fn prunes(&self, dir_name: &str) -> bool {
+        self.matches_name(dir_name)
+    }
+}
+
+/// Files a project's virtual environment can be rebuilt from
+const VENV_SOURCES: &[&str] = &[
+    "pyproject.toml",
+    "poetry.lock",
+    "uv.lock",
+    "Pipfile.lock",
+    "pdm.lock",
+    "pylock.toml",
+];
+
+/// A virtual environment, recognised by its `pyvenv.cfg` whatever its name
+///
+/// Only environments that can be rebuilt verify: the project next to it
+/// must have a `pyproject.toml`, a `requirements*.txt` or a lock file. The
+/// environment in `$VIRTUAL_ENV` and any environment a running process was
+/// started from are refused.
+pub struct PythonVenvDetector;
+
+impl TargetDetector for PythonVenvDetector {
+    fn id(&self) -> &str {
+        "python-venv"
+    }
+
+    fn display_name(&self) -> &str {
+        "Python virtualenv"
+    }
+
+    fn category(&self) -> CleanTarget {
+        CleanTarget::PythonVenv
+    }
+
+    fn matches_name(&self, _dir_name: &str) -> bool {
+        // `.venv`, `venv`, `env`...: the name proves nothing
+        false
+    }
+
+    fn matches_contents(&self, path: &Path) -> bool {
+        path.join("pyvenv.cfg").is_file()
+    }
+
+    fn verify(&self, path: &Path) -> Result<(), String> {
+        let cfg = fs::read_to_string(path.join("pyvenv.cfg"))
+            .map_err(|_| "no pyvenv.cfg inside".to_string())?;
+        if !cfg.lines().any(|l| l.trim_start().starts_with("home")) {
+            return Err("pyvenv.cfg does not name a base interpreter".to_string());
+        }
+
+        let project = path.parent().ok_or("directory has no parent")?;
+        if !has_venv_source(project) {
+            return Err(
+                "nothing to rebuild it from: no pyproject.toml, requirements*.txt or lock file next to it"
+                    .to_string(),
+            );
+        }
+
+        if let Some(active) = std::env::var_os("VIRTUAL_ENV") {
+            if same_dir(Path::new(&active), path) {
+                return Err("it is the active virtual environment ($VIRTUAL_ENV)".to_string());
+            }
+        }
+        if let Some(pid) = process_running_from(path) {
+            return Err(format!("it is in use by running process {}", pid));
+        }
+        Ok(())
+    }
+}
+
+fn has_venv_source(project: &Path) -> bool {
+    if VENV_SOURCES.iter().any(|name| project.join(name).is_file()) {
+        return true;
+    }
+    let Ok(entries) = fs::read_dir(project) else {
+        return false;
+    };
+    entries.flatten().any(|entry| {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        name.starts_with("requirements") && name.ends_with(".txt")
+    })
+}
+
+fn same_dir(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_python_cache_markers() {
+        let temp_dir = TempDir::new().unwrap();
+        let project = temp_dir.path();
+        let pycache = project.join("__pycache__");
+        fs::create_dir_all(&pycache).unwrap();
+        assert!(PythonCacheDetector.verify(&pycache).is_ok());
+        fs::write(pycache.join("notes.txt"), "keep me").unwrap();
+        assert!(PythonCacheDetector.verify(&pycache).is_err());
+        fs::write(pycache.join("app.cpython-312.pyc"), [0u8; 16]).unwrap();
+        assert!(PythonCacheDetector.verify(&pycache).is_ok());
+
+        let tox = project.join(".tox");
+        fs::create_dir_all(&tox).unwrap();
+        assert!(PythonCacheDetector.verify(&tox).is_err());
+        fs::write(project.join("tox.ini"), "[tox]\n").unwrap();
+        assert!(PythonCacheDetector.verify(&tox).is_ok());
+
+        let important = project.join("important");
+        fs::create_dir_all(&important).unwrap();
+        assert!(PythonCacheDetector.verify(&important).is_err());
+    }
+
+    #[test]
+    fn test_venv_needs_cfg_and_project() {
+        let temp_dir = TempDir::new().unwrap();
+        let project = temp_dir.path();
+        let venv = project.join("env");
+        fs::create_dir_all(venv.join("bin")).unwrap();
+        assert!(PythonVenvDetector.verify(&venv).is_err());
+
+        fs::write(
+            venv.join("pyvenv.cfg"),
+            "home = /usr/bin\ninclude-system-site-packages = false\nversion = 3.12.3\n",
+        )
+        .unwrap();
+        let error = PythonVenvDetector.verify(&venv).unwrap_err();
+        assert!(error.contains("nothing to rebuild it from"));
+
+        fs::write(project.join("requirements-dev.txt"), "pytest\n").unwrap();
+        assert!(PythonVenvDetector.verify(&venv).is_ok());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_venv_in_use_is_refused() {
+        use std::process::Command;
+
+        let temp_dir = TempDir::new().unwrap();
+        let project = temp_dir.path();
+        let venv = project.join(".venv");
+        fs::create_dir_all(venv.join("bin")).unwrap();
+        fs::write(venv.join("pyvenv.cfg"), "home = /usr/bin\n").unwrap();
+        fs::write(project.join("pyproject.toml"), "[project]\n").unwrap();
+        assert!(PythonVenvDetector.verify(&venv).is_ok());
+
+        // Like a venv's `python`, a symlink to an interpreter elsewhere
+        let sleep = venv.join("bin/sleep");
+        std::os::unix::fs::symlink("/bin/sleep", &sleep).unwrap();
+        let mut child = Command::new(&sleep).arg("30").spawn().unwrap();
+        // Until the child has exec'd, /proc still shows this test binary
+        let cmdline = format!("/proc/{}/cmdline", child.id());
+        for _ in 0..200 {
+            if fs::read(&cmdline)
+                .is_ok_and(|argv| argv.starts_with(sleep.as_os_str().as_encoded_bytes()))
+            {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        let error = PythonVenvDetector.verify(&venv).unwrap_err();
+        child.kill().unwrap();
+        child.wait().unwrap();
+        assert!(error.contains(&child.id().to_string()));
+    }
+}
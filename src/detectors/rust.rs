@@ -0,0 +1,401 @@
+use super::{require_sibling, TargetDetector};
+use crate::types::CleanTarget;
+use globset::Glob;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// First line of every `CACHEDIR.TAG`, from the Cache Directory Tagging spec
+const CACHEDIR_SIGNATURE: &str = "Signature: 8a477f597d28d172789f06886806bc55";
+
+/// The parts of the environment that decide where cargo builds
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct CargoEnv {
+    /// `CARGO_TARGET_DIR` (or `CARGO_BUILD_TARGET_DIR`), made absolute
+    pub target_dir: Option<PathBuf>,
+    /// `$CARGO_HOME` (default `~/.cargo`), whose config applies everywhere
+    pub cargo_home: Option<PathBuf>,
+}
+
+impl CargoEnv {
+    /// Read the environment of this process
+    pub fn from_env() -> Self {
+        let target_dir = ["CARGO_TARGET_DIR", "CARGO_BUILD_TARGET_DIR"]
+            .iter()
+            .filter_map(std::env::var_os)
+            .find(|dir| !dir.is_empty())
+            .and_then(|dir| std::path::absolute(PathBuf::from(dir)).ok());
+        let cargo_home = std::env::var_os("CARGO_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cargo")));
+        Self {
+            target_dir,
+            cargo_home,
+        }
+    }
+}
+
+/// Cargo build output: `target` next to a `Cargo.toml`, or any directory
+/// cargo was told to build into
+///
+/// Shared target directories set with `build.target-dir` in the
+/// `.cargo/config.toml` hierarchy or with `CARGO_TARGET_DIR` are reported as
+/// [linked targets](TargetDetector::linked_targets) of the projects using
+/// them. Workspace members build into the workspace root's directory.
+/// Directories without a `Cargo.toml` next to them must carry cargo's
+/// `CACHEDIR.TAG` and `.rustc_info.json`.
+#[derive(Debug, Clone)]
+pub struct RustTargetDetector {
+    env: CargoEnv,
+}
+
+impl RustTargetDetector {
+    /// Detector using this process's cargo environment
+    pub fn new() -> Self {
+        Self::with_env(CargoEnv::from_env())
+    }
+
+    /// Detector using an explicit cargo environment
+    pub fn with_env(env: CargoEnv) -> Self {
+        Self { env }
+    }
+}
+
+impl Default for RustTargetDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TargetDetector for RustTargetDetector {
+    fn id(&self) -> &str {
+        "rust-target"
+    }
+
+    fn display_name(&self) -> &str {
+        "Rust target"
+    }
+
+    fn category(&self) -> CleanTarget {
+        CleanTarget::RustTarget
+    }
+
+    fn matches_name(&self, dir_name: &str) -> bool {
+        dir_name == "target"
+    }
+
+    fn matches_contents(&self, path: &Path) -> bool {
+        // Shared target dirs from `build.target-dir` can have any name; the
+        // stat keeps the reads off every other directory
+        path.join("CACHEDIR.TAG").is_file() && verify_contents(path).is_ok()
+    }
+
+    fn verify(&self, path: &Path) -> Result<(), String> {
+        require_sibling(path, &["Cargo.toml"])
+            .or_else(|sibling| verify_contents(path).map_err(|e| format!("{}, and {}", sibling, e)))
+    }
+
+    fn prunes(&self, dir_name: &str) -> bool {
+        self.matches_name(dir_name)
+    }
+
+    fn link_markers(&self) -> &[&str] {
+        &["Cargo.toml"]
+    }
+
+    fn keep_dirs(&self, _target: &Path, project: &Path) -> Vec<PathBuf> {
+        // Every member of a workspace builds into the same directory
+        let root = workspace_root(project);
+        let mut dirs = vec![project.to_path_buf()];
+        dirs.extend(workspace_members(&root));
+        dirs.push(root);
+        dirs
+    }
+
+    fn linked_targets(&self, dir: &Path) -> Vec<PathBuf> {
+        if !dir.join("Cargo.toml").is_file() {
+            return Vec::new();
+        }
+        let target = target_dir(dir, &self.env);
+        if target == dir.join("target") || verify_contents(&target).is_err() {
+            return Vec::new();
+        }
+        target.canonicalize().into_iter().collect()
+    }
+}
+
+/// Check that `path` holds cargo build output
+///
+/// Cargo writes a `CACHEDIR.TAG` naming itself and a `.rustc_info.json`
+/// with the compiler fingerprint into every target directory it uses.
+pub fn verify_contents(path: &Path) -> Result<(), String> {
+    let tag = fs::read_to_string(path.join("CACHEDIR.TAG"))
+        .map_err(|_| "no CACHEDIR.TAG inside".to_string())?;
+    if !tag.starts_with(CACHEDIR_SIGNATURE) || !tag.contains("cargo") {
+        return Err("CACHEDIR.TAG was not written by cargo".to_string());
+    }
+
+    let info = fs::read_to_string(path.join(".rustc_info.json"))
+        .map_err(|_| "no .rustc_info.json inside".to_string())?;
+    match serde_json::from_str::<serde_json::Value>(&info) {
+        Ok(info) if info.get("rustc_fingerprint").is_some() => Ok(()),
+        _ => Err(".rustc_info.json is not cargo's".to_string()),
+    }
+}
+
+/// Root of the workspace the package in `manifest_dir` belongs to
+///
+/// That is `manifest_dir` itself for a workspace root or a package outside
+/// any workspace.
+pub fn workspace_root(manifest_dir: &Path) -> PathBuf {
+    let Some(manifest) = read_manifest(manifest_dir) else {
+        return manifest_dir.to_path_buf();
+    };
+    if manifest.contains_key("workspace") {
+        return manifest_dir.to_path_buf();
+    }
+    // An explicit `package.workspace` wins over searching
+    if let Some(root) = manifest
+        .get("package")
+        .and_then(|p| p.get("workspace"))
+        .and_then(|w| w.as_str())
+    {
+        return manifest_dir.join(root);
+    }
+
+    // Otherwise the nearest ancestor with a [workspace] that lists it
+    for root in manifest_dir.ancestors().skip(1) {
+        let Some(workspace) = read_manifest(root).and_then(|m| m.get("workspace").cloned()) else {
+            continue;
+        };
+        let Ok(relative) = manifest_dir.strip_prefix(root) else {
+            break;
+        };
+        let listed = |key: &str| {
+            workspace
+                .get(key)
+                .and_then(|v| v.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|v| v.as_str())
+                .filter_map(|pattern| Glob::new(pattern.trim_end_matches('/')).ok())
+                .any(|glob| glob.compile_matcher().is_match(relative))
+        };
+        if listed("members") && !listed("exclude") {
+            return root.to_path_buf();
+        }
+        // Cargo stops at the first workspace it finds
+        break;
+    }
+    manifest_dir.to_path_buf()
+}
+
+/// Where cargo puts the build output of the package in `manifest_dir`
+///
+/// Resolved like cargo run from `manifest_dir`: `CARGO_TARGET_DIR`, then
+/// `build.target-dir` from the nearest `.cargo/config.toml` (relative paths
+/// are relative to the directory holding `.cargo`), then `$CARGO_HOME`'s
+/// config, then `target` in the workspace root.
+pub fn target_dir(manifest_dir: &Path, env: &CargoEnv) -> PathBuf {
+    if let Some(dir) = &env.target_dir {
+        return dir.clone();
+    }
+    let configured = manifest_dir
+        .ancestors()
+        .map(|dir| dir.join(".cargo"))
+        .chain(env.cargo_home.clone())
+        .find_map(|cargo_dir| configured_target_dir(&cargo_dir));
+    match configured {
+        Some(dir) => dir,
+        None => workspace_root(manifest_dir).join("target"),
+    }
+}
+
+/// `build.target-dir` from `config.toml` (or the older `config`) in a
+/// `.cargo` directory, resolved against its parent
+fn configured_target_dir(cargo_dir: &Path) -> Option<PathBuf> {
+    ["config.toml", "config"].iter().find_map(|name| {
+        let text = fs::read_to_string(cargo_dir.join(name)).ok()?;
+        let config = toml::from_str::<toml::Table>(&text).ok()?;
+        let dir = config.get("build")?.get("target-dir")?.as_str()?;
+        Some(cargo_dir.parent().unwrap_or(cargo_dir).join(dir))
+    })
+}
+
+/// Package directories listed in the `members` of the workspace at `root`
+fn workspace_members(root: &Path) -> Vec<PathBuf> {
+    let Some(workspace) = read_manifest(root).and_then(|m| m.get("workspace").cloned()) else {
+        return Vec::new();
+    };
+    let patterns = workspace
+        .get("members")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|v| v.as_str());
+    let mut members = Vec::new();
+    for pattern in patterns {
+        // Expanded one path component at a time, like cargo does
+        let mut dirs = vec![root.to_path_buf()];
+        for component in Path::new(pattern.trim_end_matches('/')).components() {
+            let component = component.as_os_str().to_string_lossy();
+            if !component.contains(['*', '?', '[', '{']) {
+                dirs.iter_mut().for_each(|dir| dir.push(&*component));
+                continue;
+            }
+            let Ok(glob) = Glob::new(&component) else {
+                dirs.clear();
+                break;
+            };
+            let glob = glob.compile_matcher();
+            dirs = dirs
+                .iter()
+                .filter_map(|dir| fs::read_dir(dir).ok())
+                .flatten()
+                .flatten()
+                .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+                .filter(|e| glob.is_match(e.file_name()))
+                .map(|e| e.path())
+                .collect();
+        }
+        members.extend(dirs.into_iter().filter(|d| d.join("Cargo.toml").is_file()));
+    }
+    members
+}
+
+fn read_manifest(dir: &Path) -> Option<toml::Table> {
+    let text = fs::read_to_string(dir.join("Cargo.toml")).ok()?;
+    toml::from_str(&text).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detectors::DetectorRegistry;
+    use tempfile::TempDir;
+
+    /// What cargo leaves in a fresh target directory
+    fn fake_target(dir: &Path) {
+        fs::create_dir_all(dir.join("debug")).unwrap();
+        fs::write(
+            dir.join("CACHEDIR.TAG"),
+            format!(
+                "{}\n# This file is a cache directory tag created by cargo.\n",
+                CACHEDIR_SIGNATURE
+            ),
+        )
+        .unwrap();
+        fs::write(
+            dir.join(".rustc_info.json"),
+            r#"{"rustc_fingerprint":1,"outputs":{}}"#,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_workspace_root() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("crates/a")).unwrap();
+        fs::create_dir_all(root.join("crates/skip")).unwrap();
+        fs::create_dir_all(root.join("tools/x")).unwrap();
+        fs::write(
+            root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"crates/skip\"]\n",
+        )
+        .unwrap();
+        for member in ["crates/a", "crates/skip", "tools/x"] {
+            fs::write(
+                root.join(member).join("Cargo.toml"),
+                "[package]\nname = \"m\"\n",
+            )
+            .unwrap();
+        }
+
+        assert_eq!(workspace_root(root), root);
+        assert_eq!(workspace_root(&root.join("crates/a")), root);
+        assert_eq!(
+            workspace_root(&root.join("crates/skip")),
+            root.join("crates/skip")
+        );
+        assert_eq!(workspace_root(&root.join("tools/x")), root.join("tools/x"));
+    }
+
+    #[test]
+    fn test_target_dir_resolution() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let app = root.join("work/app");
+        fs::create_dir_all(&app).unwrap();
+        fs::write(app.join("Cargo.toml"), "[package]\nname = \"app\"\n").unwrap();
+        let env = CargoEnv::default();
+
+        assert_eq!(target_dir(&app, &env), app.join("target"));
+
+        let home = root.join("cargo-home");
+        fs::create_dir_all(&home).unwrap();
+        fs::write(
+            home.join("config.toml"),
+            "[build]\ntarget-dir = \"/tmp/everything\"\n",
+        )
+        .unwrap();
+        let with_home = CargoEnv {
+            cargo_home: Some(home),
+            ..CargoEnv::default()
+        };
+        assert_eq!(target_dir(&app, &with_home), Path::new("/tmp/everything"));
+
+        // The nearest .cargo/config.toml wins, relative to its parent
+        fs::create_dir_all(root.join("work/.cargo")).unwrap();
+        fs::write(
+            root.join("work/.cargo/config.toml"),
+            "build.target-dir = \"shared\"\n",
+        )
+        .unwrap();
+        assert_eq!(target_dir(&app, &with_home), root.join("work/shared"));
+
+        let env = CargoEnv {
+            target_dir: Some(root.join("env-target")),
+            ..with_home
+        };
+        assert_eq!(target_dir(&app, &env), root.join("env-target"));
+    }
+
+    #[test]
+    fn test_linked_targets_need_cargo_contents() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let shared = root.join("shared-target");
+        fs::create_dir_all(&shared).unwrap();
+        let app = root.join("app");
+        fs::create_dir_all(app.join(".cargo")).unwrap();
+        fs::write(app.join("Cargo.toml"), "[package]\nname = \"app\"\n").unwrap();
+        fs::write(
+            app.join(".cargo/config.toml"),
+            "[build]\ntarget-dir = \"../shared-target\"\n",
+        )
+        .unwrap();
+
+        let detector = RustTargetDetector::with_env(CargoEnv::default());
+        assert!(detector.linked_targets(&app).is_empty());
+        assert!(detector.verify(&shared).is_err());
+
+        fake_target(&shared);
+        assert_eq!(
+            detector.linked_targets(&app),
+            [shared.canonicalize().unwrap()]
+        );
+        assert!(detector.verify(&shared).is_ok());
+        assert!(detector.linked_targets(root).is_empty());
+
+        // The registry only asks when the scanner saw a Cargo.toml
+        let registry = DetectorRegistry::new().with(detector);
+        assert!(registry.linked_targets(&app, |_| false).is_empty());
+        assert_eq!(
+            registry
+                .linked_targets(&app, |name| name == "Cargo.toml")
+                .len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,180 @@
+//! Progress events emitted by the [`Cleaner`](crate::Cleaner).
+//!
+//! The cleaner never writes to the terminal itself. Everything it does is
+//! reported as a [`CleanEvent`] to a [`CleanObserver`], which can render a
+//! progress bar, write log lines, forward events over a channel or simply
+//! record them.
+
+use crate::filter::Exclusion;
+use crate::goal::GoalOutcome;
+use crate::types::{CleanStats, ScanResult};
+use crossbeam_channel::Sender;
+use serde::Serialize;
+use std::fmt;
+use std::path::PathBuf;
+
+/// Why a target was left in place
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", content = "detail", rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum SkipReason {
+    /// The directory changed since the scan or its markers are gone
+    Verification(String),
+    /// The free-space goal was reached before this target's turn
+    GoalReached,
+    /// Inside a git work tree but not ignored by git
+    NotIgnored,
+    /// Holds this many files tracked by git
+    Tracked(usize),
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SkipReason::Verification(reason) => write!(f, "{}", reason),
+            SkipReason::GoalReached => write!(f, "free-space goal already reached"),
+            SkipReason::NotIgnored => write!(f, "not ignored by git"),
+            SkipReason::Tracked(1) => write!(f, "contains a file tracked by git"),
+            SkipReason::Tracked(files) => write!(f, "contains {} files tracked by git", files),
+        }
+    }
+}
+
+/// Something that happened while cleaning
+///
+/// Targets turned down by the cleaner's [`Filter`](crate::filter::Filter)
+/// are reported with `Excluded` (before `Planned` in a batch run) and are
+/// not part of the plan.
+///
+/// For every planned or found target exactly one of `Skipped`, `Deleted`,
+/// `Trashed`, `WouldDelete` or `Failed` is emitted, unless the run is interrupted first.
+/// A batch run starts with `Planned`; a streaming
+/// [`Pipeline`](crate::pipeline::Pipeline) run starts with `Streaming` and
+/// announces each target with `Found` instead.
+///
+/// Serializes as an object whose `event` field names the variant, which is
+/// what `--format ndjson` prints one per line.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum CleanEvent {
+    /// A target was left out by the filter and will not be touched
+    Excluded {
+        /// Target that was left out
+        target: ScanResult,
+        /// Which rule excluded it
+        reason: Exclusion,
+    },
+    /// The cleaner is about to process these targets
+    Planned {
+        /// Targets in processing order
+        targets: Vec<ScanResult>,
+        /// Whether this is a dry run
+        dry_run: bool,
+    },
+    /// A streaming run started; targets follow as they are found
+    Streaming {
+        /// Whether this is a dry run
+        dry_run: bool,
+    },
+    /// Streaming: a target was discovered and sized
+    Found {
+        /// The sized target
+        target: ScanResult,
+    },
+    /// The observer declined the confirmation; nothing was touched
+    Cancelled,
+    /// Re-checking a target right before deletion
+    Verifying {
+        /// Target being checked
+        target: ScanResult,
+    },
+    /// A target was left in place
+    Skipped {
+        /// Target that was skipped
+        target: ScanResult,
+        /// Why it was skipped
+        reason: SkipReason,
+    },
+    /// Deletion of a verified target started
+    Deleting {
+        /// Target being deleted
+        target: ScanResult,
+    },
+    /// A target was removed
+    Deleted {
+        /// Target that was removed
+        target: ScanResult,
+        /// Bytes freed
+        bytes: u64,
+    },
+    /// A target was moved to the trash
+    Trashed {
+        /// Target that was moved
+        target: ScanResult,
+        /// Where it is now
+        #[serde(with = "crate::report::os_path")]
+        location: PathBuf,
+    },
+    /// Dry run: a target would have been removed
+    WouldDelete {
+        /// Target that would be removed
+        target: ScanResult,
+    },
+    /// Removing a target failed
+    Failed {
+        /// Target that could not be removed
+        target: ScanResult,
+        /// Error message
+        error: String,
+    },
+    /// The interrupt flag was raised; remaining targets were skipped
+    Interrupted {
+        /// Number of targets that were not processed
+        remaining: usize,
+    },
+    /// How a free-space goal turned out, sent right before `Finished`
+    SpaceGoal {
+        /// Space required and available
+        outcome: GoalOutcome,
+    },
+    /// The run is over
+    Finished {
+        /// Final statistics
+        stats: CleanStats,
+    },
+}
+
+/// Receives [`CleanEvent`]s from a [`Cleaner`](crate::Cleaner)
+///
+/// Events may arrive from several threads at once when parallel processing
+/// is enabled.
+pub trait CleanObserver: Send + Sync {
+    /// Handle one event
+    fn on_event(&self, event: &CleanEvent);
+
+    /// Decide whether the planned targets may be deleted
+    ///
+    /// Only consulted by [`Cleaner::clean`](crate::Cleaner::clean). The
+    /// default declines, so observers that cannot ask anyone never delete by
+    /// accident.
+    fn confirm(&self, _targets: &[ScanResult]) -> bool {
+        false
+    }
+}
+
+/// Observer that ignores every event and declines confirmation
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NullObserver;
+
+impl CleanObserver for NullObserver {
+    fn on_event(&self, _event: &CleanEvent) {}
+}
+
+/// Forward events over a channel, e.g. to a UI thread
+impl CleanObserver for Sender<CleanEvent> {
+    fn on_event(&self, event: &CleanEvent) {
+        // A dropped receiver only means nobody is listening any more
+        let _ = self.send(event.clone());
+    }
+}
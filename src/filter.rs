@@ -0,0 +1,469 @@
+//! Selection rules applied to sized scan results.
+//!
+//! Detectors decide what a directory *is*; a [`Filter`] decides whether a
+//! found target should be cleaned this time. Targets it turns down are not
+//! errors: they are reported as [`Excluded`] with the [`Exclusion`] that
+//! applied, so summaries can say what was left out and why.
+
+use crate::types::ScanResult;
+use crate::utils::{format_age, format_size};
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+/// Why a target was not selected
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Exclusion {
+    /// Something inside the target (or its project) changed too recently
+    RecentlyActive {
+        /// Newest change that was found, if any could be read
+        last_activity: Option<SystemTime>,
+        /// Minimum age that was asked for
+        min_age: Duration,
+    },
+    /// Smaller than the minimum size
+    TooSmall {
+        /// Measured size in bytes
+        size: u64,
+        /// Minimum that was asked for
+        min_size: u64,
+    },
+    /// Larger than the maximum size
+    TooLarge {
+        /// Measured size in bytes
+        size: u64,
+        /// Maximum that was asked for
+        max_size: u64,
+    },
+    /// Holds fewer files than the minimum
+    TooFewFiles {
+        /// Number of files inside
+        files: usize,
+        /// Minimum that was asked for
+        min_files: usize,
+    },
+    /// Passed every other rule but did not rank among the first `top`
+    NotInTop {
+        /// How many targets were kept
+        top: usize,
+        /// What they were ranked by
+        rank_by: Ranking,
+    },
+    /// Not needed: the [free-space goal](crate::goal) is reached without it
+    GoalReached {
+        /// Bytes that must be available
+        required: u64,
+    },
+    /// On a different filesystem than the one the free-space goal is for
+    OtherFilesystem,
+    /// Its project asks for it to be kept; see [`keep`](crate::keep)
+    Kept {
+        /// The keep marker, as recorded by the scanner
+        marker: String,
+    },
+}
+
+/// What [`Filter::with_top`] ranks targets by, largest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Ranking {
+    /// Size in bytes
+    #[default]
+    Size,
+    /// Number of files
+    Files,
+}
+
+impl Ranking {
+    fn key(&self, result: &ScanResult) -> u64 {
+        match self {
+            Ranking::Size => result.size,
+            Ranking::Files => result.file_count as u64,
+        }
+    }
+}
+
+impl Exclusion {
+    /// Stable identifier, as used in JSON output
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Exclusion::RecentlyActive { .. } => "recently-active",
+            Exclusion::TooSmall { .. } => "too-small",
+            Exclusion::TooLarge { .. } => "too-large",
+            Exclusion::TooFewFiles { .. } => "too-few-files",
+            Exclusion::NotInTop { .. } => "not-in-top",
+            Exclusion::GoalReached { .. } => "goal-reached",
+            Exclusion::OtherFilesystem => "other-filesystem",
+            Exclusion::Kept { .. } => "kept",
+        }
+    }
+}
+
+impl fmt::Display for Exclusion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Exclusion::RecentlyActive {
+                last_activity: Some(time),
+                min_age,
+            } => {
+                let age = SystemTime::now().duration_since(*time).unwrap_or_default();
+                write!(
+                    f,
+                    "last changed {} ago, within the last {}",
+                    format_age(age),
+                    format_age(*min_age)
+                )
+            }
+            Exclusion::RecentlyActive {
+                last_activity: None,
+                ..
+            } => write!(f, "last activity is unknown"),
+            Exclusion::TooSmall { size, min_size } => write!(
+                f,
+                "{} is below the minimum of {}",
+                format_size(*size),
+                format_size(*min_size)
+            ),
+            Exclusion::TooLarge { size, max_size } => write!(
+                f,
+                "{} is above the maximum of {}",
+                format_size(*size),
+                format_size(*max_size)
+            ),
+            Exclusion::TooFewFiles { files, min_files } => {
+                write!(f, "{} files, fewer than {}", files, min_files)
+            }
+            Exclusion::NotInTop { top, rank_by } => {
+                let by = match rank_by {
+                    Ranking::Size => "largest",
+                    Ranking::Files => "with the most files",
+                };
+                write!(f, "not among the {} {}", top, by)
+            }
+            Exclusion::GoalReached { required } => write!(
+                f,
+                "not needed to reach {} of free space",
+                format_size(*required)
+            ),
+            Exclusion::OtherFilesystem => {
+                write!(f, "on another filesystem, so it frees no space there")
+            }
+            Exclusion::Kept { marker } => write!(f, "kept by {}", marker),
+        }
+    }
+}
+
+/// Same shape as [`SkipReason`](crate::SkipReason): `{"kind", "detail"}`
+impl Serialize for Exclusion {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Exclusion", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("detail", &self.to_string())?;
+        state.end()
+    }
+}
+
+/// A target the filter turned down
+#[derive(Debug, Clone, Serialize)]
+pub struct Excluded {
+    /// The target that is left in place
+    pub target: ScanResult,
+    /// Why it was not selected
+    pub reason: Exclusion,
+}
+
+/// Results split by a [`Filter`]
+#[derive(Debug, Clone, Default)]
+pub struct Selection {
+    /// Targets to clean, in their original order
+    pub selected: Vec<ScanResult>,
+    /// Targets left out
+    pub excluded: Vec<Excluded>,
+}
+
+impl From<Vec<ScanResult>> for Selection {
+    /// Everything selected, nothing excluded
+    fn from(selected: Vec<ScanResult>) -> Self {
+        Selection {
+            selected,
+            excluded: Vec::new(),
+        }
+    }
+}
+
+/// Decides which sized targets are cleaned
+///
+/// The default filter selects everything. Per-target rules are checked
+/// first; [`with_top`](Filter::with_top) then ranks what is left.
+#[derive(Debug, Clone)]
+pub struct Filter {
+    older_than: Option<Duration>,
+    now: SystemTime,
+    min_size: Option<u64>,
+    max_size: Option<u64>,
+    min_files: Option<usize>,
+    top: Option<(usize, Ranking)>,
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Self {
+            older_than: None,
+            now: SystemTime::now(),
+            min_size: None,
+            max_size: None,
+            min_files: None,
+            top: None,
+        }
+    }
+}
+
+impl Filter {
+    /// Create a filter that selects everything
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only select targets whose last activity is at least `age` ago
+    ///
+    /// Targets whose last activity could not be read are left out.
+    pub fn with_older_than(mut self, age: Duration) -> Self {
+        self.older_than = Some(age);
+        self
+    }
+
+    /// Measure ages from `now` instead of the time the filter was created
+    pub fn with_now(mut self, now: SystemTime) -> Self {
+        self.now = now;
+        self
+    }
+
+    /// Only select targets of at least `bytes`
+    pub fn with_min_size(mut self, bytes: u64) -> Self {
+        self.min_size = Some(bytes);
+        self
+    }
+
+    /// Only select targets of at most `bytes`
+    pub fn with_max_size(mut self, bytes: u64) -> Self {
+        self.max_size = Some(bytes);
+        self
+    }
+
+    /// Only select targets holding at least `files` files
+    pub fn with_min_files(mut self, files: usize) -> Self {
+        self.min_files = Some(files);
+        self
+    }
+
+    /// Keep only the `count` largest targets by `rank_by`
+    ///
+    /// The selection is then ordered by rank instead of by path. Ranking
+    /// needs every result, so [`check`](Filter::check) ignores it.
+    pub fn with_top(mut self, count: usize, rank_by: Ranking) -> Self {
+        self.top = Some((count, rank_by));
+        self
+    }
+
+    /// Whether any rule is set
+    pub fn is_active(&self) -> bool {
+        self.older_than.is_some()
+            || self.min_size.is_some()
+            || self.max_size.is_some()
+            || self.min_files.is_some()
+            || self.top.is_some()
+    }
+
+    /// Whether a rule needs the whole result list, so results cannot be
+    /// checked one at a time
+    pub fn needs_all_results(&self) -> bool {
+        self.top.is_some()
+    }
+
+    /// Check a single sized target against the per-target rules
+    ///
+    /// Targets with a [keep marker](ScanResult::kept_by) are always left out.
+    pub fn check(&self, result: &ScanResult) -> Result<(), Exclusion> {
+        if let Some(marker) = &result.kept_by {
+            return Err(Exclusion::Kept {
+                marker: marker.clone(),
+            });
+        }
+        if let Some(min_size) = self.min_size {
+            if result.size < min_size {
+                return Err(Exclusion::TooSmall {
+                    size: result.size,
+                    min_size,
+                });
+            }
+        }
+        if let Some(max_size) = self.max_size {
+            if result.size > max_size {
+                return Err(Exclusion::TooLarge {
+                    size: result.size,
+                    max_size,
+                });
+            }
+        }
+        if let Some(min_files) = self.min_files {
+            if result.file_count < min_files {
+                return Err(Exclusion::TooFewFiles {
+                    files: result.file_count,
+                    min_files,
+                });
+            }
+        }
+
+        if let Some(min_age) = self.older_than {
+            let old_enough = result.last_activity.is_some_and(|time| {
+                self.now
+                    .duration_since(time)
+                    .is_ok_and(|age| age >= min_age)
+            });
+            if !old_enough {
+                return Err(Exclusion::RecentlyActive {
+                    last_activity: result.last_activity,
+                    min_age,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Split sized targets into selected and excluded ones
+    pub fn apply(&self, results: Vec<ScanResult>) -> Selection {
+        let mut selection = Selection::default();
+        for result in results {
+            match self.check(&result) {
+                Ok(()) => selection.selected.push(result),
+                Err(reason) => selection.excluded.push(Excluded {
+                    target: result,
+                    reason,
+                }),
+            }
+        }
+
+        if let Some((top, rank_by)) = self.top {
+            // Stable, so equal ranks keep their path order
+            selection
+                .selected
+                .sort_by_key(|result| std::cmp::Reverse(rank_by.key(result)));
+            let rest = selection
+                .selected
+                .split_off(top.min(selection.selected.len()));
+            selection
+                .excluded
+                .extend(rest.into_iter().map(|target| Excluded {
+                    target,
+                    reason: Exclusion::NotInTop { top, rank_by },
+                }));
+        }
+        selection
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::CleanTarget;
+    use std::path::PathBuf;
+
+    const DAY: Duration = Duration::from_secs(86_400);
+
+    fn target(name: &str, days_ago: Option<u64>, now: SystemTime) -> ScanResult {
+        let result = ScanResult::new(PathBuf::from(name), CleanTarget::NodeModules);
+        match days_ago {
+            Some(days) => result.with_last_activity(now - DAY * days as u32),
+            None => result,
+        }
+    }
+
+    #[test]
+    fn test_older_than() {
+        let now = SystemTime::now();
+        let filter = Filter::new().with_older_than(DAY * 30).with_now(now);
+        assert!(filter.is_active());
+
+        let selection = filter.apply(vec![
+            target("/old", Some(45), now),
+            target("/recent", Some(2), now),
+            target("/unknown", None, now),
+        ]);
+
+        assert_eq!(selection.selected.len(), 1);
+        assert_eq!(selection.selected[0].path, PathBuf::from("/old"));
+        assert_eq!(selection.excluded.len(), 2);
+        assert_eq!(selection.excluded[0].reason.kind(), "recently-active");
+        assert!(selection.excluded[0]
+            .reason
+            .to_string()
+            .ends_with("within the last 30 days"));
+        assert_eq!(
+            selection.excluded[1].reason.to_string(),
+            "last activity is unknown"
+        );
+
+        let json = serde_json::to_value(&selection.excluded[1].reason).unwrap();
+        assert_eq!(json["kind"], "recently-active");
+    }
+
+    #[test]
+    fn test_size_rules_and_top() {
+        let now = SystemTime::now();
+        let sized =
+            |name: &str, size: u64, files: usize| target(name, None, now).with_size(size, files);
+        let results = vec![
+            sized("/a", 10, 1),
+            sized("/b", 5_000, 40),
+            sized("/c", 9_000, 2),
+            sized("/d", 100_000, 900),
+            sized("/e", 7_000, 50),
+        ];
+
+        let selection = Filter::new()
+            .with_min_size(1_000)
+            .with_max_size(50_000)
+            .with_min_files(10)
+            .apply(results.clone());
+        let kept: Vec<_> = selection.selected.iter().map(|r| r.path.clone()).collect();
+        assert_eq!(kept, [PathBuf::from("/b"), PathBuf::from("/e")]);
+        let kinds: Vec<_> = selection.excluded.iter().map(|e| e.reason.kind()).collect();
+        assert_eq!(kinds, ["too-small", "too-few-files", "too-large"]);
+
+        // Ranked largest first; the rest is reported as not in the top
+        let selection = Filter::new()
+            .with_top(2, Ranking::Size)
+            .apply(results.clone());
+        let kept: Vec<_> = selection.selected.iter().map(|r| r.path.clone()).collect();
+        assert_eq!(kept, [PathBuf::from("/d"), PathBuf::from("/c")]);
+        assert_eq!(selection.excluded.len(), 3);
+        assert_eq!(
+            selection.excluded[0].reason.to_string(),
+            "not among the 2 largest"
+        );
+
+        let selection = Filter::new()
+            .with_min_size(1_000)
+            .with_top(1, Ranking::Files)
+            .apply(results);
+        assert_eq!(selection.selected[0].path, PathBuf::from("/d"));
+        assert_eq!(selection.excluded.len(), 4);
+    }
+
+    #[test]
+    fn test_default_selects_everything() {
+        let now = SystemTime::now();
+        let filter = Filter::new();
+        assert!(!filter.is_active());
+        let selection = filter.apply(vec![target("/a", None, now), target("/b", Some(0), now)]);
+        assert_eq!(selection.selected.len(), 2);
+        assert!(selection.excluded.is_empty());
+
+        // ...except what a project asks to keep
+        let kept = target("/c", None, now).with_kept_by("/c/../.clean-files-keep");
+        let selection = filter.apply(vec![target("/a", None, now), kept]);
+        assert_eq!(selection.selected.len(), 1);
+        assert_eq!(selection.excluded[0].reason.kind(), "kept");
+    }
+}
@@ -0,0 +1,438 @@
+//! Git-aware safety checks.
+//!
+//! A directory called `build` or `target` is committed source in some
+//! repositories. With git awareness on, the [`Cleaner`](crate::Cleaner) only
+//! removes a target inside a work tree if git would ignore it and none of
+//! the files below it are tracked. Repositories are read natively: the index
+//! for tracked paths, `.gitignore` files and `.git/info/exclude` for ignore
+//! rules, so no `git` executable is needed. Targets outside any repository
+//! are not affected.
+
+use crate::ignore::{IgnoreRules, IgnoreStack};
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Per-directory ignore file read by git
+const GITIGNORE: &str = ".gitignore";
+
+/// What git thinks of a directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum GitStatus {
+    /// Not inside a git work tree
+    Outside,
+    /// Ignored and holding no tracked files, so safe to delete
+    Ignored,
+    /// Neither ignored nor tracked: git would offer to add it
+    NotIgnored,
+    /// Holds this many tracked files
+    Tracked(usize),
+}
+
+/// A work tree with its index loaded
+#[derive(Debug)]
+pub struct Repository {
+    workdir: PathBuf,
+    /// Where `info/exclude` and `config` live; differs from the `.git`
+    /// directory for linked worktrees
+    common_dir: PathBuf,
+    /// Tracked paths relative to the work tree, `/`-separated and sorted
+    tracked: Vec<Vec<u8>>,
+}
+
+impl Repository {
+    /// Find the repository whose work tree contains `path`
+    ///
+    /// `path` itself is not considered a work tree root, so a target with
+    /// its own `.git` is judged by the repository around it.
+    pub fn discover(path: &Path) -> Result<Option<Self>> {
+        for dir in path.ancestors().skip(1) {
+            if let Some(git_dir) = git_dir(dir)? {
+                return Self::open(dir, &git_dir).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Load the repository with work tree `workdir` and `.git` directory
+    /// `git_dir`
+    pub fn open(workdir: &Path, git_dir: &Path) -> Result<Self> {
+        let common_dir = match fs::read_to_string(git_dir.join("commondir")) {
+            Ok(common) => git_dir.join(common.trim()),
+            Err(_) => git_dir.to_path_buf(),
+        };
+        let hash_len = if uses_sha256(&common_dir) { 32 } else { 20 };
+        let index = git_dir.join("index");
+        let mut tracked = match fs::read(&index) {
+            Ok(data) => parse_index(&data, hash_len)
+                .with_context(|| format!("Unreadable git index: {}", index.display()))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", index.display())),
+        };
+        tracked.sort();
+        Ok(Self {
+            workdir: workdir.to_path_buf(),
+            common_dir,
+            tracked,
+        })
+    }
+
+    /// Root of the work tree
+    pub fn workdir(&self) -> &Path {
+        &self.workdir
+    }
+
+    /// Number of tracked files at or below `path`
+    pub fn tracked_below(&self, path: &Path) -> usize {
+        let Some(relative) = self.relative(path) else {
+            return 0;
+        };
+        let mut prefix = relative.clone();
+        prefix.push(b'/');
+        let start = self
+            .tracked
+            .partition_point(|p| p.as_slice() < relative.as_slice());
+        self.tracked[start..]
+            .iter()
+            .take_while(|p| **p == relative || p.starts_with(&prefix))
+            .count()
+    }
+
+    /// Whether git ignores `path`, directly or through an ignored parent
+    ///
+    /// `.gitignore` files from the work tree root down to `path` apply, the
+    /// deepest one first, then `info/exclude`.
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> Result<bool> {
+        let Ok(relative) = path.strip_prefix(&self.workdir) else {
+            return Ok(false);
+        };
+
+        let mut ignores = IgnoreStack::new();
+        let exclude = self.common_dir.join("info").join("exclude");
+        match fs::read_to_string(&exclude) {
+            Ok(text) => {
+                ignores = ignores.push(IgnoreRules::parse(&self.workdir, &text, Some(&exclude))?)
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", exclude.display()))
+            }
+        }
+        ignores = ignores.enter(&self.workdir, GITIGNORE)?;
+
+        // Git never looks inside an ignored directory, so the first ignored
+        // ancestor decides
+        let mut current = self.workdir.clone();
+        let components: Vec<_> = relative.components().collect();
+        for (i, component) in components.iter().enumerate() {
+            current.push(component);
+            let last = i + 1 == components.len();
+            if ignores.matched(&current, !last || is_dir).is_some() {
+                return Ok(true);
+            }
+            if !last {
+                ignores = ignores.enter(&current, GITIGNORE)?;
+            }
+        }
+        Ok(false)
+    }
+
+    /// What git thinks of the directory `path` inside this work tree
+    pub fn status(&self, path: &Path) -> Result<GitStatus> {
+        let tracked = self.tracked_below(path);
+        if tracked > 0 {
+            return Ok(GitStatus::Tracked(tracked));
+        }
+        Ok(if self.is_ignored(path, true)? {
+            GitStatus::Ignored
+        } else {
+            GitStatus::NotIgnored
+        })
+    }
+
+    /// `path` below the work tree as index bytes
+    fn relative(&self, path: &Path) -> Option<Vec<u8>> {
+        let relative = path.strip_prefix(&self.workdir).ok()?;
+        let mut bytes = Vec::new();
+        for component in relative.components() {
+            let Component::Normal(name) = component else {
+                return None;
+            };
+            if !bytes.is_empty() {
+                bytes.push(b'/');
+            }
+            bytes.extend_from_slice(name.as_encoded_bytes());
+        }
+        Some(bytes)
+    }
+}
+
+/// Checks targets against the repositories containing them, loading each
+/// repository's index once
+#[derive(Debug, Default)]
+pub struct GitGuard {
+    repositories: Mutex<HashMap<PathBuf, Arc<Repository>>>,
+}
+
+impl GitGuard {
+    /// A guard with nothing loaded yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// What git thinks of the directory `target`
+    pub fn status(&self, target: &Path) -> Result<GitStatus> {
+        let Some(workdir) = target
+            .ancestors()
+            .skip(1)
+            .find(|dir| dir.join(".git").exists())
+        else {
+            return Ok(GitStatus::Outside);
+        };
+
+        let repository = {
+            let mut repositories = self.repositories.lock().unwrap();
+            match repositories.get(workdir) {
+                Some(repository) => repository.clone(),
+                None => {
+                    let git_dir = git_dir(workdir)?.context("Not a git work tree")?;
+                    let repository = Arc::new(Repository::open(workdir, &git_dir)?);
+                    repositories.insert(workdir.to_path_buf(), repository.clone());
+                    repository
+                }
+            }
+        };
+        repository.status(target)
+    }
+}
+
+/// The `.git` directory of a work tree rooted at `dir`, if it is one
+///
+/// Linked worktrees and submodules have a `.git` file pointing elsewhere.
+fn git_dir(dir: &Path) -> Result<Option<PathBuf>> {
+    let dot_git = dir.join(".git");
+    let Ok(metadata) = fs::metadata(&dot_git) else {
+        return Ok(None);
+    };
+    if metadata.is_dir() {
+        return Ok(Some(dot_git));
+    }
+    let text = fs::read_to_string(&dot_git)
+        .with_context(|| format!("Failed to read {}", dot_git.display()))?;
+    match text.trim().strip_prefix("gitdir:") {
+        Some(target) => Ok(Some(dir.join(target.trim()))),
+        None => bail!("{} does not point to a git directory", dot_git.display()),
+    }
+}
+
+/// Whether the repository's objects are named by SHA-256
+fn uses_sha256(common_dir: &Path) -> bool {
+    fs::read_to_string(common_dir.join("config")).is_ok_and(|config| {
+        config.lines().any(|line| {
+            let line: String = line.split_whitespace().collect();
+            line.eq_ignore_ascii_case("objectformat=sha256")
+        })
+    })
+}
+
+/// Paths of the entries in a version 2, 3 or 4 index file
+fn parse_index(data: &[u8], hash_len: usize) -> Result<Vec<Vec<u8>>> {
+    let u32_at = |pos: usize| -> Result<u32> {
+        match data.get(pos..pos + 4) {
+            Some(bytes) => Ok(u32::from_be_bytes(bytes.try_into().unwrap())),
+            None => bail!("truncated at byte {}", pos),
+        }
+    };
+    let u16_at = |pos: usize| -> Result<u16> {
+        match data.get(pos..pos + 2) {
+            Some(bytes) => Ok(u16::from_be_bytes(bytes.try_into().unwrap())),
+            None => bail!("truncated at byte {}", pos),
+        }
+    };
+    if data.get(..4) != Some(b"DIRC") {
+        bail!("not an index file");
+    }
+    let version = u32_at(4)?;
+    if !(2..=4).contains(&version) {
+        bail!("unsupported index version {}", version);
+    }
+    let count = u32_at(8)? as usize;
+
+    let mut paths: Vec<Vec<u8>> = Vec::with_capacity(count);
+    let mut pos = 12;
+    for _ in 0..count {
+        let start = pos;
+        // ctime, mtime, dev, ino, mode, uid, gid and size, then the object
+        // name and the flags
+        let flags_at = start + 40 + hash_len;
+        let flags = u16_at(flags_at)?;
+        pos = flags_at + 2;
+        if version >= 3 && flags & 0x4000 != 0 {
+            pos += 2;
+        }
+
+        let path = if version == 4 {
+            // The path is stored as "drop N bytes of the previous path, then
+            // append this"
+            let (strip, used) = varint(data.get(pos..).unwrap_or_default())?;
+            pos += used;
+            let end = nul(data, pos)?;
+            let previous = paths.last().map(Vec::as_slice).unwrap_or_default();
+            if strip > previous.len() {
+                bail!("bad path compression at byte {}", pos);
+            }
+            let mut path = previous[..previous.len() - strip].to_vec();
+            path.extend_from_slice(&data[pos..end]);
+            pos = end + 1;
+            path
+        } else {
+            // NUL-padded to a multiple of eight bytes
+            let end = nul(data, pos)?;
+            let path = data[pos..end].to_vec();
+            pos = start + ((end - start + 8) & !7);
+            path
+        };
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+/// Position of the NUL ending the string at `pos`
+fn nul(data: &[u8], pos: usize) -> Result<usize> {
+    match data
+        .get(pos..)
+        .and_then(|rest| rest.iter().position(|&b| b == 0))
+    {
+        Some(offset) => Ok(pos + offset),
+        None => bail!("unterminated path at byte {}", pos),
+    }
+}
+
+/// Git's offset varint; returns the value and the bytes used
+fn varint(data: &[u8]) -> Result<(usize, usize)> {
+    let mut bytes = data.iter();
+    let mut byte = *bytes.next().context("truncated varint")?;
+    let mut value = usize::from(byte & 0x7f);
+    let mut used = 1;
+    while byte & 0x80 != 0 {
+        byte = *bytes.next().context("truncated varint")?;
+        used += 1;
+        value = ((value + 1) << 7) | usize::from(byte & 0x7f);
+    }
+    Ok((value, used))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    /// An index holding `paths`, written the way git does
+    fn index(version: u32, paths: &[&str]) -> Vec<u8> {
+        let mut data = b"DIRC".to_vec();
+        data.extend(version.to_be_bytes());
+        data.extend((paths.len() as u32).to_be_bytes());
+        let mut previous: &[u8] = b"";
+        for path in paths {
+            let start = data.len();
+            data.extend([0u8; 40 + 20]);
+            data.extend((path.len().min(0xfff) as u16).to_be_bytes());
+            if version == 4 {
+                let common = previous
+                    .iter()
+                    .zip(path.as_bytes())
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                data.push((previous.len() - common) as u8);
+                data.extend(&path.as_bytes()[common..]);
+                data.push(0);
+            } else {
+                data.extend(path.as_bytes());
+                let len = data.len() - start;
+                data.resize(start + ((len + 8) & !7), 0);
+            }
+            previous = path.as_bytes();
+        }
+        data
+    }
+
+    #[test]
+    fn test_parse_index() {
+        let paths = ["Cargo.toml", "build/gen.rs", "build/x/y.rs", "src/main.rs"];
+        for version in [2, 3, 4] {
+            let parsed = parse_index(&index(version, &paths), 20).unwrap();
+            let parsed: Vec<_> = parsed.iter().map(|p| String::from_utf8_lossy(p)).collect();
+            assert_eq!(parsed, paths, "version {}", version);
+        }
+        assert!(parse_index(b"DIRC\0\0\0\x09\0\0\0\0", 20).is_err());
+        assert!(parse_index(&index(2, &paths)[..40], 20).is_err());
+        assert_eq!(varint(&[0x80, 0x00]).unwrap(), (128, 2));
+    }
+
+    #[test]
+    fn test_status() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join(".git/info")).unwrap();
+        fs::write(root.join(".git/info/exclude"), "/scratch\n").unwrap();
+        fs::write(root.join(".gitignore"), "target/\n").unwrap();
+        fs::create_dir_all(root.join("app/out")).unwrap();
+        fs::write(root.join("app/.gitignore"), "out\n!target\n").unwrap();
+        fs::write(
+            root.join(".git/index"),
+            index(2, &["build/gen.rs", "src/lib.rs"]),
+        )
+        .unwrap();
+
+        let guard = GitGuard::new();
+        let status = |path: &str| guard.status(&root.join(path)).unwrap();
+        assert_eq!(status("target"), GitStatus::Ignored);
+        assert_eq!(status("build"), GitStatus::Tracked(1));
+        assert_eq!(status("node_modules"), GitStatus::NotIgnored);
+        assert_eq!(status("scratch"), GitStatus::Ignored);
+        assert_eq!(status("app/out"), GitStatus::Ignored);
+        assert_eq!(status("app/target"), GitStatus::NotIgnored);
+        assert_eq!(status("app/out/node_modules"), GitStatus::Ignored);
+        assert_eq!(status("buil"), GitStatus::NotIgnored);
+        assert_eq!(guard.status(Path::new("/")).unwrap(), GitStatus::Outside);
+    }
+
+    #[test]
+    fn test_real_repository() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let git = |args: &[&str]| {
+            Command::new("git")
+                .args(args)
+                .current_dir(root)
+                .output()
+                .is_ok_and(|out| out.status.success())
+        };
+        // Only runs where git is installed
+        if !git(&["init", "-q"]) {
+            return;
+        }
+        fs::create_dir_all(root.join("build")).unwrap();
+        fs::create_dir_all(root.join("target/debug")).unwrap();
+        fs::write(root.join("build/keep.c"), "int x;").unwrap();
+        fs::write(root.join("target/debug/app"), "bin").unwrap();
+        fs::write(root.join(".gitignore"), "/target\n").unwrap();
+        assert!(git(&["add", "."]));
+        assert!(git(&["update-index", "--index-version", "4"]));
+
+        let guard = GitGuard::new();
+        assert_eq!(
+            guard.status(&root.join("build")).unwrap(),
+            GitStatus::Tracked(1)
+        );
+        assert_eq!(
+            guard.status(&root.join("target")).unwrap(),
+            GitStatus::Ignored
+        );
+    }
+}
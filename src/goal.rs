@@ -0,0 +1,284 @@
+//! Free-space goals: clean only until a filesystem has enough room.
+//!
+//! A [`SpaceGoal`] says how much space should be available, either as bytes
+//! or as a share of the filesystem. A [`FreeSpaceGoal`] ties it to the
+//! filesystem holding a path and a [`Strategy`] for ranking candidates; it
+//! chooses the fewest targets whose estimated sizes reach the goal. The
+//! [`Cleaner`](crate::Cleaner) then deletes them one by one, re-reading the
+//! free space after each, and stops as soon as the goal is met.
+
+use crate::filter::{Excluded, Exclusion, Selection};
+use crate::platform::{device_id, disk_space, DiskSpace};
+use crate::types::ScanResult;
+use crate::utils::{format_size, parse_size};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::SystemTime;
+
+/// How much space should be available
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpaceGoal {
+    /// At least this many bytes
+    Bytes(u64),
+    /// At least this percentage of the filesystem's size
+    Percent(f64),
+}
+
+impl SpaceGoal {
+    /// Bytes that must be available on a filesystem of this size
+    pub fn required(&self, space: DiskSpace) -> u64 {
+        match self {
+            SpaceGoal::Bytes(bytes) => *bytes,
+            SpaceGoal::Percent(percent) => (space.total as f64 * percent / 100.0) as u64,
+        }
+    }
+}
+
+/// Parses `50G`, `1.5T`, `4096` or `15%`
+impl FromStr for SpaceGoal {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().strip_suffix('%') {
+            Some(percent) => match percent.trim().parse::<f64>() {
+                Ok(p) if (0.0..=100.0).contains(&p) => Ok(SpaceGoal::Percent(p)),
+                _ => Err(format!("invalid percentage '{}' (expected 0-100%)", s)),
+            },
+            None => parse_size(s).map(SpaceGoal::Bytes),
+        }
+    }
+}
+
+impl fmt::Display for SpaceGoal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpaceGoal::Bytes(bytes) => write!(f, "{}", format_size(*bytes)),
+            SpaceGoal::Percent(percent) => write!(f, "{}%", percent),
+        }
+    }
+}
+
+/// Order in which candidates are chosen to reach a goal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Strategy {
+    /// Biggest first, so the fewest directories are removed
+    #[default]
+    Largest,
+    /// Longest untouched first
+    Oldest,
+    /// Size weighted by the days since the last activity
+    Mixed,
+}
+
+impl Strategy {
+    /// Sort candidates, best first
+    ///
+    /// The sort is stable, so ties keep their scan order. Targets whose last
+    /// activity is unknown count as active just now.
+    pub fn rank(&self, results: &mut [ScanResult], now: SystemTime) {
+        let age = |result: &ScanResult| {
+            result
+                .last_activity
+                .and_then(|time| now.duration_since(time).ok())
+                .unwrap_or_default()
+        };
+        match self {
+            Strategy::Largest => results.sort_by_key(|r| Reverse(r.size)),
+            Strategy::Oldest => results.sort_by_key(|r| Reverse(age(r))),
+            Strategy::Mixed => results.sort_by_key(|r| {
+                let days = age(r).as_secs() / 86_400;
+                Reverse(u128::from(r.size) * u128::from(days + 1))
+            }),
+        }
+    }
+}
+
+impl fmt::Display for Strategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Strategy::Largest => "largest",
+            Strategy::Oldest => "oldest",
+            Strategy::Mixed => "mixed",
+        })
+    }
+}
+
+/// What came of a free-space goal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct GoalOutcome {
+    /// Bytes that had to be available
+    pub required: u64,
+    /// Bytes available before cleaning
+    pub available_before: u64,
+    /// Bytes available afterwards; estimated from target sizes in a dry run
+    pub available: u64,
+    /// Whether `available` reaches `required`
+    pub met: bool,
+    /// Whether `available` is an estimate rather than a measurement
+    pub estimated: bool,
+}
+
+/// A [`SpaceGoal`] for the filesystem holding a path, with a [`Strategy`]
+#[derive(Debug, Clone)]
+pub struct FreeSpaceGoal {
+    path: PathBuf,
+    goal: SpaceGoal,
+    strategy: Strategy,
+    now: SystemTime,
+}
+
+impl FreeSpaceGoal {
+    /// Aim for `goal` on the filesystem holding `path`
+    pub fn new(path: impl Into<PathBuf>, goal: SpaceGoal) -> Self {
+        Self {
+            path: path.into(),
+            goal,
+            strategy: Strategy::default(),
+            now: SystemTime::now(),
+        }
+    }
+
+    /// Rank candidates with `strategy` (default: [`Strategy::Largest`])
+    pub fn with_strategy(mut self, strategy: Strategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Measure ages from `now` instead of the time the goal was created
+    pub fn with_now(mut self, now: SystemTime) -> Self {
+        self.now = now;
+        self
+    }
+
+    /// The path whose filesystem is measured
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The amount asked for
+    pub fn goal(&self) -> SpaceGoal {
+        self.goal
+    }
+
+    /// Current free space of the filesystem
+    pub fn space(&self) -> Result<DiskSpace> {
+        disk_space(&self.path)
+    }
+
+    /// Pick the targets needed to reach the goal from `space`
+    ///
+    /// Candidates are ranked by the strategy and taken until their sizes
+    /// add up to the missing space. Targets on other filesystems cannot
+    /// help and are left out, as is everything after the goal is reached.
+    pub fn choose(&self, mut results: Vec<ScanResult>, space: DiskSpace) -> Selection {
+        let required = self.goal.required(space);
+        let device = device_id(&self.path).ok();
+        self.strategy.rank(&mut results, self.now);
+
+        let mut selection = Selection::default();
+        let mut expected = space.available;
+        for result in results {
+            let reason = if device.is_some() && device_id(&result.path).ok() != device {
+                Some(Exclusion::OtherFilesystem)
+            } else if expected >= required {
+                Some(Exclusion::GoalReached { required })
+            } else {
+                None
+            };
+            match reason {
+                Some(reason) => selection.excluded.push(Excluded {
+                    target: result,
+                    reason,
+                }),
+                None => {
+                    expected = expected.saturating_add(result.size);
+                    selection.selected.push(result);
+                }
+            }
+        }
+        selection
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::CleanTarget;
+    use std::fs;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    const GB: u64 = 1024 * 1024 * 1024;
+    const DAY: Duration = Duration::from_secs(86_400);
+
+    #[test]
+    fn test_parse_goal() {
+        assert_eq!("50G".parse(), Ok(SpaceGoal::Bytes(50 * GB)));
+        assert_eq!("15%".parse(), Ok(SpaceGoal::Percent(15.0)));
+        assert!("150%".parse::<SpaceGoal>().is_err());
+        assert!("lots".parse::<SpaceGoal>().is_err());
+
+        let space = DiskSpace::new(10 * GB, 200 * GB);
+        assert_eq!(SpaceGoal::Percent(25.0).required(space), 50 * GB);
+        assert_eq!(SpaceGoal::Bytes(GB).required(space), GB);
+    }
+
+    #[test]
+    fn test_strategies() {
+        let now = SystemTime::now();
+        let target = |name: &str, size: u64, days: u32| {
+            ScanResult::new(PathBuf::from(name), CleanTarget::RustTarget)
+                .with_size(size, 1)
+                .with_last_activity(now - DAY * days)
+        };
+        let results = vec![
+            target("/big-new", 100, 0),
+            target("/small-old", 10, 300),
+            target("/mid", 50, 10),
+        ];
+        let order = |strategy: Strategy| {
+            let mut results = results.clone();
+            strategy.rank(&mut results, now);
+            results
+                .into_iter()
+                .map(|r| r.path.to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(order(Strategy::Largest), ["/big-new", "/mid", "/small-old"]);
+        assert_eq!(order(Strategy::Oldest), ["/small-old", "/mid", "/big-new"]);
+        // 10 * 301 > 50 * 11 > 100 * 1
+        assert_eq!(order(Strategy::Mixed), ["/small-old", "/mid", "/big-new"]);
+    }
+
+    #[test]
+    fn test_choose_stops_at_goal() {
+        let temp_dir = TempDir::new().unwrap();
+        let results: Vec<_> = [("a", 4 * GB), ("b", 8 * GB), ("c", GB)]
+            .into_iter()
+            .map(|(name, size)| {
+                let path = temp_dir.path().join(name);
+                fs::create_dir(&path).unwrap();
+                ScanResult::new(path, CleanTarget::NodeModules).with_size(size, 1)
+            })
+            .collect();
+
+        // 5 GB free, 15 GB wanted: the 8 GB and 4 GB targets are enough
+        let goal = FreeSpaceGoal::new(temp_dir.path(), SpaceGoal::Bytes(15 * GB));
+        let selection = goal.choose(results.clone(), DiskSpace::new(5 * GB, 100 * GB));
+        let chosen: Vec<_> = selection.selected.iter().map(|r| r.size).collect();
+        assert_eq!(chosen, [8 * GB, 4 * GB]);
+        assert_eq!(selection.excluded.len(), 1);
+        assert_eq!(selection.excluded[0].reason.kind(), "goal-reached");
+
+        // Already there: nothing is needed
+        let selection = goal.choose(results, DiskSpace::new(20 * GB, 100 * GB));
+        assert!(selection.selected.is_empty());
+        assert_eq!(selection.excluded.len(), 3);
+    }
+}
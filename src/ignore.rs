@@ -0,0 +1,356 @@
+//! Exclusion patterns in gitignore syntax.
+//!
+//! Trees can be shielded from the scanner in two ways: glob patterns passed
+//! to [`Scanner::with_excludes`](crate::Scanner::with_excludes), and
+//! `.cleanfilesignore` files. The files use the same syntax as `.gitignore`
+//! and may appear at the scan root or in any directory below it; each applies
+//! to its own directory and everything beneath, and a file deeper in the tree
+//! overrides the ones above it (including re-including with `!pattern`).
+//!
+//! ```text
+//! # Never touch the VM images, wherever they are
+//! customer-vm-images/
+//! # Keep this one build tree
+//! /legacy/target
+//! ```
+//!
+//! A matching directory is neither entered nor reported, so nothing inside
+//! it can be deleted.
+
+use anyhow::{Context, Result};
+use globset::{GlobBuilder, GlobMatcher};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Name of the per-directory ignore file
+pub const IGNORE_FILE: &str = ".cleanfilesignore";
+
+/// One compiled pattern
+#[derive(Debug, Clone)]
+struct Rule {
+    /// The pattern as written
+    pattern: String,
+    matcher: GlobMatcher,
+    /// `!pattern`: re-include what an earlier rule excluded
+    negated: bool,
+    /// `pattern/`: only match directories
+    dir_only: bool,
+    /// Matched against the full path instead of the path below the base
+    absolute: bool,
+    /// An exclude glob without a slash, which also matches the name of
+    /// paths outside the base
+    floating: bool,
+}
+
+/// Patterns from one source, matched relative to a base directory
+#[derive(Debug, Clone)]
+pub struct IgnoreRules {
+    base: PathBuf,
+    source: Option<PathBuf>,
+    rules: Vec<Rule>,
+}
+
+/// The rule that excluded a path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IgnoreMatch<'a> {
+    /// The pattern as written
+    pub pattern: &'a str,
+    /// File the pattern came from; `None` for exclude globs
+    pub source: Option<&'a Path>,
+}
+
+impl fmt::Display for IgnoreMatch<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.source {
+            Some(file) => write!(f, "'{}' in {}", self.pattern, file.display()),
+            None => write!(f, "exclude pattern '{}'", self.pattern),
+        }
+    }
+}
+
+impl IgnoreRules {
+    /// Parse the lines of a gitignore-style file that applies below `base`
+    pub fn parse(base: &Path, text: &str, source: Option<&Path>) -> Result<Self> {
+        let mut rules = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let rule = compile(line, false).with_context(|| match source {
+                Some(file) => format!(
+                    "Invalid pattern on line {} of {}",
+                    number + 1,
+                    file.display()
+                ),
+                None => format!("Invalid pattern on line {}", number + 1),
+            })?;
+            rules.extend(rule);
+        }
+        Ok(Self {
+            base: base.to_path_buf(),
+            source: source.map(Path::to_path_buf),
+            rules,
+        })
+    }
+
+    /// Compile exclude globs given for a scan of `base`
+    ///
+    /// They follow the gitignore rules, except that an absolute path such as
+    /// `/home/me/work/vm-images` is matched against the full path rather than
+    /// anchored at `base`, and a bare name also matches directories outside
+    /// `base`, such as a shared build directory.
+    pub fn from_globs(base: &Path, globs: &[String]) -> Result<Self> {
+        let rules = globs
+            .iter()
+            .map(|glob| {
+                compile(glob, true).with_context(|| format!("Invalid exclude pattern '{}'", glob))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            base: base.to_path_buf(),
+            source: None,
+            rules: rules.into_iter().flatten().collect(),
+        })
+    }
+
+    /// Read the ignore file named `file_name` in `dir`, if there is one
+    pub fn load(dir: &Path, file_name: &str) -> Result<Option<Self>> {
+        let path = dir.join(file_name);
+        match fs::read_to_string(&path) {
+            Ok(text) => Self::parse(dir, &text, Some(&path)).map(Some),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+
+    /// Whether there are no patterns at all
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// The last rule matching `path`, which decides whether it is excluded
+    ///
+    /// Returns `None` if no rule matches, `Some(None)` if the deciding rule
+    /// re-includes it.
+    fn decide(&self, path: &Path, is_dir: bool) -> Option<Option<IgnoreMatch<'_>>> {
+        let relative = path.strip_prefix(&self.base).ok();
+        let rule = self.rules.iter().rev().find(|rule| {
+            if rule.dir_only && !is_dir {
+                return false;
+            }
+            match relative {
+                _ if rule.absolute => rule.matcher.is_match(path),
+                Some(relative) => {
+                    !relative.as_os_str().is_empty() && rule.matcher.is_match(relative)
+                }
+                None => {
+                    rule.floating
+                        && path
+                            .file_name()
+                            .is_some_and(|name| rule.matcher.is_match(name))
+                }
+            }
+        })?;
+        Some((!rule.negated).then_some(IgnoreMatch {
+            pattern: &rule.pattern,
+            source: self.source.as_deref(),
+        }))
+    }
+
+    /// The rule excluding `path`, if any
+    pub fn matched(&self, path: &Path, is_dir: bool) -> Option<IgnoreMatch<'_>> {
+        self.decide(path, is_dir).flatten()
+    }
+}
+
+/// Compile one line; `None` for blank lines and comments
+fn compile(line: &str, allow_absolute: bool) -> Result<Option<Rule>> {
+    // Trailing spaces are ignored unless escaped
+    let mut line = line.trim_end_matches('\r');
+    while line.ends_with(' ') && !line.ends_with("\\ ") {
+        line = &line[..line.len() - 1];
+    }
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+
+    let (negated, mut pattern) = match line.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    let dir_only = pattern.ends_with('/');
+    pattern = pattern.trim_end_matches('/');
+    if pattern.is_empty() {
+        return Ok(None);
+    }
+
+    let absolute = allow_absolute && Path::new(pattern).is_absolute();
+    let floating = allow_absolute && !absolute && !pattern.contains('/');
+    let glob = if absolute {
+        pattern.to_string()
+    } else if let Some(anchored) = pattern.strip_prefix('/') {
+        anchored.to_string()
+    } else if pattern.contains('/') {
+        // A slash anywhere but the end anchors the pattern to the base
+        pattern.to_string()
+    } else {
+        format!("**/{}", pattern)
+    };
+
+    let matcher = GlobBuilder::new(&glob)
+        .literal_separator(true)
+        .backslash_escape(true)
+        .build()?
+        .compile_matcher();
+    Ok(Some(Rule {
+        pattern: line.to_string(),
+        matcher,
+        negated,
+        dir_only,
+        absolute,
+        floating,
+    }))
+}
+
+/// The ignore files in effect for one directory of a walk
+///
+/// Cheap to clone; [`enter`](IgnoreStack::enter) returns the stack for a
+/// subdirectory without touching its parent.
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreStack {
+    top: Option<Arc<Frame>>,
+}
+
+#[derive(Debug)]
+struct Frame {
+    rules: IgnoreRules,
+    parent: Option<Arc<Frame>>,
+}
+
+impl IgnoreStack {
+    /// An empty stack
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add rules that override everything already on the stack
+    pub fn push(&self, rules: IgnoreRules) -> Self {
+        Self {
+            top: Some(Arc::new(Frame {
+                rules,
+                parent: self.top.clone(),
+            })),
+        }
+    }
+
+    /// The stack for `dir`: this one plus `dir`'s own `file_name`, if any
+    pub fn enter(&self, dir: &Path, file_name: &str) -> Result<Self> {
+        Ok(match IgnoreRules::load(dir, file_name)? {
+            Some(rules) if !rules.is_empty() => self.push(rules),
+            _ => self.clone(),
+        })
+    }
+
+    /// The rule excluding `path`, if any
+    ///
+    /// The deepest file with a matching rule decides.
+    pub fn matched(&self, path: &Path, is_dir: bool) -> Option<IgnoreMatch<'_>> {
+        let mut frame = self.top.as_deref();
+        while let Some(current) = frame {
+            if let Some(decision) = current.rules.decide(path, is_dir) {
+                return decision;
+            }
+            frame = current.parent.as_deref();
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_gitignore_syntax() {
+        let base = Path::new("/src");
+        let text = "# comment\n\nbuild/\n/top-only\ndocs/*/out\n*.tmp\n!keep.tmp\n\\#hash\n";
+        let rules = IgnoreRules::parse(base, text, None).unwrap();
+        let excluded = |path: &str, is_dir: bool| rules.matched(&base.join(path), is_dir).is_some();
+
+        assert!(excluded("build", true));
+        assert!(excluded("a/b/build", true));
+        assert!(!excluded("build", false));
+        assert!(excluded("top-only", true));
+        assert!(!excluded("a/top-only", true));
+        assert!(excluded("docs/api/out", true));
+        assert!(!excluded("docs/api/v1/out", true));
+        assert!(excluded("a/x.tmp", false));
+        assert!(!excluded("a/keep.tmp", false));
+        assert!(excluded("#hash", true));
+        assert!(!excluded("src", true));
+
+        let matched = rules.matched(&base.join("x/build"), true).unwrap();
+        assert_eq!(matched.pattern, "build/");
+        assert!(IgnoreRules::parse(base, "[unclosed", None).is_err());
+    }
+
+    #[test]
+    fn test_exclude_globs() {
+        let base = Path::new("/home/me");
+        let globs = [
+            "/home/me/work/vm-images".to_string(),
+            "vendor".to_string(),
+            "clients/*/legacy".to_string(),
+        ];
+        let rules = IgnoreRules::from_globs(base, &globs).unwrap();
+
+        assert!(rules
+            .matched(Path::new("/home/me/work/vm-images"), true)
+            .is_some());
+        assert!(rules
+            .matched(Path::new("/home/me/a/vendor"), true)
+            .is_some());
+        assert!(rules
+            .matched(Path::new("/home/me/clients/x/legacy"), true)
+            .is_some());
+        assert!(rules
+            .matched(Path::new("/home/me/y/clients/x/legacy"), true)
+            .is_none());
+        // Outside the base only name patterns apply
+        assert!(rules.matched(Path::new("/srv/vendor"), true).is_some());
+        assert!(rules
+            .matched(Path::new("/srv/clients/x/legacy"), true)
+            .is_none());
+        assert_eq!(
+            rules
+                .matched(Path::new("/home/me/vendor"), true)
+                .unwrap()
+                .to_string(),
+            "exclude pattern 'vendor'"
+        );
+    }
+
+    #[test]
+    fn test_stack_precedence() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let app = root.join("app");
+        fs::create_dir(&app).unwrap();
+        fs::write(root.join(IGNORE_FILE), "target/\n").unwrap();
+        fs::write(app.join(IGNORE_FILE), "!target\n").unwrap();
+
+        let stack = IgnoreStack::new().enter(root, IGNORE_FILE).unwrap();
+        assert!(stack.matched(&root.join("lib/target"), true).is_some());
+        let stack = stack.enter(&app, IGNORE_FILE).unwrap();
+        assert!(stack.matched(&app.join("target"), true).is_none());
+        assert!(stack.matched(&root.join("lib/target"), true).is_some());
+
+        // No file: the parent's rules carry over
+        let empty = root.join("empty");
+        fs::create_dir(&empty).unwrap();
+        let stack = IgnoreStack::new().enter(root, IGNORE_FILE).unwrap();
+        let below = stack.enter(&empty, IGNORE_FILE).unwrap();
+        assert!(below.matched(&empty.join("target"), true).is_some());
+    }
+}
@@ -0,0 +1,155 @@
+//! Project-declared opt-outs from cleaning.
+//!
+//! Some build outputs are slow or impossible to regenerate: a `node_modules`
+//! with patched packages, a `target` cross-compiled for hardware that is
+//! gone. A project protects the targets next to its manifest with any of:
+//!
+//! - an empty `.clean-files-keep` file in the project directory
+//! - `keep = true` under `[package.metadata.clean-files]` (or
+//!   `[workspace.metadata.clean-files]`) in `Cargo.toml`
+//! - `"cleanFiles": { "keep": true }` in `package.json`
+//!
+//! Targets kept away from their manifest are protected by the projects
+//! their detector names in
+//! [`keep_dirs`](crate::detectors::TargetDetector::keep_dirs): the package
+//! building into a shared Cargo target dir, the members of a workspace, the
+//! source tree of a CMake build or the workspace of a Bazel output base.
+//!
+//! The [`Scanner`](crate::Scanner) records the marker on each result, the
+//! [`Filter`](crate::filter::Filter) leaves such targets out, and the
+//! [`Cleaner`](crate::Cleaner) checks again right before deleting.
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Name of the file that protects every target next to it
+pub const KEEP_FILE: &str = ".clean-files-keep";
+
+/// What protects a target
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum KeepMarker {
+    /// A [`KEEP_FILE`] in the project directory
+    KeepFile(PathBuf),
+    /// `keep = true` in a `Cargo.toml`'s `clean-files` metadata
+    CargoMetadata(PathBuf),
+    /// `"cleanFiles": {"keep": true}` in a `package.json`
+    PackageJson(PathBuf),
+}
+
+impl fmt::Display for KeepMarker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeepMarker::KeepFile(path) => write!(f, "{}", path.display()),
+            KeepMarker::CargoMetadata(path) => {
+                write!(f, "metadata.clean-files.keep in {}", path.display())
+            }
+            KeepMarker::PackageJson(path) => write!(f, "cleanFiles.keep in {}", path.display()),
+        }
+    }
+}
+
+/// The marker protecting `target`, if its project declares one
+///
+/// Only the directory containing `target` is consulted. Manifests that
+/// cannot be read or parsed protect nothing; the detectors decide whether
+/// such a project is a project at all.
+pub fn keep_marker(target: &Path) -> Option<KeepMarker> {
+    project_keep_marker(target.parent()?)
+}
+
+/// The marker in the project directory `project`, if it declares one
+pub fn project_keep_marker(project: &Path) -> Option<KeepMarker> {
+    let keep_file = project.join(KEEP_FILE);
+    if keep_file.is_file() {
+        return Some(KeepMarker::KeepFile(keep_file));
+    }
+
+    let cargo_toml = project.join("Cargo.toml");
+    if cargo_keeps(&cargo_toml) {
+        return Some(KeepMarker::CargoMetadata(cargo_toml));
+    }
+
+    let package_json = project.join("package.json");
+    if package_json_keeps(&package_json) {
+        return Some(KeepMarker::PackageJson(package_json));
+    }
+
+    None
+}
+
+fn cargo_keeps(path: &Path) -> bool {
+    let Ok(text) = fs::read_to_string(path) else {
+        return false;
+    };
+    let Ok(manifest) = toml::from_str::<toml::Table>(&text) else {
+        return false;
+    };
+    ["package", "workspace"].iter().any(|section| {
+        manifest
+            .get(*section)
+            .and_then(|s| s.get("metadata"))
+            .and_then(|m| m.get("clean-files"))
+            .and_then(|c| c.get("keep"))
+            .and_then(|k| k.as_bool())
+            == Some(true)
+    })
+}
+
+fn package_json_keeps(path: &Path) -> bool {
+    let Ok(text) = fs::read_to_string(path) else {
+        return false;
+    };
+    let Ok(package) = serde_json::from_str::<serde_json::Value>(&text) else {
+        return false;
+    };
+    package["cleanFiles"]["keep"] == serde_json::Value::Bool(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_keep_markers() {
+        let temp_dir = TempDir::new().unwrap();
+        let project = temp_dir.path();
+        let target = project.join("target");
+        assert_eq!(keep_marker(&target), None);
+
+        fs::write(
+            project.join("Cargo.toml"),
+            "[package]\nname = \"fw\"\n\n[package.metadata.clean-files]\nkeep = true\n",
+        )
+        .unwrap();
+        assert_eq!(
+            keep_marker(&target),
+            Some(KeepMarker::CargoMetadata(project.join("Cargo.toml")))
+        );
+
+        fs::write(
+            project.join("Cargo.toml"),
+            "[workspace.metadata.clean-files]\nkeep = false\n",
+        )
+        .unwrap();
+        assert_eq!(keep_marker(&target), None);
+
+        fs::write(
+            project.join("package.json"),
+            r#"{"cleanFiles": {"keep": true}}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            keep_marker(&project.join("node_modules")),
+            Some(KeepMarker::PackageJson(_))
+        ));
+
+        fs::write(project.join(KEEP_FILE), "").unwrap();
+        assert_eq!(
+            keep_marker(&target).unwrap().to_string(),
+            project.join(KEEP_FILE).display().to_string()
+        );
+    }
+}
@@ -0,0 +1,50 @@
+//! Scan development trees for regenerable build artifacts and remove them.
+//!
+//! `clean-files` is both a command-line tool and a library. The binary is a
+//! thin layer over the types exported here, so other tools can embed the same
+//! discovery and deletion logic:
+//!
+//! ```no_run
+//! use clean_files::{CleanTarget, Cleaner, Scanner};
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let results = Scanner::new(CleanTarget::All)
+//!     .with_max_depth(4)
+//!     .scan("/home/me/projects".as_ref())?;
+//!
+//! // Preview first; a dry run only adds up what would be freed.
+//! let stats = Cleaner::new(true, false).clean_without_confirmation(results)?;
+//! println!("{} bytes could be freed", stats.total_size);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Scanner`] finds candidate directories using the rules in [`detectors`]
+//! and sizes them, [`Cleaner`] re-verifies each candidate with the same
+//! detector and deletes it, and [`CleanStats`] summarises what happened.
+
+#![warn(missing_docs)]
+
+pub mod caches;
+pub mod cleaner;
+pub mod config;
+pub mod detectors;
+pub mod events;
+pub mod filter;
+pub mod git;
+pub mod goal;
+pub mod ignore;
+pub mod keep;
+pub mod pipeline;
+pub mod platform;
+pub mod report;
+pub mod scanner;
+pub mod sweep;
+pub mod trash;
+pub mod types;
+pub mod utils;
+
+pub use cleaner::Cleaner;
+pub use events::{CleanEvent, CleanObserver, SkipReason};
+pub use scanner::Scanner;
+pub use types::{CleanStats, CleanTarget, ScanResult};
@@ -1,23 +1,67 @@
-mod cleaner;
 mod cli;
-mod platform;
-mod scanner;
-mod types;
-mod utils;
+mod commands;
+mod output;
+mod terminal;
+mod tui;
 
 use anyhow::Result;
 use clap::Parser;
-use cleaner::Cleaner;
-use cli::Cli;
+use clean_files::config::Config;
+use clean_files::detectors::DetectorRegistry;
+use clean_files::filter::{Filter, Selection};
+use clean_files::goal::{FreeSpaceGoal, Strategy};
+use clean_files::pipeline::Pipeline;
+use clean_files::report::Report;
+use clean_files::utils::format_size;
+use clean_files::{CleanObserver, CleanStats, CleanTarget, Cleaner, ScanResult, Scanner};
+use cli::{CleanArgs, Cli, Command, OutputFormat, ScanArgs};
 use colored::*;
-use scanner::Scanner;
+use output::{JsonReporter, NdjsonReporter};
+use std::io::{self, IsTerminal};
+use std::path::Path;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use types::CleanTarget;
-use utils::format_size;
+use terminal::TerminalReporter;
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    let config = cli.config.as_deref();
+
+    match &cli.command {
+        // The flat flags are the `clean` command, as they always were
+        None => run_clean(&cli.clean, config, cli.format),
+        Some(Command::Clean(args)) => run_clean(args, config, cli.format),
+        Some(Command::Scan { scan, save_plan }) => {
+            commands::scan(scan, save_plan.as_deref(), config, cli.format)
+        }
+        Some(Command::Sweep(args)) => commands::sweep(args, config, cli.format),
+        Some(Command::Caches(args)) => commands::caches(args, cli.format),
+        Some(Command::Report { file }) => commands::report(file, cli.format),
+        Some(Command::Restore { path, list, yes }) => {
+            commands::restore(path, *list, *yes, cli.format)
+        }
+        Some(Command::Config { action }) => commands::config(action, config, cli.format),
+        Some(Command::Explain {
+            path,
+            root,
+            target,
+            max_depth,
+        }) => commands::explain(
+            path,
+            root.as_deref(),
+            (*target).into(),
+            *max_depth,
+            config,
+            cli.format,
+        ),
+        Some(Command::Targets { path }) => list_targets(config, path),
+    }
+}
+
+fn run_clean(args: &CleanArgs, config: Option<&Path>, format: OutputFormat) -> Result<()> {
+    // Machine-readable formats keep stdout for JSON only
+    let human = format == OutputFormat::Human;
+    let path = &args.scan.path;
 
     // Set up Ctrl+C handler for graceful shutdown
     let interrupted = Arc::new(AtomicBool::new(false));
@@ -34,70 +78,263 @@ fn main() -> Result<()> {
     })?;
 
     // Print banner
-    print_banner();
+    if human {
+        print_banner();
+    }
 
     // Validate path
-    if !cli.path.exists() {
+    validate_dir(path);
+
+    // The picker needs the terminal to itself
+    if args.interactive && (!human || !io::stdout().is_terminal()) {
         eprintln!(
-            "{} Path does not exist: {}",
-            "Error:".red().bold(),
-            cli.path.display()
+            "{} --interactive needs a terminal and the human output format",
+            "Error:".red().bold()
         );
         std::process::exit(1);
     }
 
-    if !cli.path.is_dir() {
+    // The pipeline has no point at which it could ask for confirmation
+    if args.pipeline && !args.yes && !args.dry_run {
         eprintln!(
-            "{} Path is not a directory: {}",
-            "Error:".red().bold(),
-            cli.path.display()
+            "{} --pipeline deletes without a confirmation prompt; pass --yes, or --dry-run to preview",
+            "Error:".red().bold()
         );
         std::process::exit(1);
     }
 
     // Convert target type
-    let target: CleanTarget = cli.target.into();
+    let target: CleanTarget = args.scan.target.into();
 
-    println!(
-        "Scanning directory: {}",
-        cli.path.display().to_string().cyan().bold()
-    );
-    println!("Target: {}", target.name().green());
-    if cli.dry_run {
-        println!(
-            "{}",
-            "Mode: DRY RUN (no files will be deleted)".yellow().bold()
+    if human {
+        match &args.plan {
+            Some(plan) => println!(
+                "Cleaning plan: {}",
+                plan.display().to_string().cyan().bold()
+            ),
+            None => println!(
+                "Scanning directory: {}",
+                path.display().to_string().cyan().bold()
+            ),
+        }
+        println!("Target: {}", target.name().green());
+        if args.dry_run {
+            println!(
+                "{}",
+                "Mode: DRY RUN (no files will be deleted)".yellow().bold()
+            );
+        }
+        if let Some(goal) = args.ensure_free {
+            println!(
+                "Goal: at least {} free ({} first)",
+                goal.to_string().green(),
+                Strategy::from(args.strategy)
+            );
+        }
+        println!();
+
+        // Scan for targets
+        if args.plan.is_none() {
+            println!("{}", "Scanning...".yellow());
+        }
+    }
+    let registry = load_registry(config, path)?;
+    let scanner = build_scanner(&args.scan, &registry);
+    let filter = build_filter(&args.scan);
+    let picker_registry = args.interactive.then(|| registry.clone());
+
+    // Clean the targets, reporting progress in the requested format
+    let json = Arc::new(JsonReporter::default());
+    let observer: Arc<dyn CleanObserver> = match format {
+        OutputFormat::Human => Arc::new(
+            TerminalReporter::new(args.scan.verbose, registry.clone())
+                .with_details(filter.is_active() || args.ensure_free.is_some()),
+        ),
+        OutputFormat::Json => json.clone(),
+        OutputFormat::Ndjson => Arc::new(NdjsonReporter),
+    };
+    let mut cleaner = Cleaner::new(args.dry_run, args.scan.verbose)
+        .with_interrupt_flag(interrupted)
+        .with_parallel(args.parallel)
+        .with_trash(args.trash)
+        .with_git_aware(args.git_aware)
+        .with_filter(filter.clone())
+        .with_registry(registry.clone())
+        .with_observer(observer);
+    if let Some(goal) = args.ensure_free {
+        cleaner = cleaner.with_space_goal(
+            FreeSpaceGoal::new(path.clone(), goal).with_strategy(args.strategy.into()),
         );
     }
-    println!();
 
-    // Scan for targets
-    println!("{}", "Scanning...".yellow());
-    let mut scanner = Scanner::new(target);
-    if let Some(depth) = cli.max_depth {
+    let stats = if args.pipeline {
+        Pipeline::new(&scanner, &cleaner).run(path)?
+    } else {
+        let results = match &args.plan {
+            // Plan files can be edited, so every entry must be found again
+            // by the detector it names before it is even considered
+            Some(plan) => {
+                let skip = |entry: &ScanResult, reason: &str| {
+                    eprintln!(
+                        "{} skipping plan entry {}: {}",
+                        "Warning:".yellow().bold(),
+                        entry.path.display(),
+                        reason
+                    );
+                };
+                let (accepted, refused) = Report::load(plan)?.checked_remaining(&registry);
+                for (entry, reason) in &refused {
+                    skip(entry, reason);
+                }
+
+                // Excludes and ignore files may have changed since the plan
+                // was written
+                let mut results = Vec::new();
+                for entry in accepted {
+                    if !target.should_clean(&entry.target_type) {
+                        continue;
+                    }
+                    match scanner.excluded_by(path, &entry.path) {
+                        Ok(None) => results.push(entry),
+                        Ok(Some(rule)) => skip(&entry, &format!("excluded by {}", rule)),
+                        Err(e) => skip(&entry, &format!("{:#}", e)),
+                    }
+                }
+                results
+            }
+            None => scanner.scan(path)?,
+        };
+
+        if let Some(registry) = &picker_registry {
+            // The picker is the confirmation; it only offers what the
+            // filter selects
+            let Selection { selected, excluded } = filter.apply(results);
+            match tui::pick(selected, registry)? {
+                Some(chosen) => cleaner.clean_selection(Selection {
+                    selected: chosen,
+                    excluded,
+                })?,
+                None => {
+                    println!("{}", "Cleanup cancelled.".yellow());
+                    return Ok(());
+                }
+            }
+        } else if args.yes && !args.dry_run {
+            // Override confirmation if --yes flag is set
+            if human {
+                println!("{}", "Skipping confirmation (--yes flag set)".yellow());
+            }
+            cleaner.clean_without_confirmation(results)?
+        } else {
+            cleaner.clean(results)?
+        }
+    };
+
+    // Print final statistics
+    match format {
+        OutputFormat::Human => print_stats(&stats, args.dry_run),
+        OutputFormat::Json => {
+            let mut report = json.report();
+            report.stats = stats;
+            output::print_report(&report)?;
+        }
+        OutputFormat::Ndjson => {}
+    }
+
+    Ok(())
+}
+
+/// Scanner configured from the shared scan options
+fn build_scanner(args: &ScanArgs, registry: &DetectorRegistry) -> Scanner {
+    let mut scanner = Scanner::new(args.target.into())
+        .with_registry(registry.clone())
+        .with_threads(args.threads);
+    if let Some(depth) = args.max_depth {
         scanner = scanner.with_max_depth(depth);
     }
-    if cli.verbose {
+    if args.verbose {
         scanner = scanner.with_verbose(true);
     }
+    scanner
+        .with_excludes(args.exclude.clone())
+        .with_source_activity(args.include_sources)
+        .with_outside_root(args.outside_root)
+}
 
-    let results = scanner.scan(&cli.path)?;
+/// Selection rules from the shared scan options
+fn build_filter(args: &ScanArgs) -> Filter {
+    let mut filter = Filter::new();
+    if let Some(age) = args.older_than {
+        filter = filter.with_older_than(age);
+    }
+    if let Some(bytes) = args.min_size {
+        filter = filter.with_min_size(bytes);
+    }
+    if let Some(bytes) = args.max_size {
+        filter = filter.with_max_size(bytes);
+    }
+    if let Some(files) = args.min_files {
+        filter = filter.with_min_files(files);
+    }
+    if let Some(count) = args.top {
+        filter = filter.with_top(count, args.rank_by.into());
+    }
+    filter
+}
 
-    // Clean the targets
-    let cleaner = Cleaner::new(cli.dry_run, cli.verbose)
-        .with_interrupt_flag(interrupted)
-        .with_parallel(cli.parallel);
+/// Exit with an error unless `path` is an existing directory
+fn validate_dir(path: &Path) {
+    if !path.exists() {
+        eprintln!(
+            "{} Path does not exist: {}",
+            "Error:".red().bold(),
+            path.display()
+        );
+        std::process::exit(1);
+    }
 
-    // Override confirmation if --yes flag is set
-    let stats = if cli.yes && !cli.dry_run {
-        println!("{}", "Skipping confirmation (--yes flag set)".yellow());
-        cleaner.clean_without_confirmation(results)?
-    } else {
-        cleaner.clean(results)?
-    };
+    if !path.is_dir() {
+        eprintln!(
+            "{} Path is not a directory: {}",
+            "Error:".red().bold(),
+            path.display()
+        );
+        std::process::exit(1);
+    }
+}
 
-    // Print final statistics
-    print_stats(&stats, cli.dry_run);
+/// Built-in detectors plus the custom targets from configuration files
+fn load_registry(config: Option<&Path>, root: &Path) -> Result<DetectorRegistry> {
+    let mut registry = DetectorRegistry::builtin();
+    for config in Config::discover(config, root)? {
+        config.apply(&mut registry)?;
+    }
+    Ok(registry)
+}
+
+/// Print every active detector and where it was defined
+fn list_targets(config: Option<&Path>, root: &Path) -> Result<()> {
+    validate_dir(root);
+    let registry = load_registry(config, root)?;
+
+    println!("{}", "Active target detectors:".cyan().bold());
+    println!();
+    println!(
+        "  {:<16} {:<24} {:<9} {}",
+        "ID".bold(),
+        "NAME".bold(),
+        "CATEGORY".bold(),
+        "DEFINED IN".bold()
+    );
+    for (detector, source) in registry.sources() {
+        println!(
+            "  {:<16} {:<24} {:<9} {}",
+            detector.id().green(),
+            detector.display_name(),
+            detector.category().key(),
+            source.to_string().dimmed()
+        );
+    }
 
     Ok(())
 }
@@ -112,7 +349,7 @@ fn print_banner() {
     println!("{}", banner.cyan());
 }
 
-fn print_stats(stats: &types::CleanStats, dry_run: bool) {
+fn print_stats(stats: &CleanStats, dry_run: bool) {
     println!("\n{}", "=".repeat(60).cyan());
     if dry_run {
         println!(
@@ -128,7 +365,16 @@ fn print_stats(stats: &types::CleanStats, dry_run: bool) {
     println!();
 
     if stats.total_dirs == 0 {
-        println!("{}", "No directories were found to clean.".yellow());
+        if stats.excluded_dirs > 0 {
+            println!(
+                "{} ({} directories, {}, were left out by filters or keep markers)",
+                "No directories were selected for cleaning.".yellow(),
+                stats.excluded_dirs,
+                format_size(stats.excluded_size)
+            );
+        } else {
+            println!("{}", "No directories were found to clean.".yellow());
+        }
         return;
     }
 
@@ -137,16 +383,30 @@ fn print_stats(stats: &types::CleanStats, dry_run: bool) {
         "  • Total directories cleaned: {}",
         stats.total_dirs.to_string().green().bold()
     );
-    println!(
-        "  • Total space freed: {}",
-        format_size(stats.total_size).cyan().bold()
-    );
+    if stats.trashed_dirs > 0 {
+        println!(
+            "  • Total size moved to trash: {}",
+            format_size(stats.total_size).cyan().bold()
+        );
+    } else {
+        println!(
+            "  • Total space freed: {}",
+            format_size(stats.total_size).cyan().bold()
+        );
+    }
     println!(
         "  • Total files removed: {}",
         stats.total_files.to_string().yellow().bold()
     );
+    if stats.excluded_dirs > 0 {
+        println!(
+            "  • Left out by filters or keep markers: {} ({})",
+            stats.excluded_dirs.to_string().yellow().bold(),
+            format_size(stats.excluded_size).cyan()
+        );
+    }
 
-    if !dry_run && (stats.failed_dirs > 0 || stats.skipped_dirs > 0) {
+    if stats.failed_dirs > 0 || stats.skipped_dirs > 0 {
         println!();
         println!("⚠️  Errors & Warnings:");
         if stats.failed_dirs > 0 {
@@ -157,12 +417,19 @@ fn print_stats(stats: &types::CleanStats, dry_run: bool) {
         }
         if stats.skipped_dirs > 0 {
             println!(
-                "  • Skipped (interrupted): {}",
+                "  • Skipped: {} (use --verbose to see why)",
                 stats.skipped_dirs.to_string().yellow().bold()
             );
         }
     }
 
+    if stats.trashed_dirs > 0 {
+        println!(
+            "  • Space is freed when the trash is emptied; undo with {}",
+            "clean-files restore".bold()
+        );
+    }
+
     println!();
 
     println!("🗂️  Breakdown by type:");
@@ -172,6 +439,12 @@ fn print_stats(stats: &types::CleanStats, dry_run: bool) {
             stats.node_modules.to_string().green()
         );
     }
+    if stats.framework_caches > 0 {
+        println!(
+            "  • JS frameworks (.next, .turbo, ...): {}",
+            stats.framework_caches.to_string().green()
+        );
+    }
     if stats.rust_targets > 0 {
         println!(
             "  • Rust (target): {}",
@@ -184,12 +457,36 @@ fn print_stats(stats: &types::CleanStats, dry_run: bool) {
             stats.python_caches.to_string().green()
         );
     }
+    if stats.python_venvs > 0 {
+        println!(
+            "  • Python (virtualenvs): {}",
+            stats.python_venvs.to_string().green()
+        );
+    }
     if stats.java_targets > 0 {
         println!(
-            "  • Java (target/build): {}",
+            "  • JVM (target, build, .gradle, ...): {}",
             stats.java_targets.to_string().green()
         );
     }
+    if stats.dotnet_targets > 0 {
+        println!(
+            "  • .NET (bin/obj): {}",
+            stats.dotnet_targets.to_string().green()
+        );
+    }
+    if stats.native_builds > 0 {
+        println!(
+            "  • C/C++ (CMake, Meson, Autotools, Bazel): {}",
+            stats.native_builds.to_string().green()
+        );
+    }
+    if stats.custom_targets > 0 {
+        println!(
+            "  • Custom detectors: {}",
+            stats.custom_targets.to_string().green()
+        );
+    }
 
     println!();
     println!("{}", "=".repeat(60).cyan());
@@ -0,0 +1,53 @@
+use crate::terminal::ask_to_proceed;
+use clean_files::report::{event_line, Report, ReportCollector};
+use clean_files::{CleanEvent, CleanObserver, ScanResult};
+use std::io::{self, Write};
+
+/// Collects a [`Report`] for `--format json`; stdout is reserved for the
+/// document, so the confirmation prompt goes to stderr
+#[derive(Default)]
+pub struct JsonReporter {
+    collector: ReportCollector,
+}
+
+impl JsonReporter {
+    pub fn report(&self) -> Report {
+        self.collector.report()
+    }
+}
+
+impl CleanObserver for JsonReporter {
+    fn on_event(&self, event: &CleanEvent) {
+        self.collector.on_event(event);
+    }
+
+    fn confirm(&self, _targets: &[ScanResult]) -> bool {
+        ask_to_proceed(&mut io::stderr())
+    }
+}
+
+/// Prints one JSON object per event for `--format ndjson`
+pub struct NdjsonReporter;
+
+impl CleanObserver for NdjsonReporter {
+    fn on_event(&self, event: &CleanEvent) {
+        // Events cannot fail to serialize; a closed stdout is not our problem
+        if let Ok(line) = event_line(event) {
+            let mut stdout = io::stdout().lock();
+            let _ = writeln!(stdout, "{}", line);
+            let _ = stdout.flush();
+        }
+    }
+
+    fn confirm(&self, _targets: &[ScanResult]) -> bool {
+        ask_to_proceed(&mut io::stderr())
+    }
+}
+
+/// Print a finished report as pretty JSON
+pub fn print_report(report: &Report) -> anyhow::Result<()> {
+    let mut stdout = io::stdout().lock();
+    serde_json::to_writer_pretty(&mut stdout, report)?;
+    writeln!(stdout)?;
+    Ok(())
+}
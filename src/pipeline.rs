@@ -0,0 +1,234 @@
+//! Streaming scan → size → delete pipeline.
+//!
+//! [`Scanner::scan`] walks the whole tree, then sizes every target, and only
+//! then hands the list to the [`Cleaner`]. On large trees that means a long
+//! wait before anything happens and a result list that grows with the number
+//! of projects. A [`Pipeline`] instead connects the three stages with bounded
+//! channels:
+//!
+//! ```text
+//! discovery ──found──▶ sizing workers ──sized──▶ cleaning workers
+//! ```
+//!
+//! Targets are cleaned while the walk is still running, a full channel makes
+//! the upstream stage wait (backpressure), and memory use is bounded by the
+//! channel capacity instead of the size of the tree. Nothing is confirmed:
+//! run the cleaner in dry-run mode to preview.
+
+use crate::events::CleanEvent;
+use crate::types::CleanStats;
+use crate::{Cleaner, Scanner};
+use anyhow::{bail, Result};
+use crossbeam_channel::bounded;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// Runs discovery, sizing and cleaning concurrently
+pub struct Pipeline<'a> {
+    scanner: &'a Scanner,
+    cleaner: &'a Cleaner,
+    sizing_workers: usize,
+    cleaning_workers: usize,
+    capacity: usize,
+}
+
+impl<'a> Pipeline<'a> {
+    /// Connect a scanner and a cleaner
+    pub fn new(scanner: &'a Scanner, cleaner: &'a Cleaner) -> Self {
+        let threads = thread::available_parallelism().map_or(4, |n| n.get());
+        Self {
+            scanner,
+            cleaner,
+            sizing_workers: threads,
+            cleaning_workers: threads,
+            capacity: 64,
+        }
+    }
+
+    /// Number of threads sizing targets and deleting them (at least one each)
+    pub fn with_workers(mut self, sizing: usize, cleaning: usize) -> Self {
+        self.sizing_workers = sizing.max(1);
+        self.cleaning_workers = cleaning.max(1);
+        self
+    }
+
+    /// Number of targets each channel buffers before the producer waits
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    /// Scan `root` and clean targets as they are found
+    ///
+    /// Fails if the cleaner ranks targets by a filter or a free-space goal,
+    /// since that needs the complete scan.
+    pub fn run(&self, root: &Path) -> Result<CleanStats> {
+        if self.cleaner.needs_complete_scan() {
+            bail!("Ranking targets needs the complete scan and cannot be streamed");
+        }
+
+        let (found_tx, found_rx) = bounded(self.capacity);
+        let (sized_tx, sized_rx) = bounded(self.capacity);
+        let discovered = AtomicUsize::new(0);
+        let processed = AtomicUsize::new(0);
+        let totals = Mutex::new(CleanStats::default());
+
+        self.cleaner.emit(CleanEvent::Streaming {
+            dry_run: self.cleaner.is_dry_run(),
+        });
+
+        let walk = thread::scope(|s| {
+            let discovered = &discovered;
+            let discovery = s.spawn(move || {
+                // A failed send means every downstream worker has stopped
+                self.scanner.discover(root, |result| {
+                    discovered.fetch_add(1, Ordering::SeqCst);
+                    found_tx.send(result).is_ok()
+                })
+            });
+
+            for _ in 0..self.sizing_workers {
+                let found_rx = found_rx.clone();
+                let sized_tx = sized_tx.clone();
+                s.spawn(move || {
+                    for mut result in found_rx {
+                        self.scanner.measure(&mut result);
+                        if sized_tx.send(result).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+
+            for _ in 0..self.cleaning_workers {
+                let sized_rx = sized_rx.clone();
+                let processed = &processed;
+                let totals = &totals;
+                s.spawn(move || {
+                    for result in sized_rx {
+                        if self.cleaner.is_interrupted() {
+                            break;
+                        }
+                        self.cleaner.emit(CleanEvent::Found {
+                            target: result.clone(),
+                        });
+                        let stats = self.cleaner.clean_one(&result);
+                        totals.lock().unwrap().merge(&stats);
+                        processed.fetch_add(1, Ordering::SeqCst);
+                    }
+                });
+            }
+
+            // Only the workers hold channel ends now, so each stage shuts
+            // down as soon as the one before it is done
+            drop(found_rx);
+            drop(sized_tx);
+            drop(sized_rx);
+
+            discovery.join().expect("discovery thread panicked")
+        });
+
+        let mut stats = totals.into_inner().unwrap();
+
+        // Targets that were found but never reached a cleaning worker
+        let remaining = discovered.load(Ordering::SeqCst) - processed.load(Ordering::SeqCst);
+        if remaining > 0 {
+            for _ in 0..remaining {
+                stats.add_skipped();
+            }
+            self.cleaner.emit(CleanEvent::Interrupted { remaining });
+        }
+
+        self.cleaner.emit(CleanEvent::Finished {
+            stats: stats.clone(),
+        });
+
+        walk.map(|_| stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::{Filter, Ranking};
+    use crate::types::CleanTarget;
+    use std::fs;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+    use tempfile::TempDir;
+
+    fn create_projects(root: &Path, count: usize) {
+        for i in 0..count {
+            let project = root.join(format!("project{}", i));
+            fs::create_dir(&project).unwrap();
+            fs::write(project.join("package.json"), "{}").unwrap();
+            let node_modules = project.join("node_modules");
+            fs::create_dir(&node_modules).unwrap();
+            fs::write(node_modules.join("index.js"), "12345").unwrap();
+        }
+    }
+
+    #[test]
+    fn test_pipeline_cleans_everything() {
+        let temp_dir = TempDir::new().unwrap();
+        create_projects(temp_dir.path(), 20);
+
+        let scanner = Scanner::new(CleanTarget::All);
+        let cleaner = Cleaner::new(false, false);
+        let stats = Pipeline::new(&scanner, &cleaner)
+            .with_workers(2, 3)
+            .with_capacity(1)
+            .run(temp_dir.path())
+            .unwrap();
+
+        assert_eq!(stats.total_dirs, 20);
+        assert_eq!(stats.node_modules, 20);
+        assert_eq!(stats.total_size, 100);
+        assert_eq!(stats.skipped_dirs, 0);
+        for i in 0..20 {
+            let project = temp_dir.path().join(format!("project{}", i));
+            assert!(!project.join("node_modules").exists());
+            assert!(project.join("package.json").exists());
+        }
+    }
+
+    #[test]
+    fn test_pipeline_dry_run_and_interrupt() {
+        let temp_dir = TempDir::new().unwrap();
+        create_projects(temp_dir.path(), 5);
+
+        let scanner = Scanner::new(CleanTarget::All);
+        let stats = Pipeline::new(&scanner, &Cleaner::new(true, false))
+            .run(temp_dir.path())
+            .unwrap();
+        assert_eq!(stats.total_dirs, 5);
+        assert_eq!(stats.total_files, 5);
+        assert!(temp_dir.path().join("project0/node_modules").exists());
+
+        // Interrupted before it starts: everything found is skipped
+        let cleaner =
+            Cleaner::new(false, false).with_interrupt_flag(Arc::new(AtomicBool::new(true)));
+        let stats = Pipeline::new(&scanner, &cleaner)
+            .run(temp_dir.path())
+            .unwrap();
+        assert_eq!(stats.total_dirs, 0);
+        assert!(stats.skipped_dirs >= 1);
+        assert!(temp_dir.path().join("project0/node_modules").exists());
+
+        // Per-target rules stream fine; ranking needs the complete scan
+        let cleaner = Cleaner::new(true, false).with_filter(Filter::new().with_min_size(6));
+        let stats = Pipeline::new(&scanner, &cleaner)
+            .run(temp_dir.path())
+            .unwrap();
+        assert_eq!(stats.total_dirs, 0);
+        assert_eq!(stats.excluded_dirs, 5);
+
+        let cleaner =
+            Cleaner::new(true, false).with_filter(Filter::new().with_top(1, Ranking::Size));
+        assert!(Pipeline::new(&scanner, &cleaner)
+            .run(temp_dir.path())
+            .is_err());
+    }
+}
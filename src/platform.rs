@@ -1,27 +1,63 @@
+//! Filesystem operations with platform-specific handling.
+
 use anyhow::{Context, Result};
 use std::fs;
 use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Size, file count and newest modification time of a directory tree
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DirUsage {
+    /// Total size of all regular files in bytes
+    pub size: u64,
+    /// Number of regular files
+    pub files: usize,
+    /// Newest mtime of any file or directory in the tree, itself included
+    pub last_modified: Option<SystemTime>,
+}
+
+impl DirUsage {
+    fn touch(&mut self, modified: Option<SystemTime>) {
+        self.last_modified = self.last_modified.max(modified);
+    }
+
+    fn add(&mut self, other: DirUsage) {
+        self.size += other.size;
+        self.files += other.files;
+        self.touch(other.last_modified);
+    }
+}
 
 /// Calculate the size of a directory recursively
 pub fn calculate_dir_size(path: &Path) -> Result<(u64, usize)> {
-    let mut total_size = 0u64;
-    let mut file_count = 0usize;
+    let usage = measure_dir(path)?;
+    Ok((usage.size, usage.files))
+}
+
+/// Walk a directory once, collecting its size, file count and newest mtime
+pub fn measure_dir(path: &Path) -> Result<DirUsage> {
+    let mut usage = DirUsage::default();
 
     if !path.exists() {
-        return Ok((0, 0));
+        return Ok(usage);
     }
 
     // Handle symlinks - don't follow them to avoid loops
     if path.is_symlink() {
-        return Ok((0, 0));
+        return Ok(usage);
     }
 
     if path.is_file() {
         let metadata = fs::metadata(path).context("Failed to read file metadata")?;
-        return Ok((metadata.len(), 1));
+        usage.size = metadata.len();
+        usage.files = 1;
+        usage.touch(metadata.modified().ok());
+        return Ok(usage);
     }
 
     if path.is_dir() {
+        usage.touch(fs::metadata(path).and_then(|m| m.modified()).ok());
         let entries = fs::read_dir(path).context("Failed to read directory")?;
 
         for entry in entries {
@@ -35,18 +71,132 @@ pub fn calculate_dir_size(path: &Path) -> Result<(u64, usize)> {
 
             if path.is_file() {
                 if let Ok(metadata) = fs::metadata(&path) {
-                    total_size += metadata.len();
-                    file_count += 1;
+                    usage.size += metadata.len();
+                    usage.files += 1;
+                    usage.touch(metadata.modified().ok());
                 }
             } else if path.is_dir() {
-                let (size, count) = calculate_dir_size(&path)?;
-                total_size += size;
-                file_count += count;
+                usage.add(measure_dir(&path)?);
             }
         }
     }
 
-    Ok((total_size, file_count))
+    Ok(usage)
+}
+
+/// Format a timestamp in local time as `YYYY-MM-DDThh:mm:ss`
+pub fn local_datetime(time: SystemTime) -> String {
+    let secs = match time.duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs() as i64),
+    };
+    let (year, month, day, hour, minute, second) = local_parts(secs);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        year, month, day, hour, minute, second
+    )
+}
+
+#[cfg(unix)]
+fn local_parts(secs: i64) -> (i64, u32, u32, u32, u32, u32) {
+    let time = secs as libc::time_t;
+    // SAFETY: localtime_r only writes to the tm we pass in
+    let tm = unsafe {
+        let mut tm: libc::tm = std::mem::zeroed();
+        libc::localtime_r(&time, &mut tm);
+        tm
+    };
+    (
+        tm.tm_year as i64 + 1900,
+        tm.tm_mon as u32 + 1,
+        tm.tm_mday as u32,
+        tm.tm_hour as u32,
+        tm.tm_min as u32,
+        tm.tm_sec as u32,
+    )
+}
+
+/// Without libc, fall back to UTC
+#[cfg(not(unix))]
+fn local_parts(secs: i64) -> (i64, u32, u32, u32, u32, u32) {
+    let days = secs.div_euclid(86_400);
+    let rem = secs.rem_euclid(86_400) as u32;
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    (year, month, day, rem / 3600, rem / 60 % 60, rem % 60)
+}
+
+/// Free and total space of a filesystem
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DiskSpace {
+    /// Bytes available to unprivileged users
+    pub available: u64,
+    /// Size of the filesystem in bytes
+    pub total: u64,
+}
+
+impl DiskSpace {
+    /// Space figures, e.g. for tests or estimates
+    pub fn new(available: u64, total: u64) -> Self {
+        Self { available, total }
+    }
+}
+
+/// Read the free space of the filesystem holding `path` with statvfs
+#[cfg(unix)]
+pub fn disk_space(path: &Path) -> Result<DiskSpace> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .with_context(|| format!("Invalid path: {}", path.display()))?;
+    // SAFETY: statvfs only writes to the struct we pass in
+    let stat = unsafe {
+        let mut stat: libc::statvfs = std::mem::zeroed();
+        if libc::statvfs(c_path.as_ptr(), &mut stat) != 0 {
+            return Err(std::io::Error::last_os_error())
+                .with_context(|| format!("Cannot read free space of {}", path.display()));
+        }
+        stat
+    };
+    let block = stat.f_frsize as u64;
+    Ok(DiskSpace {
+        available: stat.f_bavail as u64 * block,
+        total: stat.f_blocks as u64 * block,
+    })
+}
+
+/// Read the free space of the filesystem holding `path`
+#[cfg(not(unix))]
+pub fn disk_space(_path: &Path) -> Result<DiskSpace> {
+    anyhow::bail!("Reading free space is only supported on Unix-like systems")
+}
+
+/// Identifier of the filesystem holding `path`
+#[cfg(unix)]
+pub fn device_id(path: &Path) -> Result<u64> {
+    use std::os::unix::fs::MetadataExt;
+
+    Ok(fs::metadata(path)
+        .with_context(|| format!("Cannot stat {}", path.display()))?
+        .dev())
+}
+
+/// Identifier of the filesystem holding `path`
+#[cfg(not(unix))]
+pub fn device_id(_path: &Path) -> Result<u64> {
+    anyhow::bail!("Filesystem identity is only available on Unix-like systems")
 }
 
 /// Remove a directory recursively with platform-specific handling
@@ -70,6 +220,87 @@ pub fn remove_dir_all(path: &Path) -> Result<()> {
         .with_context(|| format!("Failed to remove directory: {}", path.display()))
 }
 
+/// A running process started from inside `dir`, if there is one
+///
+/// Looks through `/proc` at each process's executable, the path it was
+/// started as (`argv[0]`) and, where readable, its `VIRTUAL_ENV`. The last
+/// two catch virtual environments whose `python` is a symlink to the base
+/// interpreter. Processes of other users whose details are unreadable are
+/// passed over.
+#[cfg(target_os = "linux")]
+pub fn process_running_from(dir: &Path) -> Option<u32> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let dir = dir.canonicalize().ok()?;
+    let inside = |path: &Path| path.canonicalize().is_ok_and(|p| p.starts_with(&dir));
+    let own_pid = std::process::id();
+    for entry in fs::read_dir("/proc").ok()?.flatten() {
+        let Some(pid) = entry
+            .file_name()
+            .to_str()
+            .and_then(|n| n.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        if pid == own_pid {
+            continue;
+        }
+        let proc_dir = entry.path();
+
+        if fs::read_link(proc_dir.join("exe")).is_ok_and(|exe| inside(&exe)) {
+            return Some(pid);
+        }
+        if let Ok(cmdline) = fs::read(proc_dir.join("cmdline")) {
+            let argv0 = cmdline.split(|&b| b == 0).next().unwrap_or_default();
+            let argv0 = Path::new(std::ffi::OsStr::from_bytes(argv0));
+            let started_as = match fs::read_link(proc_dir.join("cwd")) {
+                Ok(cwd) if argv0.is_relative() && argv0.components().count() > 1 => cwd.join(argv0),
+                _ => argv0.to_path_buf(),
+            };
+            // The file itself is usually a symlink out of the environment
+            if started_as.is_absolute() && started_as.parent().is_some_and(inside) {
+                return Some(pid);
+            }
+        }
+        if let Ok(environ) = fs::read(proc_dir.join("environ")) {
+            let active = environ
+                .split(|&b| b == 0)
+                .find_map(|var| var.strip_prefix(b"VIRTUAL_ENV="));
+            if active.is_some_and(|venv| inside(Path::new(std::ffi::OsStr::from_bytes(venv)))) {
+                return Some(pid);
+            }
+        }
+    }
+    None
+}
+
+/// A running process started from inside `dir`; only known on Linux
+#[cfg(not(target_os = "linux"))]
+pub fn process_running_from(_dir: &Path) -> Option<u32> {
+    None
+}
+
+/// Whether a process with this id is running
+#[cfg(unix)]
+pub fn process_exists(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    if pid <= 0 {
+        return false;
+    }
+    // SAFETY: signal 0 only checks that the process exists
+    let sent = unsafe { libc::kill(pid, 0) } == 0;
+    sent || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// Whether a process with this id is running; assumed so where it cannot be
+/// checked
+#[cfg(not(unix))]
+pub fn process_exists(_pid: u32) -> bool {
+    true
+}
+
 /// Check if we have permission to delete a directory
 pub fn can_delete(path: &Path) -> bool {
     if !path.exists() {
@@ -128,6 +359,54 @@ mod tests {
         assert_eq!(count, 2);
     }
 
+    #[test]
+    fn test_measure_dir_newest_mtime() {
+        let temp_dir = TempDir::new().unwrap();
+        let old = SystemTime::now() - std::time::Duration::from_secs(86_400 * 90);
+        let nested = temp_dir.path().join("nested");
+        fs::create_dir(&nested).unwrap();
+        fs::write(nested.join("a.o"), "1234").unwrap();
+        for path in [&nested.join("a.o"), &nested, &temp_dir.path().to_path_buf()] {
+            fs::File::options()
+                .read(true)
+                .open(path)
+                .unwrap()
+                .set_modified(old)
+                .unwrap();
+        }
+
+        let usage = measure_dir(temp_dir.path()).unwrap();
+        assert_eq!((usage.size, usage.files), (4, 1));
+        assert_eq!(usage.last_modified, Some(old));
+
+        // A fresh file anywhere in the tree makes the whole tree recent
+        fs::write(nested.join("b.o"), "").unwrap();
+        let usage = measure_dir(temp_dir.path()).unwrap();
+        assert!(usage.last_modified.unwrap() > old);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_disk_space() {
+        let temp_dir = TempDir::new().unwrap();
+        let space = disk_space(temp_dir.path()).unwrap();
+        assert!(space.total > 0);
+        assert!(space.available <= space.total);
+        assert_eq!(
+            device_id(temp_dir.path()).unwrap(),
+            device_id(&temp_dir.path().join(".")).unwrap()
+        );
+        assert!(disk_space(&temp_dir.path().join("missing")).is_err());
+    }
+
+    #[test]
+    fn test_local_datetime() {
+        let formatted = local_datetime(SystemTime::now());
+        assert_eq!(formatted.len(), 19);
+        assert_eq!(&formatted[10..11], "T");
+        assert!(formatted.starts_with("20"));
+    }
+
     #[test]
     fn test_remove_dir_all() {
         let temp_dir = TempDir::new().unwrap();
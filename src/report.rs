@@ -0,0 +1,560 @@
+//! Machine-readable reports.
+//!
+//! A [`Report`] is the versioned document printed by `--format json`: every
+//! target with what happened to it, plus the final [`CleanStats`]. It is
+//! built from the [`CleanEvent`] stream by a [`ReportCollector`], and
+//! [`event_line`] renders single events for `--format ndjson`.
+//!
+//! Paths are written as JSON strings when they are valid UTF-8. Anything
+//! else is written losslessly as `{"unix": [bytes]}` or
+//! `{"windows": [UTF-16 units]}`, see [`os_path`].
+
+use crate::detectors::DetectorRegistry;
+use crate::events::{CleanEvent, CleanObserver};
+use crate::filter::Selection;
+use crate::goal::GoalOutcome;
+use crate::types::{CleanStats, ScanResult};
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Version of the JSON and NDJSON formats; bumped on incompatible changes
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Everything a run found and did
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Report {
+    /// [`FORMAT_VERSION`] of the writer
+    pub version: u32,
+    /// Whether this was a dry run
+    pub dry_run: bool,
+    /// The confirmation was declined and nothing was touched
+    pub cancelled: bool,
+    /// The run was interrupted before every target was processed
+    pub interrupted: bool,
+    /// Targets in the order they were planned or found
+    pub targets: Vec<TargetReport>,
+    /// Final statistics
+    pub stats: CleanStats,
+    /// How the free-space goal turned out, if one was set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub space_goal: Option<GoalOutcome>,
+}
+
+impl Default for Report {
+    fn default() -> Self {
+        Self {
+            version: FORMAT_VERSION,
+            dry_run: false,
+            cancelled: false,
+            interrupted: false,
+            targets: Vec::new(),
+            stats: CleanStats::default(),
+            space_goal: None,
+        }
+    }
+}
+
+impl Report {
+    /// A plan for `clean --plan`: every scanned target, not yet processed
+    pub fn from_scan(targets: &[ScanResult]) -> Self {
+        Self {
+            targets: targets
+                .iter()
+                .map(|target| TargetReport {
+                    target: target.clone(),
+                    status: TargetStatus::Pending,
+                    reason: None,
+                })
+                .collect(),
+            ..Self::default()
+        }
+    }
+
+    /// A plan from filtered scan results; excluded targets are kept for
+    /// reference but are not [`remaining`](Report::remaining)
+    pub fn from_selection(selection: &Selection) -> Self {
+        let mut report = Self::from_scan(&selection.selected);
+        report
+            .targets
+            .extend(selection.excluded.iter().map(|excluded| TargetReport {
+                target: excluded.target.clone(),
+                status: TargetStatus::Excluded,
+                reason: Some(excluded.reason.to_string()),
+            }));
+        report
+    }
+
+    /// Read a plan or report written by this or an older version
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read report: {}", path.display()))?;
+        let report: Report = serde_json::from_str(&text)
+            .with_context(|| format!("Invalid report: {}", path.display()))?;
+        if report.version > FORMAT_VERSION {
+            bail!(
+                "{} was written by a newer version (format {}, this build reads up to {})",
+                path.display(),
+                report.version,
+                FORMAT_VERSION
+            );
+        }
+        Ok(report)
+    }
+
+    /// Write the report as pretty-printed JSON
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json + "\n")
+            .with_context(|| format!("Failed to write report: {}", path.display()))
+    }
+
+    /// [`remaining`](Report::remaining) targets split into those that still
+    /// [re-identify](DetectorRegistry::reidentify) as recorded and those
+    /// refused, with the reason
+    pub fn checked_remaining(
+        &self,
+        registry: &DetectorRegistry,
+    ) -> (Vec<ScanResult>, Vec<(ScanResult, String)>) {
+        let mut refused = Vec::new();
+        let accepted = self
+            .remaining()
+            .into_iter()
+            .filter_map(|target| match registry.reidentify(&target) {
+                Ok(()) => Some(target),
+                Err(reason) => {
+                    refused.push((target, reason));
+                    None
+                }
+            })
+            .collect();
+        (accepted, refused)
+    }
+
+    /// Targets still to be deleted: pending ones and dry-run candidates
+    pub fn remaining(&self) -> Vec<ScanResult> {
+        self.targets
+            .iter()
+            .filter(|t| matches!(t.status, TargetStatus::Pending | TargetStatus::WouldDelete))
+            .map(|t| t.target.clone())
+            .collect()
+    }
+}
+
+/// One target and its outcome
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct TargetReport {
+    /// The scanned target
+    #[serde(flatten)]
+    pub target: ScanResult,
+    /// What happened to it
+    pub status: TargetStatus,
+    /// Why it was skipped or why deleting it failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Outcome of a single target
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum TargetStatus {
+    /// Not processed (cancelled, interrupted or scan only)
+    Pending,
+    /// Removed
+    Deleted,
+    /// Moved to the trash
+    Trashed,
+    /// Would have been removed in a dry run
+    WouldDelete,
+    /// Left in place; see the reason
+    Skipped,
+    /// Deleting it failed; see the reason
+    Failed,
+    /// Left out by the filter; see the reason
+    Excluded,
+}
+
+impl TargetStatus {
+    /// Identifier used in JSON output
+    pub fn key(&self) -> &'static str {
+        match self {
+            TargetStatus::Pending => "pending",
+            TargetStatus::Deleted => "deleted",
+            TargetStatus::Trashed => "trashed",
+            TargetStatus::WouldDelete => "would-delete",
+            TargetStatus::Skipped => "skipped",
+            TargetStatus::Failed => "failed",
+            TargetStatus::Excluded => "excluded",
+        }
+    }
+}
+
+/// Observer that turns the event stream into a [`Report`]
+#[derive(Debug, Default)]
+pub struct ReportCollector {
+    state: Mutex<Collected>,
+}
+
+#[derive(Debug, Default)]
+struct Collected {
+    report: Report,
+    index: HashMap<PathBuf, usize>,
+}
+
+impl Collected {
+    fn add(&mut self, target: &ScanResult) {
+        self.index
+            .insert(target.path.clone(), self.report.targets.len());
+        self.report.targets.push(TargetReport {
+            target: target.clone(),
+            status: TargetStatus::Pending,
+            reason: None,
+        });
+    }
+
+    fn set(&mut self, target: &ScanResult, status: TargetStatus, reason: Option<String>) {
+        if !self.index.contains_key(&target.path) {
+            self.add(target);
+        }
+        let entry = &mut self.report.targets[self.index[&target.path]];
+        entry.status = status;
+        entry.reason = reason;
+    }
+}
+
+impl ReportCollector {
+    /// Create an empty collector
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The report so far
+    pub fn report(&self) -> Report {
+        self.state.lock().unwrap().report.clone()
+    }
+}
+
+impl CleanObserver for ReportCollector {
+    fn on_event(&self, event: &CleanEvent) {
+        let mut state = self.state.lock().unwrap();
+
+        match event {
+            // Excluded targets are announced before the plan, so keep them
+            CleanEvent::Planned { targets, dry_run } => {
+                state.report.dry_run = *dry_run;
+                for target in targets {
+                    state.add(target);
+                }
+            }
+            CleanEvent::Streaming { dry_run } => {
+                *state = Collected::default();
+                state.report.dry_run = *dry_run;
+            }
+            CleanEvent::Found { target } => state.add(target),
+            CleanEvent::Excluded { target, reason } => {
+                state.set(target, TargetStatus::Excluded, Some(reason.to_string()))
+            }
+            CleanEvent::Cancelled => state.report.cancelled = true,
+            CleanEvent::Skipped { target, reason } => {
+                state.set(target, TargetStatus::Skipped, Some(reason.to_string()))
+            }
+            CleanEvent::Deleted { target, .. } => state.set(target, TargetStatus::Deleted, None),
+            CleanEvent::Trashed { target, .. } => state.set(target, TargetStatus::Trashed, None),
+            CleanEvent::WouldDelete { target } => {
+                state.set(target, TargetStatus::WouldDelete, None)
+            }
+            CleanEvent::Failed { target, error } => {
+                state.set(target, TargetStatus::Failed, Some(error.clone()))
+            }
+            CleanEvent::Interrupted { .. } => state.report.interrupted = true,
+            CleanEvent::SpaceGoal { outcome } => state.report.space_goal = Some(*outcome),
+            CleanEvent::Finished { stats } => state.report.stats = stats.clone(),
+            _ => {}
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct EventLine<'a> {
+    version: u32,
+    #[serde(flatten)]
+    event: &'a CleanEvent,
+}
+
+/// Render one event as a single line of JSON (without the newline)
+///
+/// Every line carries the format `version` next to the `event` name.
+pub fn event_line(event: &CleanEvent) -> serde_json::Result<String> {
+    serde_json::to_string(&EventLine {
+        version: FORMAT_VERSION,
+        event,
+    })
+}
+
+/// Optional timestamps as whole seconds since the Unix epoch, for
+/// `#[serde(with = ...)]`
+pub mod unix_time {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    /// Serialize as an integer, or `null`
+    pub fn serialize<S: Serializer>(
+        time: &Option<SystemTime>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match time.and_then(|t| t.duration_since(UNIX_EPOCH).ok()) {
+            Some(since) => serializer.serialize_some(&since.as_secs()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    /// Deserialize a value written by [`serialize`]
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<SystemTime>, D::Error> {
+        Ok(Option::<u64>::deserialize(deserializer)?.map(|s| UNIX_EPOCH + Duration::from_secs(s)))
+    }
+}
+
+/// Lossless serde representation of paths, for `#[serde(with = ...)]`
+///
+/// UTF-8 paths become plain strings. Other Unix paths become
+/// `{"unix": [bytes]}` and other Windows paths `{"windows": [u16 units]}`,
+/// so no path is ever mangled by a lossy conversion.
+pub mod os_path {
+    use serde::de::Error as _;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::path::{Path, PathBuf};
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(untagged)]
+    enum Repr {
+        Utf8(String),
+        Unix { unix: Vec<u8> },
+        Windows { windows: Vec<u16> },
+    }
+
+    /// Serialize a path
+    pub fn serialize<S: Serializer>(path: &Path, serializer: S) -> Result<S::Ok, S::Error> {
+        let repr = match path.to_str() {
+            Some(s) => Repr::Utf8(s.to_string()),
+            None => encode(path),
+        };
+        repr.serialize(serializer)
+    }
+
+    /// Deserialize a path written by [`serialize`]
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<PathBuf, D::Error> {
+        decode(Repr::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+
+    /// The same representation for `Option<PathBuf>` fields
+    pub mod option {
+        use super::{decode, Repr};
+        use serde::de::Error as _;
+        use serde::{Deserialize, Deserializer, Serialize, Serializer};
+        use std::path::{Path, PathBuf};
+
+        struct Wrapped<'a>(&'a Path);
+
+        impl Serialize for Wrapped<'_> {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                super::serialize(self.0, serializer)
+            }
+        }
+
+        /// Serialize an optional path
+        pub fn serialize<S: Serializer>(
+            path: &Option<PathBuf>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            path.as_deref().map(Wrapped).serialize(serializer)
+        }
+
+        /// Deserialize an optional path written by [`serialize`]
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<PathBuf>, D::Error> {
+            Option::<Repr>::deserialize(deserializer)?
+                .map(decode)
+                .transpose()
+                .map_err(D::Error::custom)
+        }
+    }
+
+    fn decode(repr: Repr) -> Result<PathBuf, String> {
+        match repr {
+            Repr::Utf8(s) => Ok(PathBuf::from(s)),
+            Repr::Unix { unix } => decode_unix(unix),
+            Repr::Windows { windows } => decode_windows(windows),
+        }
+    }
+
+    #[cfg(unix)]
+    fn encode(path: &Path) -> Repr {
+        use std::os::unix::ffi::OsStrExt;
+        Repr::Unix {
+            unix: path.as_os_str().as_bytes().to_vec(),
+        }
+    }
+
+    #[cfg(windows)]
+    fn encode(path: &Path) -> Repr {
+        use std::os::windows::ffi::OsStrExt;
+        Repr::Windows {
+            windows: path.as_os_str().encode_wide().collect(),
+        }
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    fn encode(path: &Path) -> Repr {
+        Repr::Utf8(path.to_string_lossy().into_owned())
+    }
+
+    #[cfg(unix)]
+    fn decode_unix(bytes: Vec<u8>) -> Result<PathBuf, String> {
+        use std::os::unix::ffi::OsStringExt;
+        Ok(std::ffi::OsString::from_vec(bytes).into())
+    }
+
+    #[cfg(not(unix))]
+    fn decode_unix(bytes: Vec<u8>) -> Result<PathBuf, String> {
+        String::from_utf8(bytes)
+            .map(PathBuf::from)
+            .map_err(|_| "non-UTF-8 Unix path cannot be represented here".to_string())
+    }
+
+    #[cfg(windows)]
+    fn decode_windows(units: Vec<u16>) -> Result<PathBuf, String> {
+        use std::os::windows::ffi::OsStringExt;
+        Ok(std::ffi::OsString::from_wide(&units).into())
+    }
+
+    #[cfg(not(windows))]
+    fn decode_windows(units: Vec<u16>) -> Result<PathBuf, String> {
+        String::from_utf16(&units)
+            .map(PathBuf::from)
+            .map_err(|_| "unpaired UTF-16 Windows path cannot be represented here".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::SkipReason;
+    use crate::filter::Exclusion;
+    use crate::types::CleanTarget;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn test_report_from_events() {
+        let a = ScanResult::new(PathBuf::from("/p/a/node_modules"), CleanTarget::NodeModules)
+            .with_detector("node-modules")
+            .with_size(10, 2);
+        let b = ScanResult::new(PathBuf::from("/p/b/target"), CleanTarget::RustTarget);
+        let active = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let c = ScanResult::new(PathBuf::from("/p/c/target"), CleanTarget::RustTarget)
+            .with_last_activity(active);
+
+        let collector = ReportCollector::new();
+        collector.on_event(&CleanEvent::Excluded {
+            target: c,
+            reason: Exclusion::RecentlyActive {
+                last_activity: Some(active),
+                min_age: Duration::from_secs(86_400),
+            },
+        });
+        collector.on_event(&CleanEvent::Planned {
+            targets: vec![a.clone(), b.clone()],
+            dry_run: false,
+        });
+        collector.on_event(&CleanEvent::Deleted {
+            target: a.clone(),
+            bytes: 10,
+        });
+        collector.on_event(&CleanEvent::Skipped {
+            target: b,
+            reason: SkipReason::Verification("no Cargo.toml next to it".to_string()),
+        });
+        let mut stats = CleanStats::default();
+        stats.add_result(&a);
+        collector.on_event(&CleanEvent::Finished { stats });
+
+        let json = serde_json::to_value(collector.report()).unwrap();
+        assert_eq!(json["version"], FORMAT_VERSION);
+        assert_eq!(json["targets"][0]["status"], "excluded");
+        assert_eq!(json["targets"][0]["last_activity"], 1_700_000_000);
+        assert_eq!(json["targets"][1]["path"], "/p/a/node_modules");
+        assert_eq!(json["targets"][1]["target_type"], "node");
+        assert_eq!(json["targets"][1]["size"], 10);
+        assert_eq!(json["targets"][1]["status"], "deleted");
+        assert_eq!(json["targets"][2]["status"], "skipped");
+        assert_eq!(json["targets"][2]["reason"], "no Cargo.toml next to it");
+        assert_eq!(json["stats"]["total_dirs"], 1);
+
+        let back: Report = serde_json::from_value(json).unwrap();
+        assert_eq!(back.targets[0].status, TargetStatus::Excluded);
+        assert_eq!(back.targets[0].target.last_activity, Some(active));
+        assert_eq!(back.targets[2].status, TargetStatus::Skipped);
+        assert_eq!(
+            back.targets[1].target.detector.as_deref(),
+            Some("node-modules")
+        );
+    }
+
+    #[test]
+    fn test_plan_round_trip() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file = temp_dir.path().join("plan.json");
+        let targets = vec![
+            ScanResult::new(PathBuf::from("/p/a/node_modules"), CleanTarget::NodeModules),
+            ScanResult::new(PathBuf::from("/p/b/target"), CleanTarget::RustTarget),
+        ];
+
+        let mut plan = Report::from_scan(&targets);
+        plan.targets[1].status = TargetStatus::Deleted;
+        plan.save(&file).unwrap();
+
+        let loaded = Report::load(&file).unwrap();
+        let remaining = loaded.remaining();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].path, targets[0].path);
+
+        fs::write(&file, r#"{"version": 99, "dry_run": false, "cancelled": false, "interrupted": false, "targets": [], "stats": {}}"#).unwrap();
+        assert!(Report::load(&file)
+            .unwrap_err()
+            .to_string()
+            .contains("newer version"));
+    }
+
+    #[test]
+    fn test_event_line() {
+        let line = event_line(&CleanEvent::Interrupted { remaining: 3 }).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(json["event"], "interrupted");
+        assert_eq!(json["remaining"], 3);
+        assert_eq!(json["version"], FORMAT_VERSION);
+        assert!(!line.contains('\n'));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8_path_round_trip() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = PathBuf::from(OsStr::from_bytes(b"/tmp/caf\xe9/target"));
+        let result = ScanResult::new(path.clone(), CleanTarget::RustTarget);
+        let json = serde_json::to_string(&result).unwrap();
+        assert!(json.contains("\"unix\":["));
+
+        let back: ScanResult = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.path, path);
+    }
+}
@@ -1,189 +1,671 @@
-use crate::platform::calculate_dir_size;
+//! Directory traversal and target discovery.
+
+use crate::detectors::{DetectorRegistry, TargetDetector};
+use crate::ignore::{IgnoreMatch, IgnoreRules, IgnoreStack, IGNORE_FILE};
+use crate::platform::measure_dir;
 use crate::types::{CleanTarget, ScanResult};
-use anyhow::Result;
+use anyhow::{Context, Result};
 use rayon::prelude::*;
-use std::path::Path;
-use walkdir::WalkDir;
-
+use serde::Serialize;
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// Walks a directory tree and collects cleanable directories
+///
+/// Configure it with the `with_*` builder methods, then call [`Scanner::scan`].
 pub struct Scanner {
     target: CleanTarget,
     max_depth: Option<usize>,
     verbose: bool,
-    self_exe_path: Option<std::path::PathBuf>,
+    self_exe_path: Option<PathBuf>,
+    registry: DetectorRegistry,
+    threads: usize,
+    source_activity: bool,
+    excludes: Vec<String>,
+    outside_root: bool,
+}
+
+/// Why [`Scanner::explain`] did or did not select a directory
+#[derive(Debug, Clone, Serialize)]
+#[non_exhaustive]
+pub struct Explanation {
+    /// The directory, canonicalized
+    #[serde(with = "crate::report::os_path")]
+    pub path: PathBuf,
+    /// What a scan would report for it, if anything
+    pub matched: Option<ScanResult>,
+    /// Every detector whose name pattern matched, in registry order
+    pub checks: Vec<DetectorCheck>,
+    /// The verdict in one sentence
+    pub reason: String,
+}
+
+/// Outcome of one detector's verification in an [`Explanation`]
+#[derive(Debug, Clone, Serialize)]
+#[non_exhaustive]
+pub struct DetectorCheck {
+    /// Detector id
+    pub detector: String,
+    /// Why verification failed; `None` if it passed
+    pub error: Option<String>,
+}
+
+/// State shared by every thread of one walk
+struct Walk<'a> {
+    on_target: &'a (dyn Fn(ScanResult) -> bool + Sync),
+    /// The canonicalized scan root
+    root: PathBuf,
+    excludes: IgnoreRules,
+    /// Targets already handed out; linked targets can be reached from
+    /// several projects
+    reported: Mutex<HashSet<PathBuf>>,
+    /// Targets recognised by their contents alone, with their detector's
+    /// id; reported once the walk is done
+    deferred: Mutex<Vec<(PathBuf, String)>>,
+    stopped: AtomicBool,
 }
 
 impl Scanner {
+    /// Create a scanner that reports directories matching `target`
     pub fn new(target: CleanTarget) -> Self {
         Self {
             target,
             max_depth: None,
             verbose: false,
             self_exe_path: std::env::current_exe().ok(),
+            registry: DetectorRegistry::builtin(),
+            threads: 0,
+            source_activity: false,
+            excludes: Vec::new(),
+            outside_root: false,
         }
     }
 
+    /// Limit how deep below the root the scanner descends
     pub fn with_max_depth(mut self, depth: usize) -> Self {
         self.max_depth = Some(depth);
         self
     }
 
+    /// Report skipped entries on stderr
     pub fn with_verbose(mut self, verbose: bool) -> Self {
         self.verbose = verbose;
         self
     }
 
+    /// Use a custom set of detectors instead of the built-in ones
+    pub fn with_registry(mut self, registry: DetectorRegistry) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    /// Number of threads used to walk and size the tree
+    ///
+    /// `0` (the default) uses one thread per CPU.
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Also count changes to the project's own files as activity
+    ///
+    /// A target's [`last_activity`](ScanResult::last_activity) is normally
+    /// the newest mtime inside it. With this set, the rest of the project
+    /// directory that contains it is walked too, skipping version control
+    /// and other targets, so editing the sources keeps a target "active".
+    pub fn with_source_activity(mut self, enabled: bool) -> Self {
+        self.source_activity = enabled;
+        self
+    }
+
+    /// Never enter or report directories matching these globs
+    ///
+    /// The patterns use gitignore syntax relative to the scan root, so
+    /// `vendor` matches at any depth and `clients/*/legacy` only below the
+    /// root; absolute paths are matched as they are. `.cleanfilesignore`
+    /// files found during the walk are always honoured as well; see
+    /// [`ignore`](crate::ignore).
+    pub fn with_excludes(mut self, globs: Vec<String>) -> Self {
+        self.excludes = globs;
+        self
+    }
+
+    /// Also report [linked targets](crate::detectors::TargetDetector::linked_targets)
+    /// outside the scan root
+    ///
+    /// A global `CARGO_TARGET_DIR` or a Bazel output base under `~/.cache`
+    /// is shared with projects that were not scanned, so by default only
+    /// linked targets inside the root are reported.
+    pub fn with_outside_root(mut self, enabled: bool) -> Self {
+        self.outside_root = enabled;
+        self
+    }
+
     /// Scan a directory for cleanable targets
+    ///
+    /// Results are sorted by path, so the same tree always yields the same
+    /// list no matter how the walk was scheduled.
     pub fn scan(&self, root: &Path) -> Result<Vec<ScanResult>> {
-        let mut results = Vec::new();
-        let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+        self.in_pool(|| {
+            // First pass: Collect all targets (parallel discovery)
+            let results = Mutex::new(Vec::new());
+            self.walk(root, &|result| {
+                results.lock().unwrap().push(result);
+                true
+            })?;
+            let mut results = results.into_inner().unwrap();
+            results.sort_by(|a, b| a.path.cmp(&b.path));
+
+            // Second pass: Calculate sizes in parallel
+            // This is the most expensive part, so we parallelize it
+            results
+                .par_iter_mut()
+                .for_each(|result| self.measure(result));
+
+            Ok(results)
+        })?
+    }
 
-        let mut walker = if let Some(depth) = self.max_depth {
-            WalkDir::new(&root).max_depth(depth)
-        } else {
-            WalkDir::new(&root)
+    /// Walk `root` and hand each matching target to `on_target` as soon as it
+    /// is found, without sizing it
+    ///
+    /// The tree is walked by several threads, so `on_target` may be called
+    /// concurrently and in no particular order. Returning `false` from it
+    /// stops the walk early. This is the building block for streaming
+    /// consumers such as [`Pipeline`](crate::pipeline::Pipeline);
+    /// [`scan`](Scanner::scan) collects the same results into a sorted `Vec`.
+    pub fn discover<F>(&self, root: &Path, on_target: F) -> Result<()>
+    where
+        F: Fn(ScanResult) -> bool + Sync,
+    {
+        self.in_pool(|| self.walk(root, &on_target))?
+    }
+
+    /// Run `op` on a pool sized by [`with_threads`](Scanner::with_threads)
+    fn in_pool<R: Send>(&self, op: impl FnOnce() -> R + Send) -> Result<R> {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(self.threads)
+            .thread_name(|i| format!("clean-files-scan-{}", i))
+            .build()
+            .context("Failed to start scanner threads")?;
+        Ok(pool.install(op))
+    }
+
+    fn walk(&self, root: &Path, on_target: &(dyn Fn(ScanResult) -> bool + Sync)) -> Result<()> {
+        let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+        let walk = Walk {
+            on_target,
+            root: root.clone(),
+            excludes: IgnoreRules::from_globs(&root, &self.excludes)?,
+            reported: Mutex::new(HashSet::new()),
+            deferred: Mutex::new(Vec::new()),
+            stopped: AtomicBool::new(false),
         };
+        self.walk_dir(&root, 0, &IgnoreStack::new(), &walk);
+
+        // A shared build directory found on its own is also a linked target
+        // of the projects using it, which know who owns it; they go first
+        let mut deferred = std::mem::take(&mut *walk.deferred.lock().unwrap());
+        deferred.sort();
+        for (path, id) in deferred {
+            if walk.stopped.load(Ordering::Relaxed) {
+                break;
+            }
+            if let Some(detector) = self.registry.get(&id) {
+                self.report(&path, None, detector, &walk);
+            }
+        }
+        Ok(())
+    }
 
-        walker = walker.min_depth(1);
+    /// List the subdirectories of `dir` and visit them in parallel; rayon's
+    /// work stealing balances deep and shallow subtrees across threads
+    fn walk_dir(&self, dir: &Path, depth: usize, ignores: &IgnoreStack, walk: &Walk<'_>) {
+        if walk.stopped.load(Ordering::Relaxed) || self.max_depth.is_some_and(|max| depth >= max) {
+            return;
+        }
 
-        // First pass: Collect all targets (sequential discovery)
-        for entry in walker.into_iter().filter_entry(|e| self.should_enter(e)) {
-            let entry = match entry {
-                Ok(e) => e,
-                Err(e) => {
-                    // Log permission errors or other access issues
-                    if self.verbose {
-                        eprintln!("⚠️  Skipped (access error): {}", e);
-                    }
-                    continue;
+        // An ignore file that cannot be read or parsed might have protected
+        // anything below, so the directory is not walked at all
+        let ignores = match ignores.enter(dir, IGNORE_FILE) {
+            Ok(ignores) => ignores,
+            Err(e) => {
+                if self.verbose {
+                    eprintln!("⚠️  Skipped (bad ignore file): {}: {:#}", dir.display(), e);
                 }
-            };
+                return;
+            }
+        };
+
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                // Log permission errors or other access issues
+                if self.verbose {
+                    eprintln!("⚠️  Skipped (access error): {}: {}", dir.display(), e);
+                }
+                return;
+            }
+        };
 
-            if !entry.file_type().is_dir() {
+        // `file_type` does not follow symlinks, so linked directories are
+        // never entered
+        let (subdirs, files): (Vec<_>, Vec<_>) = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| Some((entry.file_type().ok()?.is_dir(), entry)))
+            .partition(|(is_dir, _)| *is_dir);
+        let files: HashSet<_> = files
+            .into_iter()
+            .map(|(_, entry)| entry.file_name())
+            .collect();
+
+        // Build directories the project here keeps elsewhere
+        let has_file = |name: &str| files.contains(OsStr::new(name));
+        for (detector, path) in self.registry.linked_targets(dir, has_file) {
+            if !self.outside_root && !path.starts_with(&walk.root) {
+                if self.verbose {
+                    eprintln!(
+                        "⚠️  Skipped (outside the scan root, see --outside-root): {}",
+                        path.display()
+                    );
+                }
                 continue;
             }
+            match excluded(&walk.excludes, &ignores, &path) {
+                Some(rule) if self.verbose => {
+                    eprintln!("⚠️  Skipped (excluded by {}): {}", rule, path.display())
+                }
+                Some(_) => {}
+                None => self.report(&path, Some(dir), detector, walk),
+            }
+        }
 
-            let path = entry.path();
-            let dir_name = match path.file_name() {
-                Some(name) => name.to_string_lossy(),
-                None => continue,
-            };
+        let mut subdirs: Vec<_> = subdirs.into_iter().map(|(_, entry)| entry.path()).collect();
+        subdirs.sort();
 
-            // Check if this directory matches any of our targets
-            if let Some(target_type) = self.identify_target(&dir_name, path) {
-                // Check if we are trying to delete ourselves
-                if let Some(exe_path) = &self.self_exe_path {
-                    if exe_path.starts_with(path) {
-                        if self.verbose {
-                            eprintln!("⚠️  Skipping own build directory: {}", path.display());
-                        }
-                        continue;
-                    }
-                }
+        subdirs
+            .par_iter()
+            .for_each(|path| self.visit(path, depth + 1, &ignores, walk));
+    }
 
-                if self.target.should_clean(&target_type) {
-                    results.push(ScanResult::new(path.to_path_buf(), target_type));
-                }
+    fn visit(&self, path: &Path, depth: usize, ignores: &IgnoreStack, walk: &Walk<'_>) {
+        if walk.stopped.load(Ordering::Relaxed) {
+            return;
+        }
+
+        let dir_name = match path.file_name() {
+            Some(name) => name.to_string_lossy(),
+            None => return,
+        };
+
+        if !self.should_enter(&dir_name) {
+            return;
+        }
+
+        if let Some(rule) = excluded(&walk.excludes, ignores, path) {
+            if self.verbose {
+                eprintln!("⚠️  Skipped (excluded by {}): {}", rule, path.display());
             }
+            return;
         }
 
-        // Second pass: Calculate sizes in parallel
-        // This is the most expensive part, so we parallelize it
-        results.par_iter_mut().for_each(|result| {
-            if let Ok((size, count)) = calculate_dir_size(&result.path) {
-                result.size = size;
-                result.file_count = count;
+        // Check if this directory matches any of our targets
+        if let Some(detector) = self.identify_target(&dir_name, path) {
+            // Never descend into a target; it is processed as a whole
+            if detector.matches_name(&dir_name) || detector.link_markers().is_empty() {
+                self.report(path, None, detector, walk);
+            } else {
+                walk.deferred
+                    .lock()
+                    .unwrap()
+                    .push((path.to_path_buf(), detector.id().to_string()));
             }
-        });
+            return;
+        }
+
+        if self.registry.prunes(&dir_name) {
+            // Unverified directory with a target-like name; its contents
+            // are not worth walking
+            return;
+        }
 
-        Ok(results)
+        self.walk_dir(path, depth, ignores, walk);
     }
 
-    /// Determine if we should enter a directory during traversal
-    fn should_enter(&self, entry: &walkdir::DirEntry) -> bool {
-        if !entry.file_type().is_dir() {
-            return true;
+    /// Hand a verified target to the walk's callback, once per path
+    ///
+    /// `project` is the directory a linked target was found from.
+    fn report(
+        &self,
+        path: &Path,
+        project: Option<&Path>,
+        detector: &dyn TargetDetector,
+        walk: &Walk<'_>,
+    ) {
+        // Check if we are trying to delete ourselves
+        if let Some(exe_path) = &self.self_exe_path {
+            if exe_path.starts_with(path) {
+                if self.verbose {
+                    eprintln!("⚠️  Skipping own build directory: {}", path.display());
+                }
+                return;
+            }
         }
 
-        let dir_name = entry.file_name().to_string_lossy();
+        if !self.target.should_clean(&detector.category())
+            || !walk.reported.lock().unwrap().insert(path.to_path_buf())
+        {
+            return;
+        }
+        let mut result =
+            ScanResult::new(path.to_path_buf(), detector.category()).with_detector(detector.id());
+        if let Some(project) = project {
+            result = result.with_project(project.to_path_buf());
+        }
+        if let Some(marker) = self.registry.keep_marker(&result) {
+            result = result.with_kept_by(marker.to_string());
+        }
+        if let Some(root) = detector.workspace(path) {
+            result = result.with_workspace(root);
+        }
+        if !(walk.on_target)(result) {
+            walk.stopped.store(true, Ordering::Relaxed);
+        }
+    }
 
-        // Don't enter version control directories
-        if matches!(
-            dir_name.as_ref(),
-            ".git" | ".svn" | ".hg" | ".bzr" | ".darcs"
-        ) {
-            return false;
-        }
-
-        // Check if parent directory is one of our target types
-        // If so, don't descend (we'll process the parent as a target)
-        if let Some(parent) = entry.path().parent() {
-            if let Some(parent_name) = parent.file_name() {
-                let parent_name = parent_name.to_string_lossy();
-                // Don't descend into contents of target directories
-                if matches!(
-                    parent_name.as_ref(),
-                    "node_modules"
-                        | "target"
-                        | "__pycache__"
-                        | "build"
-                        | ".pytest_cache"
-                        | ".tox"
-                        | ".mypy_cache"
-                ) {
-                    return false;
-                }
+    /// Fill in the size, file count and last activity of a discovered target
+    ///
+    /// The newest mtime is collected by the same traversal that sizes the
+    /// target.
+    pub fn measure(&self, result: &mut ScanResult) {
+        if let Ok(usage) = measure_dir(&result.path) {
+            result.size = usage.size;
+            result.file_count = usage.files;
+            result.last_activity = usage.last_modified;
+        }
+
+        if self.source_activity {
+            if let Some(project) = result.path.parent() {
+                let sources = self.newest_source_change(project, &result.path);
+                result.last_activity = result.last_activity.max(sources);
             }
         }
+    }
+
+    /// Newest mtime below `dir`, leaving out `target`, version control and
+    /// anything the detectors consider a build directory
+    fn newest_source_change(&self, dir: &Path, target: &Path) -> Option<SystemTime> {
+        let mut newest = fs::metadata(dir).and_then(|m| m.modified()).ok();
+        let Ok(entries) = fs::read_dir(dir) else {
+            return newest;
+        };
 
-        true
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let path = entry.path();
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            let modified = if file_type.is_dir() {
+                let name = entry.file_name();
+                let name = name.to_string_lossy();
+                if path == target
+                    || !self.should_enter(&name)
+                    || self.registry.prunes(&name)
+                    || self.identify_target(&name, &path).is_some()
+                {
+                    continue;
+                }
+                self.newest_source_change(&path, target)
+            } else if file_type.is_file() {
+                entry.metadata().and_then(|m| m.modified()).ok()
+            } else {
+                continue;
+            };
+            newest = newest.max(modified);
+        }
+        newest
     }
 
-    /// Identify what type of cleanable directory this is
-    fn identify_target(&self, dir_name: &str, path: &Path) -> Option<CleanTarget> {
-        match dir_name {
-            "node_modules" => {
-                // Verify it's a node_modules by checking for package.json in parent
-                if let Some(parent) = path.parent() {
-                    if parent.join("package.json").exists() {
-                        return Some(CleanTarget::NodeModules);
-                    }
+    /// Explain why `path` would or would not be found by scanning `root`
+    ///
+    /// Applies the same rules as [`scan`](Scanner::scan): directories the
+    /// walk never enters, the detectors in registry order, the self-exe guard,
+    /// the target filter and the depth limit.
+    pub fn explain(&self, root: &Path, path: &Path) -> Result<Explanation> {
+        let root = root
+            .canonicalize()
+            .with_context(|| format!("Cannot resolve {}", root.display()))?;
+        let path = path
+            .canonicalize()
+            .with_context(|| format!("Cannot resolve {}", path.display()))?;
+
+        let mut explanation = Explanation {
+            path: path.clone(),
+            matched: None,
+            checks: Vec::new(),
+            reason: String::new(),
+        };
+        let excludes = IgnoreRules::from_globs(&root, &self.excludes)?;
+        explanation.reason = self.explain_into(&root, &path, &excludes, &mut explanation);
+        Ok(explanation)
+    }
+
+    /// The exclude glob or ignore file rule that would keep a scan of `root`
+    /// out of `path`, if any
+    ///
+    /// For targets found by an earlier scan, such as the entries of a plan.
+    /// Ignore files are read from `root` down to `path`, or from the top of
+    /// the filesystem for a `path` outside `root`.
+    pub fn excluded_by(&self, root: &Path, path: &Path) -> Result<Option<String>> {
+        let root = root
+            .canonicalize()
+            .with_context(|| format!("Cannot resolve {}", root.display()))?;
+        let path = path
+            .canonicalize()
+            .with_context(|| format!("Cannot resolve {}", path.display()))?;
+        let excludes = IgnoreRules::from_globs(&root, &self.excludes)?;
+
+        let top = match path.starts_with(&root) {
+            true => root.as_path(),
+            false => path.ancestors().last().unwrap_or(&path),
+        };
+        let mut dirs: Vec<&Path> = path
+            .ancestors()
+            .take_while(|a| a.starts_with(top))
+            .collect();
+        dirs.reverse();
+
+        let mut ignores = IgnoreStack::new();
+        for dir in dirs {
+            if dir != top {
+                if let Some(rule) = excluded(&excludes, &ignores, dir) {
+                    return Ok(Some(rule.to_string()));
                 }
-                None
             }
-            "target" => {
-                // Check if it's a Rust target (has Cargo.toml in parent)
-                if let Some(parent) = path.parent() {
-                    if parent.join("Cargo.toml").exists() {
-                        return Some(CleanTarget::RustTarget);
+            if dir != path {
+                ignores = match ignores.enter(dir, IGNORE_FILE) {
+                    Ok(ignores) => ignores,
+                    Err(e) => {
+                        return Ok(Some(format!(
+                            "the unusable {} in {}: {:#}",
+                            IGNORE_FILE,
+                            dir.display(),
+                            e
+                        )))
                     }
-                    // Check if it's a Maven/Gradle target (has pom.xml or build.gradle)
-                    if parent.join("pom.xml").exists()
-                        || parent.join("build.gradle").exists()
-                        || parent.join("build.gradle.kts").exists()
-                    {
-                        return Some(CleanTarget::JavaTarget);
-                    }
-                }
-                None
+                };
             }
-            "build" => {
-                // Gradle build directory
-                if let Some(parent) = path.parent() {
-                    if parent.join("build.gradle").exists()
-                        || parent.join("build.gradle.kts").exists()
-                    {
-                        return Some(CleanTarget::JavaTarget);
-                    }
+        }
+        Ok(None)
+    }
+
+    fn explain_into(
+        &self,
+        root: &Path,
+        path: &Path,
+        excludes: &IgnoreRules,
+        explanation: &mut Explanation,
+    ) -> String {
+        if !path.is_dir() {
+            return "not a directory".to_string();
+        }
+        let relative = match path.strip_prefix(root) {
+            Ok(relative) if !relative.as_os_str().is_empty() => relative,
+            _ => return format!("not below the scan root {}", root.display()),
+        };
+
+        // The walk has to reach the directory first
+        let components: Vec<_> = relative.components().collect();
+        let mut ancestor = root.to_path_buf();
+        let mut ignores = match IgnoreStack::new().enter(root, IGNORE_FILE) {
+            Ok(ignores) => ignores,
+            Err(e) => return format!("the scan root's {} is unusable: {:#}", IGNORE_FILE, e),
+        };
+        for component in &components[..components.len() - 1] {
+            ancestor.push(component);
+            let name = component.as_os_str().to_string_lossy();
+            if !self.should_enter(&name) {
+                return format!(
+                    "inside {}; version control directories are never entered",
+                    ancestor.display()
+                );
+            }
+            if let Some(rule) = excluded(excludes, &ignores, &ancestor) {
+                return format!(
+                    "inside {}, which is excluded by {}",
+                    ancestor.display(),
+                    rule
+                );
+            }
+            if let Some(detector) = self.identify_target(&name, &ancestor) {
+                return format!(
+                    "inside {}, a {} that is removed as a whole",
+                    ancestor.display(),
+                    detector.display_name()
+                );
+            }
+            if self.registry.prunes(&name) {
+                return format!(
+                    "inside {}, which looks like a build directory and is not walked",
+                    ancestor.display()
+                );
+            }
+            ignores = match ignores.enter(&ancestor, IGNORE_FILE) {
+                Ok(ignores) => ignores,
+                Err(e) => {
+                    return format!(
+                        "inside {}, whose {} is unusable: {:#}",
+                        ancestor.display(),
+                        IGNORE_FILE,
+                        e
+                    )
                 }
-                None
+            };
+        }
+        if let Some(max) = self.max_depth {
+            if components.len() > max {
+                return format!(
+                    "{} levels below the root, deeper than the maximum depth of {}",
+                    components.len(),
+                    max
+                );
             }
-            "__pycache__" => Some(CleanTarget::PythonCache),
-            ".pytest_cache" | ".tox" | ".mypy_cache" => Some(CleanTarget::PythonCache),
-            _ => None,
         }
+
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy())
+            .unwrap_or_default();
+        if !self.should_enter(&name) {
+            return "version control directories are never touched".to_string();
+        }
+        if let Some(rule) = excluded(excludes, &ignores, path) {
+            return format!("excluded by {}", rule);
+        }
+
+        for detector in self.registry.candidates(&name, path) {
+            explanation.checks.push(DetectorCheck {
+                detector: detector.id().to_string(),
+                error: detector.verify(path).err(),
+            });
+        }
+
+        let detector = match explanation.checks.iter().find(|c| c.error.is_none()) {
+            Some(check) => match self.registry.get(&check.detector) {
+                Some(detector) => detector,
+                None => return format!("unknown detector '{}'", check.detector),
+            },
+            None if explanation.checks.is_empty() => {
+                return format!("no detector matches the name '{}'", name)
+            }
+            None => return "the name matches, but no detector could verify it".to_string(),
+        };
+
+        if self
+            .self_exe_path
+            .as_ref()
+            .is_some_and(|exe| exe.starts_with(path))
+        {
+            return format!(
+                "matched by {}, but it contains the running executable",
+                detector.id()
+            );
+        }
+        if !self.target.should_clean(&detector.category()) {
+            return format!(
+                "matched by {}, but '{}' targets are not selected",
+                detector.id(),
+                detector.category()
+            );
+        }
+
+        let mut matched =
+            ScanResult::new(path.to_path_buf(), detector.category()).with_detector(detector.id());
+        let reason = match self.registry.keep_marker(&matched) {
+            Some(marker) => {
+                let reason = format!(
+                    "matched by {} ({}), but its project keeps it: {}",
+                    detector.display_name(),
+                    detector.id(),
+                    marker
+                );
+                matched = matched.with_kept_by(marker.to_string());
+                reason
+            }
+            None => format!("matched by {} ({})", detector.display_name(), detector.id()),
+        };
+        explanation.matched = Some(matched);
+        reason
+    }
+
+    /// Determine if we should enter a directory during traversal
+    fn should_enter(&self, dir_name: &str) -> bool {
+        // Don't enter version control directories
+        !matches!(dir_name, ".git" | ".svn" | ".hg" | ".bzr" | ".darcs")
+    }
+
+    /// Identify what type of cleanable directory this is
+    fn identify_target(&self, dir_name: &str, path: &Path) -> Option<&dyn TargetDetector> {
+        self.registry.identify(dir_name, path)
     }
 }
 
+/// The exclude glob or ignore file rule that keeps the walk out of `dir`
+fn excluded<'a>(
+    excludes: &'a IgnoreRules,
+    ignores: &'a IgnoreStack,
+    dir: &Path,
+) -> Option<IgnoreMatch<'a>> {
+    excludes
+        .matched(dir, true)
+        .or_else(|| ignores.matched(dir, true))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -234,7 +716,7 @@ mod tests {
         let temp_dir = TempDir::new().unwrap();
         let pycache = temp_dir.path().join("__pycache__");
         fs::create_dir(&pycache).unwrap();
-        fs::write(pycache.join("test.pyc"), &[0u8; 100]).unwrap();
+        fs::write(pycache.join("test.pyc"), [0u8; 100]).unwrap();
 
         let scanner = Scanner::new(CleanTarget::PythonCache);
         let results = scanner.scan(temp_dir.path()).unwrap();
@@ -261,4 +743,227 @@ mod tests {
 
         assert!(results.len() >= 2);
     }
+
+    #[test]
+    fn test_parallel_scan_is_deterministic() {
+        let temp_dir = TempDir::new().unwrap();
+        for i in 0..30 {
+            let project = temp_dir.path().join(format!("group{}/app{}", i % 4, i));
+            fs::create_dir_all(project.join("node_modules")).unwrap();
+            fs::write(project.join("package.json"), "{}").unwrap();
+            // Nothing below a VCS directory is ever a target
+            fs::create_dir_all(project.join(".git/__pycache__")).unwrap();
+        }
+
+        let expected = Scanner::new(CleanTarget::All)
+            .with_threads(1)
+            .scan(temp_dir.path())
+            .unwrap();
+        assert_eq!(expected.len(), 30);
+        assert!(expected.windows(2).all(|w| w[0].path < w[1].path));
+
+        for threads in [2, 8] {
+            let results = Scanner::new(CleanTarget::All)
+                .with_threads(threads)
+                .scan(temp_dir.path())
+                .unwrap();
+            let paths: Vec<_> = results.iter().map(|r| &r.path).collect();
+            let expected: Vec<_> = expected.iter().map(|r| &r.path).collect();
+            assert_eq!(paths, expected);
+        }
+    }
+
+    #[test]
+    fn test_max_depth_and_early_stop() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir(temp_dir.path().join("__pycache__")).unwrap();
+        fs::create_dir_all(temp_dir.path().join("a/__pycache__")).unwrap();
+        fs::create_dir_all(temp_dir.path().join("a/b/__pycache__")).unwrap();
+
+        let scanner = Scanner::new(CleanTarget::All);
+        assert_eq!(scanner.scan(temp_dir.path()).unwrap().len(), 3);
+        let shallow = Scanner::new(CleanTarget::All).with_max_depth(2);
+        assert_eq!(shallow.scan(temp_dir.path()).unwrap().len(), 2);
+        let none = Scanner::new(CleanTarget::All).with_max_depth(0);
+        assert!(none.scan(temp_dir.path()).unwrap().is_empty());
+
+        let seen = std::sync::atomic::AtomicUsize::new(0);
+        scanner
+            .with_threads(1)
+            .discover(temp_dir.path(), |_| {
+                seen.fetch_add(1, Ordering::SeqCst);
+                false
+            })
+            .unwrap();
+        assert_eq!(seen.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_explain() {
+        let temp_dir = TempDir::new().unwrap();
+        let project = temp_dir.path().join("app");
+        fs::create_dir_all(project.join("target/debug")).unwrap();
+        fs::create_dir_all(project.join(".git/objects")).unwrap();
+
+        let scanner = Scanner::new(CleanTarget::All);
+        let root = temp_dir.path();
+
+        let explanation = scanner.explain(root, &project.join("target")).unwrap();
+        assert!(explanation.matched.is_none());
+        assert_eq!(explanation.checks.len(), 5);
+        assert!(explanation.reason.contains("no detector could verify"));
+
+        fs::write(project.join("Cargo.toml"), "[package]").unwrap();
+        let explanation = scanner.explain(root, &project.join("target")).unwrap();
+        assert!(explanation.matched.is_some());
+        assert!(explanation.reason.contains("rust-target"));
+
+        let filtered = Scanner::new(CleanTarget::NodeModules);
+        let explanation = filtered.explain(root, &project.join("target")).unwrap();
+        assert!(explanation
+            .reason
+            .contains("'rust' targets are not selected"));
+
+        let inside = scanner
+            .explain(root, &project.join("target/debug"))
+            .unwrap();
+        assert!(inside.reason.contains("removed as a whole"));
+        let vcs = scanner
+            .explain(root, &project.join(".git/objects"))
+            .unwrap();
+        assert!(vcs.reason.contains("version control"));
+        let plain = scanner.explain(root, &project).unwrap();
+        assert!(plain.reason.contains("no detector matches the name 'app'"));
+    }
+
+    #[test]
+    fn test_excludes_and_ignore_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        for dir in ["vm-images/a", "work/api", "work/web", "keep"] {
+            fs::create_dir_all(root.join(dir).join("__pycache__")).unwrap();
+        }
+        fs::write(root.join(".cleanfilesignore"), "vm-images/\n").unwrap();
+        fs::write(root.join("work/.cleanfilesignore"), "/api\n").unwrap();
+
+        let scanner = Scanner::new(CleanTarget::All).with_excludes(vec!["keep".to_string()]);
+        let results = scanner.scan(root).unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].path.ends_with("work/web/__pycache__"));
+
+        let explanation = scanner
+            .explain(root, &root.join("work/api/__pycache__"))
+            .unwrap();
+        assert!(explanation.reason.contains("excluded by '/api' in"));
+        let explanation = scanner.explain(root, &root.join("keep")).unwrap();
+        assert_eq!(explanation.reason, "excluded by exclude pattern 'keep'");
+
+        // A broken ignore file protects its whole directory
+        fs::write(root.join("work/.cleanfilesignore"), "[oops\n").unwrap();
+        assert!(scanner.scan(root).unwrap().is_empty());
+        assert!(Scanner::new(CleanTarget::All)
+            .with_excludes(vec!["[oops".to_string()])
+            .scan(root)
+            .is_err());
+    }
+
+    #[test]
+    fn test_linked_cargo_target_dirs() {
+        use crate::detectors::{CargoEnv, RustTargetDetector};
+
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("src");
+        let shared = temp_dir.path().join("cache/shared-target");
+        fs::create_dir_all(shared.join("debug")).unwrap();
+        fs::write(
+            shared.join("CACHEDIR.TAG"),
+            "Signature: 8a477f597d28d172789f06886806bc55\n# created by cargo\n",
+        )
+        .unwrap();
+        fs::write(
+            shared.join(".rustc_info.json"),
+            r#"{"rustc_fingerprint":7}"#,
+        )
+        .unwrap();
+        fs::create_dir_all(root.join(".cargo")).unwrap();
+        fs::write(
+            root.join(".cargo/config.toml"),
+            "[build]\ntarget-dir = \"../cache/shared-target\"\n",
+        )
+        .unwrap();
+        for app in ["a", "b"] {
+            fs::create_dir_all(root.join(app)).unwrap();
+            fs::write(root.join(app).join("Cargo.toml"), "[package]").unwrap();
+        }
+
+        // Outside the scanned tree, it is only reported when asked for
+        let registry =
+            DetectorRegistry::new().with(RustTargetDetector::with_env(CargoEnv::default()));
+        let default = Scanner::new(CleanTarget::All)
+            .with_registry(registry.clone())
+            .scan(&root)
+            .unwrap();
+        assert!(default.is_empty());
+
+        // Both projects build into the shared directory; it is found once
+        let results = Scanner::new(CleanTarget::All)
+            .with_registry(registry.clone())
+            .with_outside_root(true)
+            .scan(&root)
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].path, shared.canonicalize().unwrap());
+        assert_eq!(results[0].detector.as_deref(), Some("rust-target"));
+        assert!(registry.verify(&results[0]).is_ok());
+
+        let excluded = Scanner::new(CleanTarget::All)
+            .with_registry(registry)
+            .with_outside_root(true)
+            .with_excludes(vec!["shared-target".to_string()])
+            .scan(&root)
+            .unwrap();
+        assert!(excluded.is_empty());
+    }
+
+    #[test]
+    fn test_last_activity_and_sources() {
+        let temp_dir = TempDir::new().unwrap();
+        let project = temp_dir.path().join("app");
+        let node_modules = project.join("node_modules");
+        fs::create_dir_all(&node_modules).unwrap();
+        fs::write(project.join("package.json"), "{}").unwrap();
+        fs::write(node_modules.join("index.js"), "x").unwrap();
+
+        let old = std::time::SystemTime::now() - std::time::Duration::from_secs(86_400 * 60);
+        for path in [
+            node_modules.join("index.js"),
+            node_modules.clone(),
+            project.join("package.json"),
+            project.clone(),
+        ] {
+            fs::File::options()
+                .read(true)
+                .open(path)
+                .unwrap()
+                .set_modified(old)
+                .unwrap();
+        }
+
+        let results = Scanner::new(CleanTarget::All)
+            .scan(temp_dir.path())
+            .unwrap();
+        assert_eq!(results[0].last_activity, Some(old));
+
+        // Editing the sources only counts when asked for
+        fs::write(project.join("index.js"), "edited").unwrap();
+        let results = Scanner::new(CleanTarget::All)
+            .scan(temp_dir.path())
+            .unwrap();
+        assert_eq!(results[0].last_activity, Some(old));
+        let results = Scanner::new(CleanTarget::All)
+            .with_source_activity(true)
+            .scan(temp_dir.path())
+            .unwrap();
+        assert!(results[0].last_activity.unwrap() > old);
+    }
 }
@@ -0,0 +1,736 @@
+//! Partial cleaning of Cargo target directories.
+//!
+//! Deleting a whole `target` forces a full rebuild, yet most of the waste is
+//! usually in a few places. A [`Sweep`] removes only what it was asked to and
+//! leaves the rest of the directory usable by cargo:
+//!
+//! - the `incremental` caches of every profile, and generated `doc`s
+//! - everything built with a profile (`debug`, `release` or a custom one),
+//!   for the host and every target triple
+//! - everything cross-compiled for a target triple
+//! - compiled crates that no build has used for a while, or that were built
+//!   by a rustc that is no longer installed, like `cargo sweep`
+//!
+//! A compiled crate ("unit") is recorded in `.fingerprint/<name>-<hash>` of
+//! its profile directory, with its output in `deps` and `build` under the
+//! same hash. Cargo reads every fingerprint of the dependency graph on each
+//! build, so the fingerprint's access time says when the unit was last used.
+//! On filesystems mounted with `noatime` that falls back to when it was
+//! built. The compiler version is read from the `.rmeta` headers and shared
+//! with binaries and build scripts through the compiler hash in the
+//! fingerprints; units whose compiler cannot be told are kept.
+
+use crate::platform::{measure_dir, remove_dir_all};
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, SystemTime};
+
+/// Why a part of a target directory is removed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", content = "detail", rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum SweepReason {
+    /// Everything built for this target triple
+    Triple(String),
+    /// Everything built with this profile
+    Profile(String),
+    /// An `incremental` compilation cache
+    Incremental,
+    /// Generated documentation
+    Doc,
+    /// A unit no build has used for this many days
+    Unused(u64),
+    /// A unit built by this rustc, which is no longer installed
+    OldToolchain(String),
+}
+
+impl SweepReason {
+    /// Stable identifier of the variant, used to group items
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SweepReason::Triple(_) => "triple",
+            SweepReason::Profile(_) => "profile",
+            SweepReason::Incremental => "incremental",
+            SweepReason::Doc => "doc",
+            SweepReason::Unused(_) => "unused",
+            SweepReason::OldToolchain(_) => "old_toolchain",
+        }
+    }
+}
+
+impl fmt::Display for SweepReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SweepReason::Triple(triple) => write!(f, "built for {}", triple),
+            SweepReason::Profile(profile) => write!(f, "built with profile {}", profile),
+            SweepReason::Incremental => write!(f, "incremental cache"),
+            SweepReason::Doc => write!(f, "documentation"),
+            SweepReason::Unused(1) => write!(f, "unused for 1 day"),
+            SweepReason::Unused(days) => write!(f, "unused for {} days", days),
+            SweepReason::OldToolchain(version) => {
+                write!(f, "built by {}, which is not installed", version)
+            }
+        }
+    }
+}
+
+/// One thing a sweep removes: a directory, or the files of one unit
+#[derive(Debug, Clone, Serialize)]
+pub struct SweepItem {
+    /// Where it is, relative to the target directory (`debug/incremental`,
+    /// or `debug/serde-<hash>` for a unit)
+    pub name: String,
+    /// Files and directories removed together
+    #[serde(serialize_with = "serialize_paths")]
+    pub paths: Vec<PathBuf>,
+    /// Total size in bytes
+    pub size: u64,
+    /// Number of files
+    pub files: usize,
+    /// Why it is removed
+    pub reason: SweepReason,
+}
+
+impl SweepItem {
+    fn new(target: &Path, paths: Vec<PathBuf>, reason: SweepReason) -> Self {
+        let name = paths[0]
+            .strip_prefix(target)
+            .unwrap_or(&paths[0])
+            .to_string_lossy()
+            .replace(".fingerprint/", "");
+        let mut item = Self {
+            name,
+            paths,
+            size: 0,
+            files: 0,
+            reason,
+        };
+        for path in &item.paths {
+            if let Ok(usage) = measure_dir(path) {
+                item.size += usage.size;
+                item.files += usage.files;
+            }
+        }
+        item
+    }
+
+    /// Delete everything the item covers
+    pub fn remove(&self) -> Result<()> {
+        remove_paths(&self.paths)
+    }
+}
+
+/// Delete files and directory trees; paths already gone are not an error
+pub(crate) fn remove_paths(paths: &[PathBuf]) -> Result<()> {
+    for path in paths {
+        if path.is_dir() && !path.is_symlink() {
+            remove_dir_all(path)?;
+        } else {
+            match fs::remove_file(path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                    return Err(e).with_context(|| format!("Failed to remove {}", path.display()))
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn serialize_paths<S: serde::Serializer>(
+    paths: &[PathBuf],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(paths.iter().map(|p| p.to_string_lossy()))
+}
+
+/// What to remove from Cargo target directories
+///
+/// ```no_run
+/// use clean_files::sweep::Sweep;
+/// use std::time::Duration;
+///
+/// # fn main() -> anyhow::Result<()> {
+/// let sweep = Sweep::new()
+///     .with_incremental(true)
+///     .with_older_than(Duration::from_secs(30 * 86_400));
+/// for item in sweep.plan("target".as_ref())? {
+///     item.remove()?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Sweep {
+    triples: Vec<String>,
+    profiles: Vec<String>,
+    incremental: bool,
+    doc: bool,
+    older_than: Option<Duration>,
+    installed: Option<Vec<String>>,
+}
+
+impl Sweep {
+    /// A sweep that removes nothing
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Remove everything built for `triple`
+    pub fn with_triple(mut self, triple: impl Into<String>) -> Self {
+        self.triples.push(triple.into());
+        self
+    }
+
+    /// Remove everything built with `profile`
+    ///
+    /// `dev` and `test` build into `debug`, `bench` into `release`.
+    pub fn with_profile(mut self, profile: &str) -> Self {
+        let dir = match profile {
+            "dev" | "test" => "debug",
+            "bench" => "release",
+            other => other,
+        };
+        self.profiles.push(dir.to_string());
+        self
+    }
+
+    /// Remove the incremental compilation caches
+    pub fn with_incremental(mut self, enabled: bool) -> Self {
+        self.incremental = enabled;
+        self
+    }
+
+    /// Remove generated documentation
+    pub fn with_doc(mut self, enabled: bool) -> Self {
+        self.doc = enabled;
+        self
+    }
+
+    /// Remove units no build has used for at least `age`
+    pub fn with_older_than(mut self, age: Duration) -> Self {
+        self.older_than = Some(age);
+        self
+    }
+
+    /// Remove units built by a compiler whose version line is not in
+    /// `versions` (see [`installed_toolchains`])
+    pub fn with_installed_toolchains(mut self, versions: Vec<String>) -> Self {
+        self.installed = Some(versions);
+        self
+    }
+
+    /// Whether nothing at all was selected
+    pub fn is_empty(&self) -> bool {
+        self.triples.is_empty()
+            && self.profiles.is_empty()
+            && !self.incremental
+            && !self.doc
+            && self.older_than.is_none()
+            && self.installed.is_none()
+    }
+
+    /// What this sweep would remove from the target directory `target`
+    ///
+    /// Nothing is listed twice: the contents of a triple or profile that is
+    /// removed as a whole are not listed again.
+    pub fn plan(&self, target: &Path) -> Result<Vec<SweepItem>> {
+        let layout =
+            Layout::read(target).with_context(|| format!("Failed to read {}", target.display()))?;
+        let mut items = Vec::new();
+
+        let mut triples = Vec::new();
+        for triple in &layout.triples {
+            if self.triples.contains(triple) {
+                let reason = SweepReason::Triple(triple.clone());
+                items.push(SweepItem::new(target, vec![target.join(triple)], reason));
+            } else {
+                triples.push(triple);
+            }
+        }
+
+        let mut profiles = Vec::new();
+        for dir in &layout.profiles {
+            if dir
+                .triple
+                .as_ref()
+                .is_some_and(|t| self.triples.contains(t))
+            {
+                continue;
+            }
+            if self.profiles.contains(&dir.profile) {
+                let reason = SweepReason::Profile(dir.profile.clone());
+                items.push(SweepItem::new(target, vec![dir.path.clone()], reason));
+            } else {
+                profiles.push(dir);
+            }
+        }
+
+        if self.doc {
+            let roots = std::iter::once(target.to_path_buf())
+                .chain(triples.iter().map(|triple| target.join(triple)));
+            for doc in roots.map(|root| root.join("doc")).filter(|d| d.is_dir()) {
+                items.push(SweepItem::new(target, vec![doc], SweepReason::Doc));
+            }
+        }
+
+        if self.incremental {
+            for dir in &profiles {
+                let incremental = dir.path.join("incremental");
+                if incremental.is_dir() {
+                    items.push(SweepItem::new(
+                        target,
+                        vec![incremental],
+                        SweepReason::Incremental,
+                    ));
+                }
+            }
+        }
+
+        if self.older_than.is_some() || self.installed.is_some() {
+            let units: Vec<Vec<Unit>> = profiles
+                .iter()
+                .map(|dir| Unit::list(&dir.path))
+                .collect::<Result<_>>()?;
+            let compilers = compiler_versions(units.iter().flatten());
+            let now = SystemTime::now();
+            for unit in units.iter().flatten() {
+                if let Some(reason) = self.unit_reason(unit, &compilers, now) {
+                    items.push(SweepItem::new(target, unit.paths.clone(), reason));
+                }
+            }
+        }
+
+        Ok(items)
+    }
+
+    fn unit_reason(
+        &self,
+        unit: &Unit,
+        compilers: &HashMap<u64, String>,
+        now: SystemTime,
+    ) -> Option<SweepReason> {
+        if let Some(installed) = &self.installed {
+            let version = unit.rustc.and_then(|hash| compilers.get(&hash));
+            if let Some(version) = version.filter(|v| !installed.contains(v)) {
+                return Some(SweepReason::OldToolchain(version.clone()));
+            }
+        }
+        let age = now.duration_since(unit.last_used?).ok()?;
+        match self.older_than {
+            Some(limit) if age >= limit => Some(SweepReason::Unused(age.as_secs() / 86_400)),
+            _ => None,
+        }
+    }
+}
+
+/// Version lines (`rustc 1.80.0 (051478957 2024-07-21)`) of every installed
+/// toolchain
+///
+/// Asks rustup for its toolchains, or runs the `rustc` on `PATH` if there is
+/// no rustup. Fails rather than return nothing, since every unit would then
+/// look like it came from an uninstalled compiler.
+pub fn installed_toolchains() -> Result<Vec<String>> {
+    let mut versions = Vec::new();
+    let toolchains = command_output(Command::new("rustup").args(["toolchain", "list"]));
+    match toolchains {
+        Some(list) => {
+            for name in list.lines().filter_map(|l| l.split_whitespace().next()) {
+                let version =
+                    command_output(Command::new("rustup").args(["run", name, "rustc", "-V"]));
+                versions.extend(version);
+            }
+        }
+        None => versions.extend(command_output(Command::new("rustc").arg("-V"))),
+    }
+    versions.retain(|v| v.starts_with("rustc "));
+    if versions.is_empty() {
+        bail!("No Rust toolchain found, so there is no telling which ones were removed");
+    }
+    Ok(versions)
+}
+
+fn command_output(command: &mut Command) -> Option<String> {
+    let output = command.output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8(output.stdout).ok()?;
+    Some(text.trim().to_string())
+}
+
+/// A profile directory such as `debug` or `x86_64-unknown-linux-gnu/release`
+#[derive(Debug)]
+struct ProfileDir {
+    path: PathBuf,
+    profile: String,
+    triple: Option<String>,
+}
+
+/// The profile and triple directories of a target directory
+#[derive(Debug, Default)]
+struct Layout {
+    profiles: Vec<ProfileDir>,
+    triples: Vec<String>,
+}
+
+impl Layout {
+    /// Profile directories are told apart by their `.fingerprint`; a triple
+    /// directory holds profile directories
+    fn read(target: &Path) -> Result<Self> {
+        let mut layout = Layout::default();
+        for (name, path) in subdirs(target)? {
+            if is_profile_dir(&path) {
+                layout.profiles.push(ProfileDir {
+                    path,
+                    profile: name,
+                    triple: None,
+                });
+                continue;
+            }
+            let profiles: Vec<_> = subdirs(&path)?
+                .into_iter()
+                .filter(|(_, p)| is_profile_dir(p))
+                .collect();
+            if profiles.is_empty() {
+                continue;
+            }
+            for (profile, path) in profiles {
+                layout.profiles.push(ProfileDir {
+                    path,
+                    profile,
+                    triple: Some(name.clone()),
+                });
+            }
+            layout.triples.push(name);
+        }
+        Ok(layout)
+    }
+}
+
+fn is_profile_dir(path: &Path) -> bool {
+    path.join(".fingerprint").is_dir()
+}
+
+/// Real subdirectories of `dir` with UTF-8 names, sorted
+fn subdirs(dir: &Path) -> Result<Vec<(String, PathBuf)>> {
+    let mut dirs = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+        if let Ok(name) = entry.file_name().into_string() {
+            dirs.push((name, entry.path()));
+        }
+    }
+    dirs.sort();
+    Ok(dirs)
+}
+
+/// One compiled crate in a profile directory
+#[derive(Debug)]
+struct Unit {
+    /// Its fingerprint directory first, then its outputs
+    paths: Vec<PathBuf>,
+    /// Newest access or modification of its fingerprint
+    last_used: Option<SystemTime>,
+    /// Hash of the compiler that built it, from the fingerprint
+    rustc: Option<u64>,
+    /// Version line read from its `.rmeta`, if it has one
+    version: Option<String>,
+}
+
+impl Unit {
+    /// Every unit with a fingerprint in `profile_dir`
+    fn list(profile_dir: &Path) -> Result<Vec<Unit>> {
+        // Outputs of all units, by hash
+        let mut outputs: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+        for dir in ["build", "deps", "examples"] {
+            let Ok(entries) = fs::read_dir(profile_dir.join(dir)) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                if let Some(hash) = unit_hash(&entry.file_name().to_string_lossy()) {
+                    outputs
+                        .entry(hash.to_string())
+                        .or_default()
+                        .push(entry.path());
+                }
+            }
+        }
+
+        let mut units = Vec::new();
+        for (name, fingerprint) in subdirs(&profile_dir.join(".fingerprint"))? {
+            let Some(hash) = unit_hash(&name) else {
+                continue;
+            };
+            let mut paths = vec![fingerprint.clone()];
+            let mut outputs = outputs.get(hash).cloned().unwrap_or_default();
+            outputs.sort();
+            let version = outputs
+                .iter()
+                .find(|p| p.extension().is_some_and(|e| e == "rmeta"))
+                .and_then(|rmeta| rmeta_version(rmeta));
+            paths.extend(outputs);
+
+            let (last_used, rustc) = read_fingerprint(&fingerprint);
+            units.push(Unit {
+                paths,
+                last_used,
+                rustc,
+                version,
+            });
+        }
+        Ok(units)
+    }
+}
+
+/// The hash in `name-0123456789abcdef` or `libname-0123456789abcdef.rlib`
+fn unit_hash(file_name: &str) -> Option<&str> {
+    let stem = file_name.split('.').next()?;
+    let (_, hash) = stem.rsplit_once('-')?;
+    (hash.len() == 16 && hash.bytes().all(|b| b.is_ascii_hexdigit())).then_some(hash)
+}
+
+/// When a fingerprint was last used, and the compiler hash it records
+fn read_fingerprint(dir: &Path) -> (Option<SystemTime>, Option<u64>) {
+    let mut last_used = None;
+    let mut rustc = None;
+    let Ok(entries) = fs::read_dir(dir) else {
+        return (None, None);
+    };
+    for entry in entries.flatten() {
+        if let Ok(metadata) = entry.metadata() {
+            last_used = last_used
+                .max(metadata.accessed().ok())
+                .max(metadata.modified().ok());
+        }
+        if rustc.is_none() && entry.path().extension().is_some_and(|e| e == "json") {
+            rustc = read_without_touching(&entry.path())
+                .ok()
+                .and_then(|text| serde_json::from_str::<serde_json::Value>(&text).ok())
+                .and_then(|json| json.get("rustc")?.as_u64());
+        }
+    }
+    (last_used, rustc)
+}
+
+/// Read a file without updating its access time where the platform allows,
+/// so that looking at a fingerprint does not count as using it
+fn read_without_touching(path: &Path) -> io::Result<String> {
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        // Only the file's owner may ask for this
+        if let Ok(mut file) = File::options()
+            .read(true)
+            .custom_flags(libc::O_NOATIME)
+            .open(path)
+        {
+            let mut text = String::new();
+            file.read_to_string(&mut text)?;
+            return Ok(text);
+        }
+    }
+    fs::read_to_string(path)
+}
+
+/// Compiler version lines by the hash cargo records for them
+fn compiler_versions<'a>(units: impl Iterator<Item = &'a Unit>) -> HashMap<u64, String> {
+    let mut versions = HashMap::new();
+    for unit in units {
+        if let (Some(hash), Some(version)) = (unit.rustc, &unit.version) {
+            versions.entry(hash).or_insert_with(|| version.clone());
+        }
+    }
+    versions
+}
+
+/// The `rustc x.y.z (hash date)` line rustc writes near the start of every
+/// `.rmeta`
+fn rmeta_version(path: &Path) -> Option<String> {
+    let mut header = Vec::new();
+    File::open(path)
+        .ok()?
+        .take(512)
+        .read_to_end(&mut header)
+        .ok()?;
+    let start = header.windows(6).position(|w| w == b"rustc ")?;
+    let len = header[start..].iter().position(|&b| b == b')')? + 1;
+    let version = std::str::from_utf8(&header[start..start + len]).ok()?;
+    version
+        .bytes()
+        .all(|b| b.is_ascii_graphic() || b == b' ')
+        .then(|| version.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::FileTimes;
+    use tempfile::TempDir;
+
+    const OLD_RUSTC: &str = "rustc 1.70.0 (90c541806 2023-05-31)";
+    const NEW_RUSTC: &str = "rustc 1.80.0 (051478957 2024-07-21)";
+
+    /// A unit as cargo leaves it: fingerprint, dep-info and library outputs
+    fn fake_unit(profile: &Path, name: &str, hash: &str, rustc: u64, version: Option<&str>) {
+        let fingerprint = profile.join(format!(".fingerprint/{}-{}", name, hash));
+        fs::create_dir_all(&fingerprint).unwrap();
+        fs::write(
+            fingerprint.join(format!("lib-{}.json", name)),
+            format!(r#"{{"rustc":{},"features":"[]"}}"#, rustc),
+        )
+        .unwrap();
+        fs::create_dir_all(profile.join("deps")).unwrap();
+        fs::write(profile.join(format!("deps/{}-{}.d", name, hash)), "deps").unwrap();
+        if let Some(version) = version {
+            let mut rmeta = b"rust\0\0\0\x0a\0\0\0\0\0\0\0\0#".to_vec();
+            rmeta.extend_from_slice(version.as_bytes());
+            rmeta.extend_from_slice(b"\xc1\x02\xfb");
+            fs::write(
+                profile.join(format!("deps/lib{}-{}.rmeta", name, hash)),
+                rmeta,
+            )
+            .unwrap();
+        }
+    }
+
+    fn age(path: &Path, days: u64) {
+        let time = SystemTime::now() - Duration::from_secs(days * 86_400);
+        for entry in fs::read_dir(path).unwrap() {
+            File::options()
+                .write(true)
+                .open(entry.unwrap().path())
+                .unwrap()
+                .set_times(FileTimes::new().set_accessed(time).set_modified(time))
+                .unwrap();
+        }
+    }
+
+    fn names(items: &[SweepItem]) -> Vec<&str> {
+        items.iter().map(|i| i.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_components_profiles_and_triples() {
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path();
+        for profile in ["debug", "release", "wasm32-unknown-unknown/release"] {
+            fs::create_dir_all(target.join(profile).join(".fingerprint")).unwrap();
+            fs::create_dir_all(target.join(profile).join("incremental/x-1")).unwrap();
+            fs::write(target.join(profile).join("incremental/x-1/q"), "q").unwrap();
+        }
+        fs::create_dir_all(target.join("doc/app")).unwrap();
+        fs::create_dir_all(target.join("wasm32-unknown-unknown/doc")).unwrap();
+        fs::create_dir_all(target.join("tmp")).unwrap();
+
+        let items = Sweep::new()
+            .with_incremental(true)
+            .with_doc(true)
+            .plan(target)
+            .unwrap();
+        assert_eq!(
+            names(&items),
+            [
+                "doc",
+                "wasm32-unknown-unknown/doc",
+                "debug/incremental",
+                "release/incremental",
+                "wasm32-unknown-unknown/release/incremental",
+            ]
+        );
+        assert_eq!(items[2].files, 1);
+
+        // Whole triples and profiles swallow their components
+        let items = Sweep::new()
+            .with_triple("wasm32-unknown-unknown")
+            .with_profile("bench")
+            .with_incremental(true)
+            .with_doc(true)
+            .plan(target)
+            .unwrap();
+        assert_eq!(
+            names(&items),
+            [
+                "wasm32-unknown-unknown",
+                "release",
+                "doc",
+                "debug/incremental"
+            ]
+        );
+        assert_eq!(items[1].reason, SweepReason::Profile("release".into()));
+
+        items[0].remove().unwrap();
+        assert!(!target.join("wasm32-unknown-unknown").exists());
+        assert!(target.join("tmp").exists());
+    }
+
+    #[test]
+    fn test_unused_and_old_toolchain_units() {
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path();
+        let debug = target.join("debug");
+        fake_unit(&debug, "serde", "00000000000000a1", 7, Some(OLD_RUSTC));
+        // Binaries have no .rmeta; the compiler hash ties them to one
+        fake_unit(&debug, "app", "00000000000000a2", 7, None);
+        fake_unit(&debug, "log", "00000000000000b1", 8, Some(NEW_RUSTC));
+        fake_unit(&debug, "stale", "00000000000000b2", 8, Some(NEW_RUSTC));
+        fs::create_dir_all(debug.join("build/stale-00000000000000b2")).unwrap();
+        age(&debug.join(".fingerprint/stale-00000000000000b2"), 40);
+
+        let items = Sweep::new()
+            .with_older_than(Duration::from_secs(30 * 86_400))
+            .plan(target)
+            .unwrap();
+        assert_eq!(names(&items), ["debug/stale-00000000000000b2"]);
+        assert_eq!(items[0].reason, SweepReason::Unused(40));
+        assert_eq!(items[0].paths.len(), 4);
+
+        let items = Sweep::new()
+            .with_installed_toolchains(vec![NEW_RUSTC.to_string()])
+            .plan(target)
+            .unwrap();
+        assert_eq!(
+            names(&items),
+            ["debug/app-00000000000000a2", "debug/serde-00000000000000a1"]
+        );
+        assert_eq!(items[1].reason, SweepReason::OldToolchain(OLD_RUSTC.into()));
+
+        for item in &items {
+            item.remove().unwrap();
+        }
+        let left: Vec<_> = fs::read_dir(debug.join("deps"))
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        assert!(left.iter().all(|name| name.contains("-00000000000000b")));
+        assert!(debug.join(".fingerprint/log-00000000000000b1").exists());
+    }
+
+    #[test]
+    fn test_unit_hash_and_rmeta_version() {
+        assert_eq!(
+            unit_hash("libserde-0123456789abcdef.rlib"),
+            Some("0123456789abcdef")
+        );
+        assert_eq!(
+            unit_hash("build-script-build-0123456789abcdef"),
+            Some("0123456789abcdef")
+        );
+        assert_eq!(unit_hash("libserde.rlib"), None);
+        assert_eq!(unit_hash("x-0123"), None);
+
+        let temp_dir = TempDir::new().unwrap();
+        fake_unit(temp_dir.path(), "a", "0123456789abcdef", 1, Some(NEW_RUSTC));
+        let rmeta = temp_dir.path().join("deps/liba-0123456789abcdef.rmeta");
+        assert_eq!(rmeta_version(&rmeta).as_deref(), Some(NEW_RUSTC));
+    }
+}
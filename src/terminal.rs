@@ -0,0 +1,388 @@
+use clean_files::detectors::{DetectorRegistry, PackageManager};
+use clean_files::filter::{Excluded, Exclusion};
+use clean_files::platform::local_datetime;
+use clean_files::utils::format_size;
+use clean_files::{CleanEvent, CleanObserver, ScanResult};
+use colored::*;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Colored terminal output and progress bar driven by cleaner events
+pub struct TerminalReporter {
+    verbose: bool,
+    details: bool,
+    registry: DetectorRegistry,
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    dry_run: bool,
+    total: usize,
+    progress: Option<ProgressBar>,
+    /// Targets left out by the filter
+    excluded: Vec<Excluded>,
+}
+
+impl TerminalReporter {
+    pub fn new(verbose: bool, registry: DetectorRegistry) -> Self {
+        Self {
+            verbose,
+            details: false,
+            registry,
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// List every target in the summary even without --verbose, e.g. when
+    /// a filter decided based on what the list shows
+    pub fn with_details(mut self, details: bool) -> Self {
+        self.details = details;
+        self
+    }
+
+    /// Print a summary of what will be cleaned
+    fn print_summary(&self, results: &[ScanResult], excluded: &[Excluded], dry_run: bool) {
+        println!("\n{}", "=".repeat(60).cyan());
+        if dry_run {
+            println!("{}", "DRY RUN - No files will be deleted".yellow().bold());
+        } else {
+            println!("{}", "Cleanup Summary".cyan().bold());
+        }
+        println!("{}\n", "=".repeat(60).cyan());
+
+        let total_size: u64 = results.iter().map(|r| r.size).sum();
+        let total_files: usize = results.iter().map(|r| r.file_count).sum();
+
+        println!(
+            "Found {} directories to clean:",
+            results.len().to_string().green().bold()
+        );
+        println!("Total size: {}", format_size(total_size).cyan().bold());
+        println!("Total files: {}", total_files.to_string().yellow().bold());
+        println!();
+
+        if self.verbose || self.details {
+            for result in results {
+                print_target(result, &self.registry);
+            }
+            println!();
+        }
+
+        if !excluded.is_empty() {
+            print_left_out(excluded, self.verbose, &self.registry);
+            println!();
+        }
+
+        println!("{}", "=".repeat(60).cyan());
+        println!();
+    }
+
+    /// Progress bar for the current run, created on the first target
+    /// (for both dry-run and real mode if not verbose)
+    fn progress<'a>(&self, state: &'a mut State) -> Option<&'a ProgressBar> {
+        if self.verbose {
+            return None;
+        }
+        if state.progress.is_none() {
+            let pb = ProgressBar::new(state.total as u64);
+            pb.set_style(
+                ProgressStyle::default_bar()
+                    .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}")
+                    .unwrap()
+                    .progress_chars("#>-"),
+            );
+            state.progress = Some(pb);
+        }
+        state.progress.as_ref()
+    }
+}
+
+impl CleanObserver for TerminalReporter {
+    fn on_event(&self, event: &CleanEvent) {
+        let mut state = self.state.lock().unwrap();
+
+        match event {
+            CleanEvent::Excluded { target, reason } => {
+                if self.verbose {
+                    println!(
+                        "{} Left out {}: {}",
+                        "•".yellow(),
+                        target.path.display(),
+                        reason
+                    );
+                } else if let Some(pb) = &state.progress {
+                    // Streaming: the target was already counted when found
+                    pb.inc(1);
+                }
+                state.excluded.push(Excluded {
+                    target: target.clone(),
+                    reason: reason.clone(),
+                });
+            }
+            CleanEvent::Planned { targets, dry_run } => {
+                state.dry_run = *dry_run;
+                state.total = targets.len();
+                state.progress = None;
+                if targets.is_empty() && state.excluded.is_empty() {
+                    println!("{}", "No directories found to clean.".yellow());
+                } else {
+                    self.print_summary(targets, &state.excluded, *dry_run);
+                }
+            }
+            CleanEvent::Streaming { dry_run } => {
+                state.dry_run = *dry_run;
+                state.total = 0;
+                state.progress = None;
+                println!(
+                    "{}",
+                    "Streaming mode: targets are processed as they are found".cyan()
+                );
+                println!();
+            }
+            CleanEvent::Found { target } => {
+                if self.verbose {
+                    print_target(target, &self.registry);
+                } else if let Some(pb) = self.progress(&mut state) {
+                    // The total is unknown while streaming; grow it as we go
+                    pb.inc_length(1);
+                }
+            }
+            CleanEvent::Cancelled => {
+                println!("{}", "Cleanup cancelled.".yellow());
+            }
+            CleanEvent::WouldDelete { target } => {
+                if self.verbose {
+                    println!(
+                        "{} {} {} ({})",
+                        "[DRY RUN]".yellow(),
+                        "Would delete:".white(),
+                        target.path.display(),
+                        format_size(target.size).cyan()
+                    );
+                } else if let Some(pb) = self.progress(&mut state) {
+                    pb.set_message(format!("Checking: {}", dir_name(target)));
+                    pb.inc(1);
+                }
+            }
+            CleanEvent::Skipped { target, reason } => {
+                if self.verbose {
+                    eprintln!(
+                        "{} Skipped {}: {}",
+                        "⚠️".yellow(),
+                        target.path.display(),
+                        reason
+                    );
+                } else if let Some(pb) = self.progress(&mut state) {
+                    pb.inc(1);
+                }
+            }
+            CleanEvent::Deleting { target } => {
+                if self.verbose {
+                    println!("{} {}", "Deleting:".red(), target.path.display());
+                } else if let Some(pb) = self.progress(&mut state) {
+                    // Update progress bar with current directory name
+                    pb.set_message(format!("Deleting: {}", dir_name(target)));
+                }
+            }
+            CleanEvent::Deleted { bytes, .. } => {
+                if self.verbose {
+                    println!("  {} {} freed", "✓".green(), format_size(*bytes).cyan());
+                } else if let Some(pb) = self.progress(&mut state) {
+                    pb.inc(1);
+                }
+            }
+            CleanEvent::Trashed { location, .. } => {
+                if self.verbose {
+                    println!(
+                        "  {} moved to {}",
+                        "✓".green(),
+                        location.display().to_string().dimmed()
+                    );
+                } else if let Some(pb) = self.progress(&mut state) {
+                    pb.inc(1);
+                }
+            }
+            CleanEvent::Failed { target, error } => {
+                let failure = format!(
+                    "{} Failed to delete {}: {}",
+                    "✗".red(),
+                    target.path.display(),
+                    error
+                );
+                match self.progress(&mut state) {
+                    Some(pb) => {
+                        pb.suspend(|| eprintln!("{}", failure));
+                        pb.inc(1);
+                    }
+                    None => eprintln!("{}", failure),
+                }
+            }
+            CleanEvent::Interrupted { .. } => {
+                if let Some(pb) = &state.progress {
+                    pb.finish_with_message("Interrupted!");
+                }
+                println!(
+                    "\n{}",
+                    "⚠️  Cleanup interrupted by user. Remaining directories skipped."
+                        .yellow()
+                        .bold()
+                );
+            }
+            CleanEvent::SpaceGoal { outcome } => {
+                if let Some(pb) = state.progress.take() {
+                    pb.finish_and_clear();
+                }
+                let estimate = if outcome.estimated { "about " } else { "" };
+                let line = format!(
+                    "{}{} available, goal {}",
+                    estimate,
+                    format_size(outcome.available),
+                    format_size(outcome.required)
+                );
+                if outcome.met {
+                    println!("{} Free-space goal met: {}", "✓".green(), line);
+                } else {
+                    println!("{} Free-space goal not met: {}", "✗".red(), line.yellow());
+                }
+            }
+            CleanEvent::Finished { stats } => {
+                // Finish progress bar with appropriate message
+                if let Some(pb) = state.progress.take() {
+                    if state.dry_run {
+                        pb.finish_with_message("Dry run complete!");
+                    } else if stats.failed_dirs > 0 || stats.skipped_dirs > 0 {
+                        pb.finish_with_message(format!(
+                            "Done with {} warnings",
+                            stats.failed_dirs + stats.skipped_dirs
+                        ));
+                    } else {
+                        pb.finish_with_message("Successfully completed!");
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Ask user for confirmation
+    fn confirm(&self, _targets: &[ScanResult]) -> bool {
+        ask_to_proceed(&mut io::stdout())
+    }
+}
+
+/// One line per target: detector, path, size, file count and last activity
+pub fn print_target(result: &ScanResult, registry: &DetectorRegistry) {
+    let activity = result
+        .last_activity
+        .map(|time| format!(", last active {}", local_datetime(time).replace('T', " ")))
+        .unwrap_or_default();
+    println!(
+        "  {} {} {} ({}, {} files{})",
+        "•".cyan(),
+        registry.display_name(result).white().bold(),
+        result.path.display().to_string().dimmed(),
+        format_size(result.size).cyan(),
+        result.file_count.to_string().yellow(),
+        activity.dimmed()
+    );
+}
+
+/// Target count and size per monorepo, for the results that belong to one
+pub fn print_workspaces(results: &[ScanResult]) {
+    let mut by_root: BTreeMap<&Path, (usize, u64)> = BTreeMap::new();
+    for result in results {
+        if let Some(root) = &result.workspace {
+            let slot = by_root.entry(root).or_default();
+            slot.0 += 1;
+            slot.1 += result.size;
+        }
+    }
+    if by_root.is_empty() {
+        return;
+    }
+
+    println!();
+    println!("By workspace:");
+    for (root, (count, size)) in by_root {
+        let manager = PackageManager::detect(root)
+            .map(|m| format!(" ({})", m))
+            .unwrap_or_default();
+        println!(
+            "  {} {}{}: {} targets, {}",
+            "•".cyan(),
+            root.display(),
+            manager.dimmed(),
+            count.to_string().green(),
+            format_size(size).cyan()
+        );
+    }
+}
+
+/// How much the filter left out and why; each target only when `verbose`,
+/// except protected targets, which are always listed
+pub fn print_left_out(excluded: &[Excluded], verbose: bool, registry: &DetectorRegistry) {
+    let size: u64 = excluded.iter().map(|e| e.target.size).sum();
+    let mut by_kind: BTreeMap<&str, usize> = BTreeMap::new();
+    for e in excluded {
+        *by_kind.entry(e.reason.kind()).or_default() += 1;
+    }
+    let reasons: Vec<String> = by_kind
+        .iter()
+        .map(|(kind, count)| format!("{} {}", count, kind.replace('-', " ")))
+        .collect();
+
+    println!(
+        "Left out: {} directories, {} ({})",
+        excluded.len().to_string().yellow().bold(),
+        format_size(size).cyan(),
+        reasons.join(", ")
+    );
+    // Protected targets are always listed; the project asked for them
+    for e in excluded {
+        if verbose || matches!(e.reason, Exclusion::Kept { .. }) {
+            print_target(&e.target, registry);
+            println!("      {}", e.reason.to_string().dimmed());
+        }
+    }
+}
+
+/// Prompt on `out` and read the answer from stdin
+pub fn ask_to_proceed(out: &mut dyn Write) -> bool {
+    write!(out, "{}", "Do you want to proceed? [y/N]: ".yellow().bold()).unwrap();
+    out.flush().unwrap();
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).unwrap();
+
+    matches!(input.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+/// Ask for `expected` to be typed before something drastic
+pub fn ask_to_type(out: &mut dyn Write, prompt: &str, expected: &str) -> bool {
+    write!(
+        out,
+        "{} ",
+        format!("{} Type '{}' to confirm:", prompt, expected)
+            .red()
+            .bold()
+    )
+    .unwrap();
+    out.flush().unwrap();
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).unwrap();
+
+    input.trim() == expected
+}
+
+fn dir_name(result: &ScanResult) -> &str {
+    result
+        .path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("unknown")
+}
@@ -0,0 +1,484 @@
+//! Moving targets to the trash instead of deleting them.
+//!
+//! Implements the [freedesktop.org Trash specification][spec], so trashed
+//! directories show up in, and can be restored from, desktop file managers:
+//!
+//! * Directories on the same filesystem as the home trash go to
+//!   `$XDG_DATA_HOME/Trash` (default `~/.local/share/Trash`).
+//! * Directories on other mounts go to `$topdir/.Trash/$uid` when the
+//!   administrator created a sticky `$topdir/.Trash`, and to
+//!   `$topdir/.Trash-$uid` otherwise.
+//! * Each item gets a `.trashinfo` file with its original path and deletion
+//!   date, and trashed directories are recorded in `directorysizes`.
+//!
+//! Items are moved with a single `rename`, never copied, so trashing is as
+//! fast as deleting and nothing is half-moved if it fails.
+//!
+//! [spec]: https://specifications.freedesktop.org/trash-spec/latest/
+
+use crate::platform::{device_id, local_datetime};
+use anyhow::{anyhow, bail, Context, Result};
+use std::ffi::{OsStr, OsString};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// Serializes read-modify-write updates of `directorysizes` between threads
+static DIRECTORY_SIZES: Mutex<()> = Mutex::new(());
+
+/// A trash directory containing `files/` and `info/`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trash {
+    dir: PathBuf,
+    /// Mount point for a per-mount trash; `.trashinfo` paths are relative to it
+    topdir: Option<PathBuf>,
+}
+
+/// An item found in a trash directory
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct TrashEntry {
+    /// Name under `files/`
+    pub name: OsString,
+    /// Where the item was before it was trashed
+    pub original_path: PathBuf,
+    /// `DeletionDate` as written in the `.trashinfo` file
+    pub deleted_at: String,
+}
+
+impl Trash {
+    /// Use `dir` as a trash directory; `topdir` is set for per-mount trashes
+    pub fn open(dir: PathBuf, topdir: Option<PathBuf>) -> Self {
+        Self { dir, topdir }
+    }
+
+    /// The user's home trash, `$XDG_DATA_HOME/Trash`
+    pub fn home() -> Result<Self> {
+        let data_home = std::env::var_os("XDG_DATA_HOME")
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share"))
+            })
+            .ok_or_else(|| anyhow!("Neither XDG_DATA_HOME nor HOME is set"))?;
+        Ok(Self::open(data_home.join("Trash"), None))
+    }
+
+    /// The trash a directory at `path` should be moved to
+    ///
+    /// This is the home trash when `path` is on the same filesystem, otherwise
+    /// a trash at the top of the mount that holds `path`.
+    pub fn for_path(path: &Path) -> Result<Self> {
+        let device = device_id(path)?;
+
+        let home = Self::home()?;
+        if device_id(existing_ancestor(&home.dir)?)? == device {
+            return Ok(home);
+        }
+
+        // Walk up to the mount point: the last ancestor on the same device
+        let mut topdir = path;
+        while let Some(parent) = topdir.parent() {
+            if device_id(parent)? != device {
+                break;
+            }
+            topdir = parent;
+        }
+
+        let uid = sys::uid();
+        let shared = topdir.join(".Trash");
+        // Only trust an admin-created .Trash: a real directory with the
+        // sticky bit, never a symlink someone could point elsewhere
+        if sys::is_sticky_dir(&shared) {
+            return Ok(Self::open(
+                shared.join(uid.to_string()),
+                Some(topdir.to_path_buf()),
+            ));
+        }
+        Ok(Self::open(
+            topdir.join(format!(".Trash-{}", uid)),
+            Some(topdir.to_path_buf()),
+        ))
+    }
+
+    /// The trash directory itself
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Move `path` into the trash and return its new location
+    ///
+    /// `size` is recorded in `directorysizes` so file managers do not have to
+    /// walk the trashed directory again.
+    pub fn put(&self, path: &Path, size: u64) -> Result<PathBuf> {
+        let files = self.dir.join("files");
+        let info = self.dir.join("info");
+        for dir in [&self.dir, &files, &info] {
+            sys::create_private_dir(dir)
+                .with_context(|| format!("Cannot create trash directory {}", dir.display()))?;
+        }
+
+        let original = path
+            .canonicalize()
+            .with_context(|| format!("Cannot resolve {}", path.display()))?;
+        let recorded = match &self.topdir {
+            Some(topdir) => original.strip_prefix(topdir).unwrap_or(&original),
+            None => &original,
+        };
+        let contents = format!(
+            "[Trash Info]\nPath={}\nDeletionDate={}\n",
+            encode(recorded),
+            local_datetime(SystemTime::now())
+        );
+
+        // Creating the .trashinfo file exclusively reserves the name, even
+        // against other programs trashing at the same time
+        let base = original
+            .file_name()
+            .ok_or_else(|| anyhow!("Cannot trash {}", original.display()))?;
+        let mut counter = 1;
+        let (name, info_file) = loop {
+            let mut name = base.to_os_string();
+            if counter > 1 {
+                name.push(format!(".{}", counter));
+            }
+            let mut info_name = name.clone();
+            info_name.push(".trashinfo");
+            let info_file = info.join(info_name);
+            match OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&info_file)
+            {
+                Ok(mut file) => {
+                    file.write_all(contents.as_bytes())?;
+                    break (name, info_file);
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => counter += 1,
+                Err(e) => {
+                    return Err(e).with_context(|| format!("Cannot write {}", info_file.display()))
+                }
+            }
+        };
+
+        let destination = files.join(&name);
+        if let Err(e) = fs::rename(&original, &destination) {
+            let _ = fs::remove_file(&info_file);
+            return Err(e).with_context(|| {
+                format!(
+                    "Failed to move {} to {}",
+                    original.display(),
+                    destination.display()
+                )
+            });
+        }
+
+        if destination.is_dir() {
+            let mtime = fs::metadata(&info_file)
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map_or(0, |d| d.as_secs());
+            // The item is already trashed; a stale size cache is harmless
+            let _ = self.update_directory_sizes(Some(format!(
+                "{} {} {}",
+                size,
+                mtime,
+                encode(Path::new(&name))
+            )));
+        }
+
+        Ok(destination)
+    }
+
+    /// Every item in this trash, most recently trashed first
+    pub fn entries(&self) -> Result<Vec<TrashEntry>> {
+        let info = self.dir.join("info");
+        let listing = match fs::read_dir(&info) {
+            Ok(listing) => listing,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e).with_context(|| format!("Cannot list {}", info.display())),
+        };
+
+        let mut entries = Vec::new();
+        for file in listing.filter_map(|e| e.ok()) {
+            let path = file.path();
+            if path.extension() != Some(OsStr::new("trashinfo")) {
+                continue;
+            }
+            let Some(name) = path.file_stem() else {
+                continue;
+            };
+            // Skip unreadable or foreign-format entries rather than failing
+            let Ok(text) = fs::read_to_string(&path) else {
+                continue;
+            };
+            let field = |key: &str| {
+                text.lines()
+                    .find_map(|line| line.strip_prefix(key)?.strip_prefix('='))
+                    .map(str::trim)
+            };
+            let Some(recorded) = field("Path").map(decode) else {
+                continue;
+            };
+            let original_path = match &self.topdir {
+                Some(topdir) if recorded.is_relative() => topdir.join(recorded),
+                _ => recorded,
+            };
+            entries.push(TrashEntry {
+                name: name.to_os_string(),
+                original_path,
+                deleted_at: field("DeletionDate").unwrap_or_default().to_string(),
+            });
+        }
+
+        entries.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at));
+        Ok(entries)
+    }
+
+    /// Move a trashed item back to where it came from
+    pub fn restore(&self, entry: &TrashEntry) -> Result<PathBuf> {
+        let source = self.dir.join("files").join(&entry.name);
+        if entry.original_path.exists() {
+            bail!("{} already exists", entry.original_path.display());
+        }
+        if let Some(parent) = entry.original_path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Cannot create {}", parent.display()))?;
+        }
+        fs::rename(&source, &entry.original_path).with_context(|| {
+            format!(
+                "Failed to move {} back to {}",
+                source.display(),
+                entry.original_path.display()
+            )
+        })?;
+
+        let mut info_name = entry.name.clone();
+        info_name.push(".trashinfo");
+        let _ = fs::remove_file(self.dir.join("info").join(info_name));
+        let _ = self.update_directory_sizes(None);
+        Ok(entry.original_path.clone())
+    }
+
+    /// Drop entries for items no longer in `files/` and append `line`
+    fn update_directory_sizes(&self, line: Option<String>) -> Result<()> {
+        let _guard = DIRECTORY_SIZES.lock().unwrap();
+        let path = self.dir.join("directorysizes");
+        let existing = fs::read_to_string(&path).unwrap_or_default();
+
+        let mut contents = String::new();
+        for old in existing.lines() {
+            let name = old.splitn(3, ' ').nth(2).map(decode);
+            if name.is_some_and(|name| self.dir.join("files").join(name).exists()) {
+                contents.push_str(old);
+                contents.push('\n');
+            }
+        }
+        if let Some(line) = line {
+            contents.push_str(&line);
+            contents.push('\n');
+        }
+
+        // Replace atomically so readers never see a partial file
+        let temp = self
+            .dir
+            .join(format!("directorysizes.{}.tmp", std::process::id()));
+        fs::write(&temp, contents)?;
+        fs::rename(&temp, &path)?;
+        Ok(())
+    }
+}
+
+/// `path`, or its closest ancestor that exists
+fn existing_ancestor(path: &Path) -> Result<&Path> {
+    path.ancestors()
+        .find(|p| p.exists())
+        .ok_or_else(|| anyhow!("No existing ancestor of {}", path.display()))
+}
+
+/// Percent-encode a path as the spec requires, keeping `/` separators
+fn encode(path: &Path) -> String {
+    let mut out = String::new();
+    for &byte in sys::bytes(path).iter() {
+        if byte.is_ascii_alphanumeric() || b"-_.~!*'()/".contains(&byte) {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    out
+}
+
+/// Reverse of [`encode`]
+fn decode(text: &str) -> PathBuf {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    sys::path_from_bytes(out)
+}
+
+#[cfg(unix)]
+mod sys {
+    use anyhow::Result;
+    use std::fs;
+    use std::os::unix::ffi::{OsStrExt, OsStringExt};
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+    use std::path::{Path, PathBuf};
+
+    pub fn uid() -> u32 {
+        // SAFETY: getuid has no preconditions and cannot fail
+        unsafe { libc::getuid() }
+    }
+
+    pub fn is_sticky_dir(path: &Path) -> bool {
+        fs::symlink_metadata(path).is_ok_and(|m| m.is_dir() && m.permissions().mode() & 0o1000 != 0)
+    }
+
+    /// Create `path` readable only by us, or check an existing one is ours
+    pub fn create_private_dir(path: &Path) -> Result<()> {
+        match fs::symlink_metadata(path) {
+            Ok(meta) if meta.is_dir() && meta.uid() == uid() => Ok(()),
+            Ok(_) => anyhow::bail!("not a directory owned by the current user"),
+            Err(_) => Ok(fs::DirBuilder::new()
+                .mode(0o700)
+                .recursive(true)
+                .create(path)?),
+        }
+    }
+
+    pub fn bytes(path: &Path) -> Vec<u8> {
+        path.as_os_str().as_bytes().to_vec()
+    }
+
+    pub fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+        std::ffi::OsString::from_vec(bytes).into()
+    }
+}
+
+#[cfg(not(unix))]
+mod sys {
+    use anyhow::Result;
+    use std::path::{Path, PathBuf};
+
+    pub fn uid() -> u32 {
+        0
+    }
+
+    pub fn is_sticky_dir(_path: &Path) -> bool {
+        false
+    }
+
+    pub fn create_private_dir(path: &Path) -> Result<()> {
+        Ok(std::fs::create_dir_all(path)?)
+    }
+
+    pub fn bytes(path: &Path) -> Vec<u8> {
+        path.to_string_lossy().into_owned().into_bytes()
+    }
+
+    pub fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+        String::from_utf8_lossy(&bytes).into_owned().into()
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_put_and_restore() {
+        let temp_dir = TempDir::new().unwrap();
+        let trash = Trash::open(temp_dir.path().join("Trash"), None);
+        let project = temp_dir.path().join("my app");
+
+        for round in 0..2 {
+            let target = project.join("node_modules");
+            fs::create_dir_all(&target).unwrap();
+            fs::write(target.join("index.js"), "x").unwrap();
+
+            let location = trash.put(&target, 1).unwrap();
+            assert!(!target.exists());
+            assert!(location.join("index.js").exists());
+            let expected = if round == 0 {
+                "node_modules"
+            } else {
+                "node_modules.2"
+            };
+            assert_eq!(location.file_name().unwrap(), expected);
+        }
+
+        let info = fs::read_to_string(trash.dir().join("info/node_modules.trashinfo")).unwrap();
+        assert!(info.starts_with("[Trash Info]\nPath=/"));
+        assert!(info.contains("my%20app/node_modules\n"));
+        assert!(info.contains("DeletionDate=20"));
+        let sizes = fs::read_to_string(trash.dir().join("directorysizes")).unwrap();
+        assert_eq!(sizes.lines().count(), 2);
+        assert!(sizes
+            .lines()
+            .any(|l| l.starts_with("1 ") && l.ends_with(" node_modules.2")));
+
+        let entries = trash.entries().unwrap();
+        assert_eq!(entries.len(), 2);
+        let entry = entries
+            .iter()
+            .find(|e| e.name == OsStr::new("node_modules"))
+            .unwrap();
+        assert_eq!(
+            entry.original_path,
+            project.canonicalize().unwrap().join("node_modules")
+        );
+
+        trash.restore(entry).unwrap();
+        assert!(project.join("node_modules/index.js").exists());
+        assert_eq!(trash.entries().unwrap().len(), 1);
+        let sizes = fs::read_to_string(trash.dir().join("directorysizes")).unwrap();
+        assert_eq!(sizes.lines().count(), 1);
+
+        // Restoring over an existing directory is refused
+        let other = trash.entries().unwrap().remove(0);
+        assert!(trash.restore(&other).is_err());
+    }
+
+    #[test]
+    fn test_topdir_paths_are_relative() {
+        let temp_dir = TempDir::new().unwrap();
+        let topdir = temp_dir.path().canonicalize().unwrap();
+        let trash = Trash::open(topdir.join(".Trash-1000"), Some(topdir.clone()));
+        let target = topdir.join("app/target");
+        fs::create_dir_all(&target).unwrap();
+
+        trash.put(&target, 0).unwrap();
+        let info = fs::read_to_string(trash.dir().join("info/target.trashinfo")).unwrap();
+        assert!(info.contains("\nPath=app/target\n"));
+        assert_eq!(trash.entries().unwrap()[0].original_path, target);
+    }
+
+    #[test]
+    fn test_encode_round_trip() {
+        use std::os::unix::ffi::OsStrExt;
+        let path = Path::new(std::ffi::OsStr::from_bytes(b"/a b/%/caf\xc3\xa9/\xff"));
+        let encoded = encode(path);
+        assert_eq!(encoded, "/a%20b/%25/caf%C3%A9/%FF");
+        assert_eq!(decode(&encoded), path);
+    }
+}
@@ -0,0 +1,626 @@
+//! Full-screen picker for `--interactive`: choose which targets to clean.
+
+use anyhow::{Context, Result};
+use clean_files::detectors::DetectorRegistry;
+use clean_files::utils::{format_age, format_size};
+use clean_files::ScanResult;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Cell, Clear, Paragraph, Row, Table, TableState};
+use ratatui::{DefaultTerminal, Frame};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Column the list is sorted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SortKey {
+    Size,
+    Files,
+    Type,
+    Age,
+    Path,
+}
+
+impl SortKey {
+    fn next(self) -> Self {
+        match self {
+            SortKey::Size => SortKey::Files,
+            SortKey::Files => SortKey::Type,
+            SortKey::Type => SortKey::Age,
+            SortKey::Age => SortKey::Path,
+            SortKey::Path => SortKey::Size,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            SortKey::Size => "size",
+            SortKey::Files => "files",
+            SortKey::Type => "type",
+            SortKey::Age => "age",
+            SortKey::Path => "path",
+        }
+    }
+
+    /// Numbers read best largest first, names alphabetically
+    fn descending_by_default(self) -> bool {
+        matches!(self, SortKey::Size | SortKey::Files | SortKey::Age)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Browse,
+    Filter,
+    Confirm,
+}
+
+struct Item {
+    result: ScanResult,
+    project: PathBuf,
+    kind: String,
+    age: Duration,
+    selected: bool,
+}
+
+/// One row of the list: a project heading or one of its targets
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Entry {
+    Project(PathBuf),
+    Target(usize),
+}
+
+/// State of the picker, independent of the terminal
+struct Picker {
+    items: Vec<Item>,
+    lines: Vec<Entry>,
+    sort: SortKey,
+    descending: bool,
+    filter: String,
+    mode: Mode,
+    table: TableState,
+}
+
+impl Picker {
+    fn new(results: Vec<ScanResult>, registry: &DetectorRegistry, now: SystemTime) -> Self {
+        let items = results
+            .into_iter()
+            .map(|result| Item {
+                project: result
+                    .path
+                    .parent()
+                    .map(Path::to_path_buf)
+                    .unwrap_or_default(),
+                kind: registry.display_name(&result).to_string(),
+                age: result
+                    .last_activity
+                    .and_then(|time| now.duration_since(time).ok())
+                    .unwrap_or_default(),
+                result,
+                selected: false,
+            })
+            .collect();
+        let mut picker = Self {
+            items,
+            lines: Vec::new(),
+            sort: SortKey::Size,
+            descending: true,
+            filter: String::new(),
+            mode: Mode::Browse,
+            table: TableState::default(),
+        };
+        picker.refresh();
+        picker
+    }
+
+    fn compare(&self, a: usize, b: usize) -> Ordering {
+        let (a, b) = (&self.items[a], &self.items[b]);
+        let order = match self.sort {
+            SortKey::Size => a.result.size.cmp(&b.result.size),
+            SortKey::Files => a.result.file_count.cmp(&b.result.file_count),
+            SortKey::Type => a.kind.cmp(&b.kind),
+            SortKey::Age => a.age.cmp(&b.age),
+            SortKey::Path => a.result.path.cmp(&b.result.path),
+        };
+        if self.descending {
+            order.reverse()
+        } else {
+            order
+        }
+    }
+
+    fn matches(&self, item: &Item) -> bool {
+        let filter = self.filter.to_lowercase();
+        filter.is_empty()
+            || item.kind.to_lowercase().contains(&filter)
+            || item
+                .result
+                .path
+                .to_string_lossy()
+                .to_lowercase()
+                .contains(&filter)
+    }
+
+    /// Rebuild the visible lines after the sort or filter changed
+    fn refresh(&mut self) {
+        let mut groups: BTreeMap<&Path, Vec<usize>> = BTreeMap::new();
+        for (index, item) in self.items.iter().enumerate() {
+            if self.matches(item) {
+                groups.entry(&item.project).or_default().push(index);
+            }
+        }
+
+        let mut groups: Vec<(PathBuf, Vec<usize>)> = groups
+            .into_iter()
+            .map(|(project, mut members)| {
+                members.sort_by(|&a, &b| self.compare(a, b));
+                (project.to_path_buf(), members)
+            })
+            .collect();
+
+        // Projects are ordered by their totals for size and files, and by
+        // their first target otherwise
+        let total = |members: &[usize], of: fn(&ScanResult) -> u64| -> u64 {
+            members.iter().map(|&i| of(&self.items[i].result)).sum()
+        };
+        groups.sort_by(|(pa, a), (pb, b)| {
+            let order = match self.sort {
+                SortKey::Size => total(a, |r| r.size).cmp(&total(b, |r| r.size)),
+                SortKey::Files => {
+                    total(a, |r| r.file_count as u64).cmp(&total(b, |r| r.file_count as u64))
+                }
+                SortKey::Path => pa.cmp(pb),
+                _ => return self.compare(a[0], b[0]),
+            };
+            if self.descending {
+                order.reverse()
+            } else {
+                order
+            }
+        });
+
+        self.lines = groups
+            .into_iter()
+            .flat_map(|(project, members)| {
+                std::iter::once(Entry::Project(project))
+                    .chain(members.into_iter().map(Entry::Target))
+            })
+            .collect();
+
+        let cursor = self.table.selected().unwrap_or(0);
+        self.table.select(if self.lines.is_empty() {
+            None
+        } else {
+            Some(cursor.min(self.lines.len() - 1))
+        });
+    }
+
+    /// Targets of a line: itself, or every visible target of a project
+    fn targets_of(&self, line: usize) -> Vec<usize> {
+        match &self.lines[line] {
+            Entry::Target(index) => vec![*index],
+            Entry::Project(_) => self.lines[line + 1..]
+                .iter()
+                .map_while(|line| match line {
+                    Entry::Target(index) => Some(*index),
+                    Entry::Project(_) => None,
+                })
+                .collect(),
+        }
+    }
+
+    /// Toggle the target under the cursor, or a whole project
+    fn toggle(&mut self) {
+        let Some(line) = self.table.selected() else {
+            return;
+        };
+        let targets = self.targets_of(line);
+        let select = !targets.iter().all(|&i| self.items[i].selected);
+        for i in targets {
+            self.items[i].selected = select;
+        }
+    }
+
+    /// Select every visible target, or clear them if all are selected
+    fn toggle_all(&mut self) {
+        let visible: Vec<usize> = self
+            .lines
+            .iter()
+            .filter_map(|line| match line {
+                Entry::Target(index) => Some(*index),
+                Entry::Project(_) => None,
+            })
+            .collect();
+        let select = !visible.iter().all(|&i| self.items[i].selected);
+        for i in visible {
+            self.items[i].selected = select;
+        }
+    }
+
+    fn move_by(&mut self, delta: isize) {
+        if self.lines.is_empty() {
+            return;
+        }
+        let current = self.table.selected().unwrap_or(0) as isize;
+        let last = self.lines.len() as isize - 1;
+        self.table
+            .select(Some((current + delta).clamp(0, last) as usize));
+    }
+
+    fn set_sort(&mut self, sort: SortKey) {
+        self.sort = sort;
+        self.descending = sort.descending_by_default();
+        self.refresh();
+    }
+
+    /// Number and total size of the selected targets, hidden ones included
+    fn selected_total(&self) -> (usize, u64) {
+        self.items
+            .iter()
+            .filter(|item| item.selected)
+            .fold((0, 0), |(count, size), item| {
+                (count + 1, size + item.result.size)
+            })
+    }
+
+    fn into_selected(self) -> Vec<ScanResult> {
+        self.items
+            .into_iter()
+            .filter(|item| item.selected)
+            .map(|item| item.result)
+            .collect()
+    }
+
+    /// Apply one key press; `Some` ends the picker with the confirmed
+    /// choice (`Some(true)`) or a cancel (`Some(false)`)
+    fn handle_key(&mut self, key: KeyEvent) -> Option<bool> {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return Some(false);
+        }
+
+        match self.mode {
+            Mode::Filter => match key.code {
+                KeyCode::Enter => self.mode = Mode::Browse,
+                KeyCode::Esc => {
+                    self.filter.clear();
+                    self.mode = Mode::Browse;
+                    self.refresh();
+                }
+                KeyCode::Backspace => {
+                    self.filter.pop();
+                    self.refresh();
+                }
+                KeyCode::Char(c) => {
+                    self.filter.push(c);
+                    self.refresh();
+                }
+                _ => {}
+            },
+            Mode::Confirm => match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') => return Some(true),
+                _ => self.mode = Mode::Browse,
+            },
+            Mode::Browse => match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Some(false),
+                KeyCode::Up | KeyCode::Char('k') => self.move_by(-1),
+                KeyCode::Down | KeyCode::Char('j') => self.move_by(1),
+                KeyCode::PageUp => self.move_by(-10),
+                KeyCode::PageDown => self.move_by(10),
+                KeyCode::Home | KeyCode::Char('g') => self.move_by(isize::MIN / 2),
+                KeyCode::End | KeyCode::Char('G') => self.move_by(isize::MAX / 2),
+                KeyCode::Char(' ') => {
+                    self.toggle();
+                    self.move_by(1);
+                }
+                KeyCode::Char('a') => self.toggle_all(),
+                KeyCode::Char('s') => self.set_sort(self.sort.next()),
+                KeyCode::Char('r') => {
+                    self.descending = !self.descending;
+                    self.refresh();
+                }
+                KeyCode::Char('/') => self.mode = Mode::Filter,
+                KeyCode::Enter if self.selected_total().0 > 0 => self.mode = Mode::Confirm,
+                _ => {}
+            },
+        }
+        None
+    }
+
+    fn render(&mut self, frame: &mut Frame) {
+        let [list, status] =
+            Layout::vertical([Constraint::Min(3), Constraint::Length(2)]).areas(frame.area());
+
+        let rows: Vec<Row> = self
+            .lines
+            .iter()
+            .map(|line| match line {
+                Entry::Project(project) => Row::new(vec![
+                    Cell::from(""),
+                    Cell::from(project.display().to_string()),
+                ])
+                .style(Style::new().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+                Entry::Target(index) => {
+                    let item = &self.items[*index];
+                    let name = item
+                        .result
+                        .path
+                        .file_name()
+                        .map(|n| n.to_string_lossy().into_owned())
+                        .unwrap_or_default();
+                    let age = match item.result.last_activity {
+                        Some(_) => format_age(item.age),
+                        None => "-".to_string(),
+                    };
+                    Row::new(vec![
+                        Cell::from(if item.selected { "[x]" } else { "[ ]" }),
+                        Cell::from(format!("  {}", name)),
+                        Cell::from(format_size(item.result.size)),
+                        Cell::from(item.result.file_count.to_string()),
+                        Cell::from(item.kind.clone()),
+                        Cell::from(age),
+                    ])
+                    .style(if item.selected {
+                        Style::new().fg(Color::Green)
+                    } else {
+                        Style::new()
+                    })
+                }
+            })
+            .collect();
+
+        let arrow = if self.descending { "↓" } else { "↑" };
+        let heading = |key: SortKey, title: &str| {
+            if key == self.sort {
+                format!("{} {}", title, arrow)
+            } else {
+                title.to_string()
+            }
+        };
+        let header = Row::new(vec![
+            String::new(),
+            heading(SortKey::Path, "Project / target"),
+            heading(SortKey::Size, "Size"),
+            heading(SortKey::Files, "Files"),
+            heading(SortKey::Type, "Type"),
+            heading(SortKey::Age, "Age"),
+        ])
+        .style(Style::new().add_modifier(Modifier::BOLD | Modifier::UNDERLINED));
+
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(3),
+                Constraint::Fill(1),
+                Constraint::Length(11),
+                Constraint::Length(8),
+                Constraint::Length(20),
+                Constraint::Length(12),
+            ],
+        )
+        .header(header)
+        .block(Block::bordered().title(format!(
+            " clean-files: choose targets to delete (by {}) ",
+            self.sort.name()
+        )))
+        .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(table, list, &mut self.table);
+
+        let (count, size) = self.selected_total();
+        let filter = match self.mode {
+            Mode::Filter => format!("  Filter: {}▏", self.filter),
+            _ if !self.filter.is_empty() => format!("  Filter: {}", self.filter),
+            _ => String::new(),
+        };
+        let help = "space select · a all · s sort · r reverse · / filter · enter delete · q quit";
+        frame.render_widget(
+            Paragraph::new(vec![
+                Line::from(format!(
+                    "Selected: {} directories, {} to free{}",
+                    count,
+                    format_size(size),
+                    filter
+                ))
+                .bold(),
+                Line::from(help).dim(),
+            ]),
+            status,
+        );
+
+        if self.mode == Mode::Confirm {
+            let area = centered(frame.area(), 60, 5);
+            frame.render_widget(Clear, area);
+            frame.render_widget(
+                Paragraph::new(vec![
+                    Line::from(format!(
+                        "Delete {} directories ({})?",
+                        count,
+                        format_size(size)
+                    )),
+                    Line::from(""),
+                    Line::from("y to confirm, any other key to go back").dim(),
+                ])
+                .block(Block::bordered().title(" Confirm ").yellow()),
+                area,
+            );
+        }
+    }
+}
+
+/// A `width` x `height` rectangle in the middle of `area`
+fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+/// Let the user pick targets in a full-screen list
+///
+/// Returns the confirmed targets, or `None` if the user quit.
+pub fn pick(
+    results: Vec<ScanResult>,
+    registry: &DetectorRegistry,
+) -> Result<Option<Vec<ScanResult>>> {
+    let mut picker = Picker::new(results, registry, SystemTime::now());
+    let mut terminal = ratatui::try_init().context("Failed to start the interactive view")?;
+    let outcome = run(&mut terminal, &mut picker);
+    ratatui::restore();
+
+    Ok(match outcome? {
+        true => Some(picker.into_selected()),
+        false => None,
+    })
+}
+
+fn run(terminal: &mut DefaultTerminal, picker: &mut Picker) -> Result<bool> {
+    loop {
+        terminal.draw(|frame| picker.render(frame))?;
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press {
+                if let Some(confirmed) = picker.handle_key(key) {
+                    return Ok(confirmed);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clean_files::CleanTarget;
+
+    fn picker() -> Picker {
+        let now = SystemTime::now();
+        let target = |path: &str, target: CleanTarget, size: u64, days: u64| {
+            ScanResult::new(PathBuf::from(path), target)
+                .with_size(size, size as usize / 10)
+                .with_last_activity(now - Duration::from_secs(days * 86_400))
+        };
+        Picker::new(
+            vec![
+                target("/p/web/node_modules", CleanTarget::NodeModules, 300, 2),
+                target("/p/api/target", CleanTarget::RustTarget, 500, 40),
+                target("/p/api/node_modules", CleanTarget::NodeModules, 100, 90),
+                target("/p/tool/__pycache__", CleanTarget::PythonCache, 10, 5),
+            ],
+            &DetectorRegistry::builtin(),
+            now,
+        )
+    }
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn paths(picker: &Picker) -> Vec<String> {
+        picker
+            .lines
+            .iter()
+            .map(|line| match line {
+                Entry::Project(path) => path.display().to_string(),
+                Entry::Target(i) => picker.items[*i].result.path.display().to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_grouping_and_sorting() {
+        let mut picker = picker();
+        // Projects by total size, targets by size within them
+        assert_eq!(
+            paths(&picker),
+            [
+                "/p/api",
+                "/p/api/target",
+                "/p/api/node_modules",
+                "/p/web",
+                "/p/web/node_modules",
+                "/p/tool",
+                "/p/tool/__pycache__"
+            ]
+        );
+
+        picker.set_sort(SortKey::Age);
+        assert_eq!(paths(&picker)[1], "/p/api/node_modules");
+        picker.handle_key(key(KeyCode::Char('r')));
+        assert_eq!(paths(&picker)[0], "/p/web");
+    }
+
+    #[test]
+    fn test_selection_and_confirmation() {
+        let mut picker = picker();
+
+        // Enter does nothing until something is selected
+        assert_eq!(picker.handle_key(key(KeyCode::Enter)), None);
+        assert_eq!(picker.mode, Mode::Browse);
+
+        // Space on a project selects all of its targets
+        picker.handle_key(key(KeyCode::Char(' ')));
+        assert_eq!(picker.selected_total(), (2, 600));
+        // ...and on a target toggles just that one
+        picker.handle_key(key(KeyCode::Char(' ')));
+        assert_eq!(picker.selected_total(), (1, 100));
+
+        picker.handle_key(key(KeyCode::Enter));
+        assert_eq!(picker.mode, Mode::Confirm);
+        picker.handle_key(key(KeyCode::Char('n')));
+        assert_eq!(picker.mode, Mode::Browse);
+        picker.handle_key(key(KeyCode::Enter));
+        assert_eq!(picker.handle_key(key(KeyCode::Char('y'))), Some(true));
+
+        let chosen = picker.into_selected();
+        assert_eq!(chosen.len(), 1);
+        assert_eq!(chosen[0].path, PathBuf::from("/p/api/node_modules"));
+    }
+
+    #[test]
+    fn test_filter() {
+        let mut picker = picker();
+        picker.handle_key(key(KeyCode::Char('/')));
+        for c in "node".chars() {
+            picker.handle_key(key(KeyCode::Char(c)));
+        }
+        picker.handle_key(key(KeyCode::Enter));
+        assert_eq!(picker.lines.len(), 4);
+
+        // Select all applies to what is visible; hidden picks still count
+        picker.handle_key(key(KeyCode::Char('a')));
+        assert_eq!(picker.selected_total(), (2, 400));
+        picker.handle_key(key(KeyCode::Char('/')));
+        picker.handle_key(key(KeyCode::Esc));
+        assert_eq!(picker.lines.len(), 7);
+        assert_eq!(picker.selected_total(), (2, 400));
+
+        assert_eq!(picker.handle_key(key(KeyCode::Char('q'))), Some(false));
+    }
+
+    #[test]
+    fn test_render() {
+        let mut picker = picker();
+        picker.handle_key(key(KeyCode::Char(' ')));
+        picker.handle_key(key(KeyCode::Enter));
+
+        let mut terminal =
+            ratatui::Terminal::new(ratatui::backend::TestBackend::new(100, 16)).unwrap();
+        terminal.draw(|frame| picker.render(frame)).unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains("/p/api"));
+        assert!(screen.contains("Selected: 2 directories, 600 B to free"));
+        assert!(screen.contains("Delete 2 directories (600 B)?"));
+    }
+}
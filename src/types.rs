@@ -1,78 +1,276 @@
+//! Core data types shared by the scanner and the cleaner.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
 use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::SystemTime;
 
 /// Types of directories that can be cleaned
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum CleanTarget {
+    /// `node_modules` next to a `package.json`
     NodeModules,
+    /// Build caches of JS frameworks such as `.next` and `.turbo`
+    FrameworkCache,
+    /// Cargo `target` next to a `Cargo.toml`
     RustTarget,
+    /// `__pycache__` and Python tool caches
     PythonCache,
+    /// Python virtual environments with a project to rebuild them from
+    PythonVenv,
+    /// JVM build output and project caches: Maven, Gradle, Kotlin, sbt and
+    /// Clojure
     JavaTarget,
+    /// .NET `bin` and `obj` next to a project file
+    DotnetBuild,
+    /// CMake, Meson, Autotools and Bazel build trees, found by their contents
+    NativeBuild,
+    /// Directories found by detectors registered outside this crate
+    Custom,
+    /// Every supported type; only meaningful as a filter
     All,
 }
 
 impl CleanTarget {
     /// Returns all available clean target types (excluding All)
     /// This is useful for programmatic iteration and testing
-    #[allow(dead_code)]
     pub fn all_targets() -> Vec<CleanTarget> {
         vec![
             CleanTarget::NodeModules,
+            CleanTarget::FrameworkCache,
             CleanTarget::RustTarget,
             CleanTarget::PythonCache,
+            CleanTarget::PythonVenv,
             CleanTarget::JavaTarget,
+            CleanTarget::DotnetBuild,
+            CleanTarget::NativeBuild,
+            CleanTarget::Custom,
         ]
     }
 
+    /// Human-readable name used in summaries
     pub fn name(&self) -> &str {
         match self {
             CleanTarget::NodeModules => "node_modules",
+            CleanTarget::FrameworkCache => "js framework cache",
             CleanTarget::RustTarget => "rust target",
             CleanTarget::PythonCache => "python __pycache__",
+            CleanTarget::PythonVenv => "python venv",
             CleanTarget::JavaTarget => "java target/build",
+            CleanTarget::DotnetBuild => ".net bin/obj",
+            CleanTarget::NativeBuild => "c/c++ build",
+            CleanTarget::Custom => "custom",
+            CleanTarget::All => "all",
+        }
+    }
+
+    /// Short identifier used on the command line and in configuration files
+    pub fn key(&self) -> &'static str {
+        match self {
+            CleanTarget::NodeModules => "node",
+            CleanTarget::FrameworkCache => "framework",
+            CleanTarget::RustTarget => "rust",
+            CleanTarget::PythonCache => "python",
+            CleanTarget::PythonVenv => "venv",
+            CleanTarget::JavaTarget => "java",
+            CleanTarget::DotnetBuild => "dotnet",
+            CleanTarget::NativeBuild => "native",
+            CleanTarget::Custom => "custom",
             CleanTarget::All => "all",
         }
     }
 
+    /// Whether a directory of type `other` passes this target filter
     pub fn should_clean(&self, other: &CleanTarget) -> bool {
         self == &CleanTarget::All || self == other
     }
 }
 
+impl fmt::Display for CleanTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.key())
+    }
+}
+
+impl FromStr for CleanTarget {
+    type Err = String;
+
+    /// Parse a [`key`](CleanTarget::key)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        CleanTarget::all_targets()
+            .into_iter()
+            .chain([CleanTarget::All])
+            .find(|t| t.key() == s)
+            .ok_or_else(|| {
+                let keys: Vec<_> = CleanTarget::all_targets().iter().map(|t| t.key()).collect();
+                format!(
+                    "unknown target type '{}' (expected one of: {})",
+                    s,
+                    keys.join(", ")
+                )
+            })
+    }
+}
+
+/// Serialized as its [`key`](CleanTarget::key)
+impl Serialize for CleanTarget {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.key())
+    }
+}
+
+impl<'de> Deserialize<'de> for CleanTarget {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
 /// Result of scanning a directory
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct ScanResult {
+    /// Directory that would be removed
+    #[serde(with = "crate::report::os_path")]
     pub path: PathBuf,
+    /// What kind of artifact directory this is
     pub target_type: CleanTarget,
+    /// Id of the [`TargetDetector`](crate::detectors::TargetDetector) that
+    /// matched, used to re-verify the directory before deletion
+    pub detector: Option<String>,
+    /// Total size of all regular files in bytes
     pub size: u64,
+    /// Number of regular files inside the directory
     pub file_count: usize,
+    /// Newest mtime inside the directory (and, if the scanner was asked to,
+    /// of the project's sources); filled in together with the size
+    #[serde(default, with = "crate::report::unix_time")]
+    pub last_activity: Option<SystemTime>,
+    /// The [keep marker](crate::keep) protecting this directory, if its
+    /// project declares one; such targets are found but never cleaned
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kept_by: Option<String>,
+    /// Root of the monorepo (such as a pnpm or Yarn workspace) the target
+    /// belongs to
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "crate::report::os_path::option"
+    )]
+    pub workspace: Option<PathBuf>,
+    /// Project that declared this directory when it does not sit next to
+    /// it, such as the Cargo package building into a shared target dir
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "crate::report::os_path::option"
+    )]
+    pub project: Option<PathBuf>,
 }
 
 impl ScanResult {
+    /// Create an unsized result; the scanner fills in size and file count
     pub fn new(path: PathBuf, target_type: CleanTarget) -> Self {
         Self {
             path,
             target_type,
+            detector: None,
             size: 0,
             file_count: 0,
+            last_activity: None,
+            kept_by: None,
+            workspace: None,
+            project: None,
         }
     }
+
+    /// Record which detector matched this directory
+    pub fn with_detector(mut self, id: impl Into<String>) -> Self {
+        self.detector = Some(id.into());
+        self
+    }
+
+    /// Set the measured size and file count
+    pub fn with_size(mut self, size: u64, file_count: usize) -> Self {
+        self.size = size;
+        self.file_count = file_count;
+        self
+    }
+
+    /// Set the time of the newest change inside the directory
+    pub fn with_last_activity(mut self, time: SystemTime) -> Self {
+        self.last_activity = Some(time);
+        self
+    }
+
+    /// Record the keep marker that protects this directory
+    pub fn with_kept_by(mut self, marker: impl Into<String>) -> Self {
+        self.kept_by = Some(marker.into());
+        self
+    }
+
+    /// Record the monorepo this directory belongs to
+    pub fn with_workspace(mut self, root: PathBuf) -> Self {
+        self.workspace = Some(root);
+        self
+    }
+
+    /// Record the project that declared this directory
+    pub fn with_project(mut self, dir: PathBuf) -> Self {
+        self.project = Some(dir);
+        self
+    }
 }
 
 /// Statistics for the cleanup operation
-#[derive(Debug, Default)]
+///
+/// Missing fields deserialize as zero, so reports from older versions load.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+#[non_exhaustive]
 pub struct CleanStats {
+    /// Bytes freed (or that would be freed in a dry run)
     pub total_size: u64,
+    /// Files removed
     pub total_files: usize,
+    /// Directories removed
     pub total_dirs: usize,
+    /// `node_modules` directories removed
     pub node_modules: usize,
+    /// JS framework caches removed
+    pub framework_caches: usize,
+    /// Rust `target` directories removed
     pub rust_targets: usize,
+    /// Python cache directories removed
     pub python_caches: usize,
+    /// Python virtual environments removed
+    pub python_venvs: usize,
+    /// JVM build directories and project caches removed
     pub java_targets: usize,
+    /// .NET `bin` and `obj` directories removed
+    pub dotnet_targets: usize,
+    /// C and C++ build trees removed
+    pub native_builds: usize,
+    /// Directories from custom detectors removed
+    pub custom_targets: usize,
+    /// Directories whose deletion failed
     pub failed_dirs: usize,
+    /// Directories skipped by verification or interruption
     pub skipped_dirs: usize,
+    /// Directories moved to the trash rather than deleted; they are also
+    /// counted above, but their space is only freed when the trash is emptied
+    pub trashed_dirs: usize,
+    /// Targets left out by the filter; not counted above
+    pub excluded_dirs: usize,
+    /// Total size of the targets left out by the filter
+    pub excluded_size: u64,
 }
 
 impl CleanStats {
+    /// Record a successfully processed directory
     pub fn add_result(&mut self, result: &ScanResult) {
         self.total_size += result.size;
         self.total_files += result.file_count;
@@ -80,17 +278,57 @@ impl CleanStats {
 
         match result.target_type {
             CleanTarget::NodeModules => self.node_modules += 1,
+            CleanTarget::FrameworkCache => self.framework_caches += 1,
             CleanTarget::RustTarget => self.rust_targets += 1,
             CleanTarget::PythonCache => self.python_caches += 1,
+            CleanTarget::PythonVenv => self.python_venvs += 1,
             CleanTarget::JavaTarget => self.java_targets += 1,
+            CleanTarget::DotnetBuild => self.dotnet_targets += 1,
+            CleanTarget::NativeBuild => self.native_builds += 1,
+            CleanTarget::Custom => self.custom_targets += 1,
             CleanTarget::All => {}
         }
     }
 
+    /// Add another set of statistics to this one
+    pub fn merge(&mut self, other: &CleanStats) {
+        self.total_size += other.total_size;
+        self.total_files += other.total_files;
+        self.total_dirs += other.total_dirs;
+        self.node_modules += other.node_modules;
+        self.framework_caches += other.framework_caches;
+        self.rust_targets += other.rust_targets;
+        self.python_caches += other.python_caches;
+        self.python_venvs += other.python_venvs;
+        self.java_targets += other.java_targets;
+        self.dotnet_targets += other.dotnet_targets;
+        self.native_builds += other.native_builds;
+        self.custom_targets += other.custom_targets;
+        self.failed_dirs += other.failed_dirs;
+        self.skipped_dirs += other.skipped_dirs;
+        self.trashed_dirs += other.trashed_dirs;
+        self.excluded_dirs += other.excluded_dirs;
+        self.excluded_size += other.excluded_size;
+    }
+
+    /// Record a directory that was moved to the trash
+    pub fn add_trashed(&mut self, result: &ScanResult) {
+        self.add_result(result);
+        self.trashed_dirs += 1;
+    }
+
+    /// Record a directory that the filter left out
+    pub fn add_excluded(&mut self, result: &ScanResult) {
+        self.excluded_dirs += 1;
+        self.excluded_size += result.size;
+    }
+
+    /// Record a directory that could not be deleted
     pub fn add_failed(&mut self) {
         self.failed_dirs += 1;
     }
 
+    /// Record a directory that was left in place
     pub fn add_skipped(&mut self) {
         self.skipped_dirs += 1;
     }
@@ -103,11 +341,16 @@ mod tests {
     #[test]
     fn test_clean_target_all_targets() {
         let targets = CleanTarget::all_targets();
-        assert_eq!(targets.len(), 4);
+        assert_eq!(targets.len(), 9);
         assert!(targets.contains(&CleanTarget::NodeModules));
+        assert!(targets.contains(&CleanTarget::FrameworkCache));
         assert!(targets.contains(&CleanTarget::RustTarget));
         assert!(targets.contains(&CleanTarget::PythonCache));
+        assert!(targets.contains(&CleanTarget::PythonVenv));
         assert!(targets.contains(&CleanTarget::JavaTarget));
+        assert!(targets.contains(&CleanTarget::DotnetBuild));
+        assert!(targets.contains(&CleanTarget::NativeBuild));
+        assert!(targets.contains(&CleanTarget::Custom));
     }
 
     #[test]
@@ -117,14 +360,27 @@ mod tests {
         assert!(!CleanTarget::NodeModules.should_clean(&CleanTarget::RustTarget));
     }
 
+    #[test]
+    fn test_clean_target_keys_round_trip() {
+        for target in CleanTarget::all_targets() {
+            assert_eq!(target.key().parse::<CleanTarget>(), Ok(target));
+        }
+        assert!("cobol".parse::<CleanTarget>().is_err());
+    }
+
     #[test]
     fn test_clean_stats() {
         let mut stats = CleanStats::default();
         let result = ScanResult {
             path: PathBuf::from("/test"),
             target_type: CleanTarget::NodeModules,
+            detector: None,
             size: 1024,
             file_count: 10,
+            last_activity: None,
+            kept_by: None,
+            workspace: None,
+            project: None,
         };
 
         stats.add_result(&result);
@@ -133,5 +389,19 @@ mod tests {
         assert_eq!(stats.total_files, 10);
         assert_eq!(stats.total_dirs, 1);
         assert_eq!(stats.node_modules, 1);
+
+        let mut total = CleanStats::default();
+        total.add_failed();
+        total.merge(&stats);
+        total.merge(&stats);
+        total.add_trashed(&result);
+        total.add_excluded(&result);
+        assert_eq!(total.total_size, 3072);
+        assert_eq!(total.total_dirs, 3);
+        assert_eq!(total.excluded_dirs, 1);
+        assert_eq!(total.excluded_size, 1024);
+        assert_eq!(total.trashed_dirs, 1);
+        assert_eq!(total.node_modules, 3);
+        assert_eq!(total.failed_dirs, 1);
     }
 }
@@ -1,3 +1,7 @@
+//! Small formatting and parsing helpers.
+
+use std::time::Duration;
+
 /// Format bytes into human-readable size
 pub fn format_size(bytes: u64) -> String {
     const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
@@ -21,6 +25,74 @@ pub fn format_size(bytes: u64) -> String {
     }
 }
 
+/// Parse a size such as `500M`, `1.5G`, `20GB` or `4096` (bytes)
+///
+/// Units are binary, matching [`format_size`]: `1K` is 1024 bytes.
+pub fn parse_size(text: &str) -> Result<u64, String> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("invalid size '{}' (expected e.g. 500M or 20G)", text))?;
+
+    let exponent = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 0,
+        "K" | "KB" | "KIB" => 1,
+        "M" | "MB" | "MIB" => 2,
+        "G" | "GB" | "GIB" => 3,
+        "T" | "TB" | "TIB" => 4,
+        _ => {
+            return Err(format!(
+                "unknown unit '{}' in '{}' (use B, K, M, G or T)",
+                unit, text
+            ))
+        }
+    };
+    Ok((number * 1024_f64.powi(exponent)) as u64)
+}
+
+/// Parse an age such as `30d`, `12h`, `2w` or `90m`; a bare number is days
+pub fn parse_duration(text: &str) -> Result<Duration, String> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid age '{}' (expected e.g. 30d, 12h or 2w)", text))?;
+
+    let seconds = match unit.trim() {
+        "s" => 1,
+        "m" | "min" => 60,
+        "h" => 3_600,
+        "" | "d" => 86_400,
+        "w" => 7 * 86_400,
+        other => {
+            return Err(format!(
+                "unknown unit '{}' in '{}' (use s, m, h, d or w)",
+                other, text
+            ))
+        }
+    };
+    Ok(Duration::from_secs(number.saturating_mul(seconds)))
+}
+
+/// Format a duration as a rough human age such as `3 days` or `5 hours`
+pub fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    let (value, unit) = match secs {
+        0..=59 => return "less than a minute".to_string(),
+        60..=3_599 => (secs / 60, "minute"),
+        3_600..=86_399 => (secs / 3_600, "hour"),
+        _ => (secs / 86_400, "day"),
+    };
+    format!("{} {}{}", value, unit, if value == 1 { "" } else { "s" })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -35,4 +107,29 @@ mod tests {
         assert_eq!(format_size(1024 * 1024 * 1024), "1.00 GB");
         assert_eq!(format_size(1024_u64.pow(4)), "1.00 TB");
     }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("4096"), Ok(4096));
+        assert_eq!(parse_size("1K"), Ok(1024));
+        assert_eq!(parse_size("500M"), Ok(500 * 1024 * 1024));
+        assert_eq!(parse_size("1.5GB"), Ok(1536 * 1024 * 1024));
+        assert_eq!(parse_size("2 tb"), Ok(2 * 1024_u64.pow(4)));
+        assert!(parse_size("G").is_err());
+        assert!(parse_size("3X").is_err());
+    }
+
+    #[test]
+    fn test_parse_duration_and_format_age() {
+        assert_eq!(parse_duration("30d"), Ok(Duration::from_secs(30 * 86_400)));
+        assert_eq!(parse_duration("30"), Ok(Duration::from_secs(30 * 86_400)));
+        assert_eq!(parse_duration("12h"), Ok(Duration::from_secs(12 * 3_600)));
+        assert_eq!(parse_duration("2w"), Ok(Duration::from_secs(14 * 86_400)));
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("3y").is_err());
+
+        assert_eq!(format_age(Duration::from_secs(5)), "less than a minute");
+        assert_eq!(format_age(Duration::from_secs(3_600)), "1 hour");
+        assert_eq!(format_age(Duration::from_secs(45 * 86_400)), "45 days");
+    }
 }
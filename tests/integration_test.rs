@@ -1,5 +1,10 @@
+use clean_files::detectors::DetectorRegistry;
+use clean_files::filter::Filter;
+use clean_files::report::Report;
+use clean_files::{CleanTarget, Cleaner, ScanResult, Scanner};
 use std::fs;
 use std::path::Path;
+use std::time::{Duration, SystemTime};
 use tempfile::TempDir;
 
 // Helper function to create a test project structure
@@ -27,7 +32,7 @@ fn create_test_structure(base: &Path) {
     fs::create_dir(&rust_target).unwrap();
     let debug = rust_target.join("debug");
     fs::create_dir(&debug).unwrap();
-    fs::write(debug.join("test.exe"), &[0u8; 1000]).unwrap();
+    fs::write(debug.join("test.exe"), [0u8; 1000]).unwrap();
 
     // Create Python project
     let python_project = base.join("python-project");
@@ -35,7 +40,7 @@ fn create_test_structure(base: &Path) {
     fs::write(python_project.join("main.py"), "print('hello')").unwrap();
     let pycache = python_project.join("__pycache__");
     fs::create_dir(&pycache).unwrap();
-    fs::write(pycache.join("main.cpython-39.pyc"), &[0u8; 500]).unwrap();
+    fs::write(pycache.join("main.cpython-39.pyc"), [0u8; 500]).unwrap();
 
     // Create Java Maven project
     let java_project = base.join("java-project");
@@ -49,7 +54,7 @@ fn create_test_structure(base: &Path) {
     fs::create_dir(&java_target).unwrap();
     let classes = java_target.join("classes");
     fs::create_dir(&classes).unwrap();
-    fs::write(classes.join("Test.class"), &[0u8; 300]).unwrap();
+    fs::write(classes.join("Test.class"), [0u8; 300]).unwrap();
 
     // Create Gradle project
     let gradle_project = base.join("gradle-project");
@@ -57,13 +62,17 @@ fn create_test_structure(base: &Path) {
     fs::write(gradle_project.join("build.gradle"), "plugins { id 'java' }").unwrap();
     let build_dir = gradle_project.join("build");
     fs::create_dir(&build_dir).unwrap();
-    fs::write(build_dir.join("output.jar"), &[0u8; 200]).unwrap();
+    fs::write(build_dir.join("output.jar"), [0u8; 200]).unwrap();
 }
 
 fn dir_exists(path: &Path) -> bool {
     path.exists() && path.is_dir()
 }
 
+fn scan(root: &Path, target: CleanTarget) -> Vec<ScanResult> {
+    Scanner::new(target).scan(root).unwrap()
+}
+
 #[test]
 fn test_integration_node_modules() {
     let temp_dir = TempDir::new().unwrap();
@@ -72,8 +81,10 @@ fn test_integration_node_modules() {
     let node_modules = temp_dir.path().join("node-project/node_modules");
     assert!(dir_exists(&node_modules));
 
-    // Run scanner to find node_modules
-    // This would normally be done through the CLI, but we test the core logic here
+    let results = scan(temp_dir.path(), CleanTarget::NodeModules);
+    assert_eq!(results.len(), 1);
+    assert!(results[0].path.ends_with("node-project/node_modules"));
+    assert_eq!(results[0].file_count, 2);
 }
 
 #[test]
@@ -83,6 +94,10 @@ fn test_integration_rust_target() {
 
     let rust_target = temp_dir.path().join("rust-project/target");
     assert!(dir_exists(&rust_target));
+
+    let results = scan(temp_dir.path(), CleanTarget::RustTarget);
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].size, 1000);
 }
 
 #[test]
@@ -101,6 +116,10 @@ fn test_integration_java_target() {
 
     let java_target = temp_dir.path().join("java-project/target");
     assert!(dir_exists(&java_target));
+
+    // The Maven target and the Gradle build directory are both Java targets
+    let results = scan(temp_dir.path(), CleanTarget::JavaTarget);
+    assert_eq!(results.len(), 2);
 }
 
 #[test]
@@ -127,6 +146,548 @@ fn test_integration_full_scan() {
     ));
     assert!(dir_exists(&temp_dir.path().join("java-project/target")));
     assert!(dir_exists(&temp_dir.path().join("gradle-project/build")));
+
+    let results = scan(temp_dir.path(), CleanTarget::All);
+    assert_eq!(results.len(), 5);
+}
+
+#[test]
+fn test_library_scan_and_clean() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_structure(temp_dir.path());
+
+    let results = scan(temp_dir.path(), CleanTarget::All);
+    let expected_size: u64 = results.iter().map(|r| r.size).sum();
+
+    let stats = Cleaner::new(false, false)
+        .clean_without_confirmation(results)
+        .unwrap();
+
+    assert_eq!(stats.total_dirs, 5);
+    assert_eq!(stats.total_size, expected_size);
+    assert_eq!(stats.failed_dirs, 0);
+    assert!(!dir_exists(
+        &temp_dir.path().join("node-project/node_modules")
+    ));
+    assert!(!dir_exists(&temp_dir.path().join("gradle-project/build")));
+    // Marker files are left alone
+    assert!(temp_dir.path().join("rust-project/Cargo.toml").exists());
+}
+
+#[test]
+fn test_dry_run_deletes_nothing() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_structure(temp_dir.path());
+
+    let results = scan(temp_dir.path(), CleanTarget::All);
+    let paths: Vec<_> = results.iter().map(|r| r.path.clone()).collect();
+    let stats = Cleaner::new(true, false).clean(results).unwrap();
+
+    assert_eq!(stats.total_dirs, 5);
+    assert_eq!(stats.failed_dirs, 0);
+    for path in paths {
+        assert!(dir_exists(&path), "{} was deleted", path.display());
+    }
+}
+
+#[test]
+fn test_deletion_removes_the_whole_tree() {
+    let temp_dir = TempDir::new().unwrap();
+    let project = temp_dir.path().join("app");
+    fs::create_dir_all(&project).unwrap();
+    fs::write(project.join("package.json"), "{}").unwrap();
+    let node_modules = project.join("node_modules");
+    let mut nested = node_modules.clone();
+    for depth in 0..10 {
+        nested = nested.join(format!("level{}", depth));
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(nested.join("file.bin"), vec![0u8; 1000]).unwrap();
+    }
+
+    let results = scan(temp_dir.path(), CleanTarget::NodeModules);
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].file_count, 10);
+    let stats = Cleaner::new(false, false)
+        .clean_without_confirmation(results)
+        .unwrap();
+
+    assert_eq!(stats.total_dirs, 1);
+    assert_eq!(stats.total_files, 10);
+    assert_eq!(stats.total_size, 10_000);
+    assert!(!node_modules.exists());
+    // Only the manifest is left in the project
+    let left: Vec<_> = fs::read_dir(&project)
+        .unwrap()
+        .map(|e| e.unwrap().file_name())
+        .collect();
+    assert_eq!(left, ["package.json"]);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_deletion_does_not_follow_symlinks() {
+    let temp_dir = TempDir::new().unwrap();
+    let shared = temp_dir.path().join("shared-lib");
+    fs::create_dir_all(&shared).unwrap();
+    fs::write(shared.join("index.js"), "module.exports = {};").unwrap();
+
+    // A linked package, as `npm link` leaves behind
+    let project = temp_dir.path().join("app");
+    fs::create_dir_all(project.join("node_modules")).unwrap();
+    fs::write(project.join("package.json"), "{}").unwrap();
+    std::os::unix::fs::symlink(&shared, project.join("node_modules/shared-lib")).unwrap();
+
+    let stats = Cleaner::new(false, false)
+        .clean_without_confirmation(scan(&project, CleanTarget::NodeModules))
+        .unwrap();
+
+    assert_eq!(stats.total_dirs, 1);
+    assert!(!project.join("node_modules").exists());
+    assert!(shared.join("index.js").exists());
+}
+
+#[test]
+fn test_tampered_plan_deletes_nothing() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    let important = root.join("important");
+    fs::create_dir_all(important.join("src")).unwrap();
+    fs::write(important.join("src/main.c"), "int main(void) {}\n").unwrap();
+    fs::create_dir_all(root.join("app/node_modules")).unwrap();
+    fs::write(root.join("app/package.json"), "{}").unwrap();
+
+    let entry = |path: &Path, target: &str, detector: &str| {
+        format!(
+            r#"{{"path": "{}", "target_type": "{}", "detector": {}, "size": 0, "file_count": 0, "status": "pending"}}"#,
+            path.display(),
+            target,
+            detector
+        )
+    };
+    let node_modules = root.join("app/node_modules");
+    let targets = [
+        entry(&important, "python", "null"),
+        entry(&important, "python", r#""python-cache""#),
+        entry(&important, "custom", r#""not-registered""#),
+        entry(&node_modules, "rust", r#""rust-target""#),
+        entry(&node_modules, "python", r#""node-modules""#),
+    ];
+    let plan = root.join("plan.json");
+    fs::write(
+        &plan,
+        format!(
+            r#"{{"version": 1, "dry_run": false, "cancelled": false, "interrupted": false, "targets": [{}], "stats": {{}}}}"#,
+            targets.join(", ")
+        ),
+    )
+    .unwrap();
+
+    let report = Report::load(&plan).unwrap();
+    let (accepted, refused) = report.checked_remaining(&DetectorRegistry::builtin());
+    assert!(accepted.is_empty());
+    assert_eq!(refused.len(), 5);
+    let stats = Cleaner::new(false, false)
+        .clean_without_confirmation(accepted)
+        .unwrap();
+    assert_eq!(stats.total_dirs, 0);
+    assert!(important.join("src/main.c").exists());
+    assert!(dir_exists(&node_modules));
+
+    // An untouched entry is still accepted
+    let report = Report::from_scan(&scan(root, CleanTarget::NodeModules));
+    let (accepted, refused) = report.checked_remaining(&DetectorRegistry::builtin());
+    assert_eq!(accepted.len(), 1);
+    assert!(refused.is_empty());
+}
+
+#[test]
+fn test_plan_entries_honour_later_excludes() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    for app in ["app", "legacy"] {
+        fs::create_dir_all(root.join(app).join("node_modules")).unwrap();
+        fs::write(root.join(app).join("package.json"), "{}").unwrap();
+    }
+    let plan = root.join("plan.json");
+    Report::from_scan(&scan(root, CleanTarget::NodeModules))
+        .save(&plan)
+        .unwrap();
+
+    // Written after the plan, before it is cleaned
+    fs::write(root.join(".cleanfilesignore"), "legacy/\n").unwrap();
+
+    let scanner = Scanner::new(CleanTarget::NodeModules).with_excludes(vec!["app".to_string()]);
+    let (accepted, _) = Report::load(&plan)
+        .unwrap()
+        .checked_remaining(&DetectorRegistry::builtin());
+    assert_eq!(accepted.len(), 2);
+    let rules: Vec<_> = accepted
+        .iter()
+        .map(|r| scanner.excluded_by(root, &r.path).unwrap())
+        .collect();
+    assert!(rules.iter().all(|rule| rule.is_some()));
+
+    let kept: Vec<_> = accepted
+        .into_iter()
+        .filter(|r| {
+            Scanner::new(CleanTarget::NodeModules)
+                .excluded_by(root, &r.path)
+                .unwrap()
+                .is_none()
+        })
+        .collect();
+    assert_eq!(kept.len(), 1);
+    assert!(kept[0].path.ends_with("app/node_modules"));
+
+    Cleaner::new(false, false)
+        .clean_without_confirmation(kept)
+        .unwrap();
+    assert!(!root.join("app/node_modules").exists());
+    assert!(dir_exists(&root.join("legacy/node_modules")));
+}
+
+#[test]
+fn test_age_filter_leaves_active_projects() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_structure(temp_dir.path());
+    let month = Duration::from_secs(30 * 86_400);
+
+    // Everything was just created, so nothing is old enough
+    let results = scan(temp_dir.path(), CleanTarget::All);
+    assert!(results.iter().all(|r| r.last_activity.is_some()));
+    let stats = Cleaner::new(false, false)
+        .with_filter(Filter::new().with_older_than(month))
+        .clean_without_confirmation(results.clone())
+        .unwrap();
+    assert_eq!(stats.total_dirs, 0);
+    assert_eq!(stats.excluded_dirs, 5);
+    assert!(dir_exists(
+        &temp_dir.path().join("node-project/node_modules")
+    ));
+
+    // Seen from two months ahead, all of it is
+    let later = SystemTime::now() + 2 * month;
+    let stats = Cleaner::new(true, false)
+        .with_filter(Filter::new().with_older_than(month).with_now(later))
+        .clean(results)
+        .unwrap();
+    assert_eq!(stats.total_dirs, 5);
+    assert_eq!(stats.excluded_dirs, 0);
+}
+
+#[test]
+fn test_keep_markers_protect_targets() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_structure(temp_dir.path());
+    let rust_project = temp_dir.path().join("rust-project");
+    let mut manifest = fs::read_to_string(rust_project.join("Cargo.toml")).unwrap();
+    manifest.push_str("\n\n[package.metadata.clean-files]\nkeep = true\n");
+    fs::write(rust_project.join("Cargo.toml"), manifest).unwrap();
+    fs::write(temp_dir.path().join("python-project/.clean-files-keep"), "").unwrap();
+
+    // Still found, so summaries can show them, but never cleaned
+    let results = scan(temp_dir.path(), CleanTarget::All);
+    assert_eq!(results.len(), 5);
+    assert_eq!(results.iter().filter(|r| r.kept_by.is_some()).count(), 2);
+
+    let stats = Cleaner::new(false, false)
+        .clean_without_confirmation(results)
+        .unwrap();
+    assert_eq!(stats.total_dirs, 3);
+    assert_eq!(stats.excluded_dirs, 2);
+    assert!(dir_exists(&rust_project.join("target")));
+    assert!(dir_exists(
+        &temp_dir.path().join("python-project/__pycache__")
+    ));
+    assert!(!dir_exists(
+        &temp_dir.path().join("node-project/node_modules")
+    ));
+}
+
+/// What cargo leaves in every target directory it builds into
+fn fake_cargo_target(dir: &Path) {
+    fs::create_dir_all(dir.join("debug")).unwrap();
+    fs::write(
+        dir.join("CACHEDIR.TAG"),
+        "Signature: 8a477f597d28d172789f06886806bc55\n# This file is a cache directory tag created by cargo.\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.join(".rustc_info.json"),
+        r#"{"rustc_fingerprint":1,"outputs":{}}"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_keep_markers_follow_the_owning_project() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path().canonicalize().unwrap();
+
+    // A firmware package building into a shared directory elsewhere
+    let firmware = root.join("firmware");
+    fs::create_dir_all(firmware.join(".cargo")).unwrap();
+    fs::write(firmware.join("Cargo.toml"), "[package]\nname = \"fw\"\n").unwrap();
+    fs::write(
+        firmware.join(".cargo/config.toml"),
+        "[build]\ntarget-dir = \"../build-cache/fw\"\n",
+    )
+    .unwrap();
+    fs::write(firmware.join(".clean-files-keep"), "").unwrap();
+    let shared = root.join("build-cache/fw");
+    fake_cargo_target(&shared);
+
+    // A workspace one of whose members opted out
+    let workspace = root.join("ws");
+    fs::create_dir_all(workspace.join("crates/board")).unwrap();
+    fs::write(
+        workspace.join("Cargo.toml"),
+        "[workspace]\nmembers = [\"crates/*\"]\n",
+    )
+    .unwrap();
+    fs::write(
+        workspace.join("crates/board/Cargo.toml"),
+        "[package]\nname = \"board\"\n\n[package.metadata.clean-files]\nkeep = true\n",
+    )
+    .unwrap();
+    fake_cargo_target(&workspace.join("target"));
+
+    let results = scan(&root, CleanTarget::RustTarget);
+    assert_eq!(results.len(), 2);
+    assert!(results.iter().all(|r| r.kept_by.is_some()));
+
+    // Even when the marker was not seen at scan time
+    let results = results
+        .into_iter()
+        .map(|mut r| {
+            r.kept_by = None;
+            r
+        })
+        .collect();
+    let stats = Cleaner::new(false, false)
+        .clean_without_confirmation(results)
+        .unwrap();
+    assert_eq!(stats.total_dirs, 0);
+    assert!(dir_exists(&shared));
+    assert!(dir_exists(&workspace.join("target")));
+}
+
+#[test]
+fn test_shared_target_outside_root_needs_opt_in() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path().canonicalize().unwrap();
+    let app = root.join("proj/app");
+    fs::create_dir_all(app.join(".cargo")).unwrap();
+    fs::write(app.join("Cargo.toml"), "[package]\nname = \"app\"\n").unwrap();
+    fs::write(
+        app.join(".cargo/config.toml"),
+        "[build]\ntarget-dir = \"../../cargo-target\"\n",
+    )
+    .unwrap();
+    fake_cargo_target(&root.join("cargo-target"));
+
+    // Others may build into it too, so scanning one project leaves it alone
+    let proj = root.join("proj");
+    assert!(scan(&proj, CleanTarget::RustTarget).is_empty());
+
+    let results = Scanner::new(CleanTarget::RustTarget)
+        .with_outside_root(true)
+        .scan(&proj)
+        .unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].path, root.join("cargo-target"));
+    assert_eq!(results[0].project.as_deref(), Some(app.as_path()));
+}
+
+#[test]
+fn test_js_workspaces_and_yarn_berry() {
+    let temp_dir = TempDir::new().unwrap();
+    let mono = temp_dir.path().join("mono");
+    fs::create_dir_all(mono.join("node_modules/.pnpm")).unwrap();
+    fs::create_dir_all(mono.join("packages/ui/node_modules")).unwrap();
+    fs::write(mono.join("package.json"), "{}").unwrap();
+    fs::write(mono.join("packages/ui/package.json"), "{}").unwrap();
+    fs::write(
+        mono.join("pnpm-workspace.yaml"),
+        "packages:\n  - packages/*\n",
+    )
+    .unwrap();
+
+    let berry = temp_dir.path().join("berry");
+    fs::create_dir_all(berry.join(".yarn/cache")).unwrap();
+    fs::create_dir_all(berry.join(".yarn/releases")).unwrap();
+    fs::write(berry.join("package.json"), "{}").unwrap();
+    fs::write(berry.join(".yarnrc.yml"), "").unwrap();
+
+    let results = scan(temp_dir.path(), CleanTarget::NodeModules);
+    let found: Vec<_> = results
+        .iter()
+        .map(|r| {
+            (
+                r.path.strip_prefix(temp_dir.path()).unwrap(),
+                r.workspace.is_some(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        found,
+        [
+            (Path::new("berry/.yarn/cache"), false),
+            (Path::new("mono/node_modules"), true),
+            (Path::new("mono/packages/ui/node_modules"), true),
+        ]
+    );
+    assert_eq!(results[1].workspace.as_deref(), Some(mono.as_path()));
+}
+
+#[test]
+fn test_framework_caches() {
+    let temp_dir = TempDir::new().unwrap();
+    let app = temp_dir.path().join("app");
+    for dir in ["node_modules", ".next/cache", ".turbo", "src/.next"] {
+        fs::create_dir_all(app.join(dir)).unwrap();
+    }
+    fs::write(
+        app.join("package.json"),
+        r#"{"dependencies": {"next": "14"}}"#,
+    )
+    .unwrap();
+    fs::write(app.join(".next/cache/build"), [0u8; 64]).unwrap();
+
+    // `.turbo` without Turborepo and a stray `.next` are left alone
+    let results = scan(temp_dir.path(), CleanTarget::FrameworkCache);
+    assert_eq!(results.len(), 1);
+    assert!(results[0].path.ends_with("app/.next"));
+    assert_eq!(scan(temp_dir.path(), CleanTarget::NodeModules).len(), 1);
+
+    let stats = Cleaner::new(false, false)
+        .clean_without_confirmation(results)
+        .unwrap();
+    assert_eq!((stats.framework_caches, stats.node_modules), (1, 0));
+    assert!(dir_exists(&app.join("node_modules")));
+}
+
+#[test]
+fn test_dotnet_solution() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    for dir in [
+        "src/Api/bin/Debug",
+        "src/Api/obj",
+        "src/Lib/bin",
+        "tools/bin",
+    ] {
+        fs::create_dir_all(root.join(dir)).unwrap();
+    }
+    fs::write(root.join("src/Api/Api.csproj"), "").unwrap();
+    fs::write(root.join("src/Api/obj/project.assets.json"), "{}").unwrap();
+    // Never restored, so its bin may hold something else
+    fs::write(root.join("src/Lib/Lib.fsproj"), "").unwrap();
+    fs::write(
+        root.join("App.sln"),
+        "Project(\"{9A19103F-16F7-4668-BE54-9A1E7A4F7556}\") = \"Api\", \"src\\Api\\Api.csproj\", \"{A}\"\nEndProject\n",
+    )
+    .unwrap();
+
+    let results = scan(root, CleanTarget::DotnetBuild);
+    let found: Vec<_> = results
+        .iter()
+        .map(|r| r.path.strip_prefix(root).unwrap())
+        .collect();
+    assert_eq!(found, [Path::new("src/Api/bin"), Path::new("src/Api/obj")]);
+    assert!(results.iter().all(|r| r.workspace.as_deref() == Some(root)));
+}
+
+#[test]
+fn test_jvm_build_tools() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    for dir in [
+        "scala/target",
+        "scala/project/target",
+        "scala/.bloop",
+        "clj/.cpcache",
+        "clj/target",
+        "lein/target",
+        "kt/.gradle",
+        "kt/.kotlin",
+        "kt/build",
+        "notes/target",
+    ] {
+        fs::create_dir_all(root.join(dir)).unwrap();
+    }
+    fs::write(root.join("scala/build.sbt"), "").unwrap();
+    fs::write(root.join("clj/deps.edn"), "{}").unwrap();
+    fs::write(root.join("lein/project.clj"), "(defproject lein \"0.1.0\")").unwrap();
+    fs::write(root.join("kt/settings.gradle.kts"), "").unwrap();
+    fs::write(root.join("kt/build.gradle.kts"), "").unwrap();
+
+    let results = scan(root, CleanTarget::JavaTarget);
+    let mut found: Vec<_> = results
+        .iter()
+        .map(|r| r.path.strip_prefix(root).unwrap())
+        .collect();
+    found.sort();
+    assert_eq!(
+        found,
+        [
+            Path::new("clj/.cpcache"),
+            Path::new("clj/target"),
+            Path::new("kt/.gradle"),
+            Path::new("kt/.kotlin"),
+            Path::new("kt/build"),
+            Path::new("lein/target"),
+            Path::new("scala/.bloop"),
+            Path::new("scala/project/target"),
+            Path::new("scala/target"),
+        ]
+    );
+}
+
+#[test]
+fn test_native_build_trees() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    let source = root.join("engine");
+    fs::create_dir_all(source.join("src")).unwrap();
+    fs::create_dir_all(root.join("out-debug/CMakeFiles")).unwrap();
+    fs::write(source.join("CMakeLists.txt"), "project(engine)\n").unwrap();
+    let cache = format!("CMAKE_HOME_DIRECTORY:INTERNAL={}\n", source.display());
+    fs::write(root.join("out-debug/CMakeCache.txt"), &cache).unwrap();
+    // An in-source build is never picked up
+    fs::write(source.join("CMakeCache.txt"), &cache).unwrap();
+
+    let results = scan(root, CleanTarget::NativeBuild);
+    assert_eq!(results.len(), 1);
+    assert!(results[0].path.ends_with("out-debug"));
+
+    fs::remove_file(source.join("CMakeLists.txt")).unwrap();
+    assert!(scan(root, CleanTarget::NativeBuild).is_empty());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_bazel_output_base_outside_root_needs_opt_in() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path().canonicalize().unwrap();
+    let workspace = root.join("repo");
+    let base = root.join("cache/bazel/_bazel_me/0123abcd");
+    let out = base.join("execroot/_main/bazel-out");
+    fs::create_dir_all(&workspace).unwrap();
+    fs::create_dir_all(&out).unwrap();
+    fs::write(workspace.join("MODULE.bazel"), "").unwrap();
+    fs::write(base.join("DO_NOT_BUILD_HERE"), workspace.to_str().unwrap()).unwrap();
+    std::os::unix::fs::symlink(&out, workspace.join("bazel-out")).unwrap();
+
+    // Scanning the checkout must not expunge the output base
+    assert!(scan(&workspace, CleanTarget::NativeBuild).is_empty());
+
+    let results = Scanner::new(CleanTarget::NativeBuild)
+        .with_outside_root(true)
+        .scan(&workspace)
+        .unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].path, base);
 }
 
 #[test]